@@ -0,0 +1,20 @@
+use ndarray::Array3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// A bootstrap replicate of the aggregate: as many occupied voxels as it
+/// has, drawn from its voxels with replacement. Voxels drawn more than once
+/// are occupied once, so a replicate keeps about 63% of the distinct voxels.
+pub fn resample(occupancy: &Array3<bool>, seed: u64) -> Array3<bool> {
+    let occupied: Vec<(usize, usize, usize)> = occupancy
+        .indexed_iter()
+        .filter(|(_, &v)| v)
+        .map(|(index, _)| index)
+        .collect();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut replicate = Array3::from_elem(occupancy.dim(), false);
+    for _ in 0..occupied.len() {
+        replicate[occupied[rng.gen_range(0..occupied.len())]] = true;
+    }
+    replicate
+}
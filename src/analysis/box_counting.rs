@@ -0,0 +1,250 @@
+use ndarray::{Array3, Axis};
+
+use super::{Estimate, FitOptions};
+
+/// Classical box counting: the frame is covered with a grid of cubic boxes
+/// of 1, 2, 4, ... voxels and the occupied boxes are counted at every size.
+/// Sizes go up to half the shortest edge of the frame, so that every count
+/// comes from at least two boxes along each axis; boxes at the far edges
+/// may be cut short. A frame one voxel thick along some axis is counted
+/// with square boxes in its plane.
+pub fn estimate(occupancy: &Array3<bool>, options: &FitOptions) -> Estimate {
+    let occupied_voxels = occupancy.iter().filter(|&&v| v).count();
+    let pyramid = box_masses(occupancy);
+    let (log_counts, lacunarities): (Vec<_>, Vec<_>) = pyramid
+        .iter()
+        .map(|masses| {
+            let (count, lacunarity) = count_boxes(masses);
+            ((count as f64).log2(), lacunarity)
+        })
+        .unzip();
+    let scales = box_sizes(&pyramid);
+
+    Estimate::from_fit(
+        &scales,
+        &log_counts,
+        &lacunarities,
+        occupied_voxels,
+        options,
+    )
+}
+
+/// Box-counting dimension of the projection of the aggregate onto the plane
+/// across each axis.
+pub fn projected_dimensions(occupancy: &Array3<bool>, options: &FitOptions) -> [f64; 3] {
+    std::array::from_fn(|axis| {
+        let projection = occupancy
+            .map_axis(Axis(axis), |line| line.iter().any(|&v| v))
+            .insert_axis(Axis(axis));
+        estimate(&projection, options).fractal_dimension
+    })
+}
+
+/// Generalized (Rényi) dimensions D_q of the box measure, one per `q`, from
+/// the slope of the partition sum of the box probabilities p^q against the
+/// box size. D_1 is fitted on their entropy instead. Every D_q is fitted on
+/// the box sizes that `options` picks for the box-counting dimension.
+pub fn generalized_dimensions(
+    occupancy: &Array3<bool>,
+    qs: &[f64],
+    options: &FitOptions,
+) -> Vec<f64> {
+    let pyramid = box_masses(occupancy);
+    let total = occupancy.iter().filter(|&&v| v).count() as f64;
+    let slope = fitted_slope(&pyramid, options);
+    qs.iter()
+        .map(|&q| {
+            let sums: Vec<f64> = pyramid
+                .iter()
+                .map(|masses| {
+                    let probabilities =
+                        masses.iter().filter(|&&m| m > 0).map(|&m| m as f64 / total);
+                    if q == 1.0 {
+                        -probabilities.map(|p| p * p.log2()).sum::<f64>()
+                    } else {
+                        probabilities.map(|p| p.powf(q)).sum::<f64>().log2()
+                    }
+                })
+                .collect();
+            if q == 1.0 {
+                slope(&sums)
+            } else {
+                slope(&sums) / (1.0 - q)
+            }
+        })
+        .collect()
+}
+
+/// Multifractal singularity spectrum after Chhabra and Jensen: the
+/// singularity strength alpha and its dimension f(alpha) for every `q`, from
+/// the box measures weighted by p^q. The points are returned as rows of q,
+/// alpha and f(alpha), both fitted like the generalized dimensions.
+pub fn singularity_spectrum(
+    occupancy: &Array3<bool>,
+    qs: &[f64],
+    options: &FitOptions,
+) -> Vec<Vec<f64>> {
+    let pyramid = box_masses(occupancy);
+    let total = occupancy.iter().filter(|&&v| v).count() as f64;
+    let slope = fitted_slope(&pyramid, options);
+    qs.iter()
+        .map(|&q| {
+            let (alpha_sums, f_sums): (Vec<f64>, Vec<f64>) = pyramid
+                .iter()
+                .map(|masses| {
+                    let probabilities: Vec<f64> = masses
+                        .iter()
+                        .filter(|&&m| m > 0)
+                        .map(|&m| m as f64 / total)
+                        .collect();
+                    let partition_sum: f64 = probabilities.iter().map(|p| p.powf(q)).sum();
+                    probabilities
+                        .iter()
+                        .map(|&p| {
+                            let weight = p.powf(q) / partition_sum;
+                            (-weight * p.log2(), -weight * weight.log2())
+                        })
+                        .fold((0.0, 0.0), |(a, f), (da, df)| (a + da, f + df))
+                })
+                .unzip();
+            vec![q, slope(&alpha_sums), slope(&f_sums)]
+        })
+        .collect()
+}
+
+/// Edges of the boxes of every level of `pyramid`, coarsest first; the
+/// finest boxes are single voxels.
+fn box_sizes(pyramid: &[Array3<u32>]) -> Vec<f64> {
+    (0..pyramid.len())
+        .rev()
+        .map(|level| 2f64.powi(level as i32))
+        .collect()
+}
+
+/// The slope of a series over the levels of `pyramid`, fitted like the
+/// box-counting dimension: on the box sizes `options` picks, narrowed down
+/// on the box counts, and with its regression.
+fn fitted_slope(pyramid: &[Array3<u32>], options: &FitOptions) -> impl Fn(&[f64]) -> f64 {
+    let log_counts: Vec<f64> = pyramid
+        .iter()
+        .map(|masses| (masses.iter().filter(|&&m| m > 0).count() as f64).log2())
+        .collect();
+    let fitted = options.fitted(&box_sizes(pyramid), &log_counts);
+    let regression = options.regression;
+    move |series| regression.fit(&series[fitted.clone()]).0
+}
+
+/// Masses of the boxes at every fitted box size, coarsest first, so that the
+/// slope of the box counts against the level is the dimension.
+fn box_masses(occupancy: &Array3<bool>) -> Vec<Array3<u32>> {
+    // Flat axes stay one box thick
+    let shortest_edge = occupancy
+        .shape()
+        .iter()
+        .copied()
+        .filter(|&n| n > 1)
+        .min()
+        .unwrap_or(0);
+    let mut pyramid = vec![occupancy.mapv(u32::from)];
+    let mut size = 2;
+    while size <= shortest_edge / 2 {
+        pyramid.push(coarsen(pyramid.last().unwrap()));
+        size *= 2;
+    }
+    pyramid.reverse();
+
+    pyramid
+}
+
+/// Number of occupied boxes and the lacunarity of their masses.
+fn count_boxes(masses: &Array3<u32>) -> (usize, f64) {
+    let (mut count, mut sum, mut sum_squares) = (0, 0.0, 0.0);
+    for &m in masses.iter().filter(|&&m| m > 0) {
+        count += 1;
+        sum += m as f64;
+        sum_squares += (m as f64).powi(2);
+    }
+
+    (count, count as f64 * sum_squares / (sum * sum) - 1.0)
+}
+
+/// Merges every 2x2x2 block of boxes into a box of twice the size, or every
+/// 2x2 block along a flat axis.
+fn coarsen(masses: &Array3<u32>) -> Array3<u32> {
+    let shape = masses.shape();
+    let mut coarse = Array3::zeros((
+        shape[0].div_ceil(2),
+        shape[1].div_ceil(2),
+        shape[2].div_ceil(2),
+    ));
+    for ((x, y, z), &m) in masses.indexed_iter() {
+        coarse[[x / 2, y / 2, z / 2]] += m;
+    }
+
+    coarse
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    fn dimension(occupancy: &Array3<bool>) -> f64 {
+        estimate(occupancy, &FitOptions::default()).fractal_dimension
+    }
+
+    #[test]
+    fn lines_planes_and_cubes() {
+        let mut line = Array3::from_elem((64, 64, 64), false);
+        line.slice_mut(s![.., 32, 32]).fill(true);
+        assert!((dimension(&line) - 1.0).abs() < 0.01);
+
+        let mut plane = Array3::from_elem((64, 64, 64), false);
+        plane.slice_mut(s![.., .., 32]).fill(true);
+        assert!((dimension(&plane) - 2.0).abs() < 0.01);
+
+        let cube = Array3::from_elem((64, 64, 64), true);
+        let estimate = estimate(&cube, &FitOptions::default());
+        assert!((estimate.fractal_dimension - 3.0).abs() < 0.01);
+        // Every box of a solid cube holds the same mass
+        assert!(estimate.lacunarity.abs() < 1e-12);
+    }
+
+    #[test]
+    fn generalized_dimensions_follow_the_fit_window() {
+        // A small patch of plane and a long line: the slope changes with scale
+        let mut occupancy = Array3::from_elem((32, 32, 32), false);
+        occupancy.slice_mut(s![..8, ..8, 0]).fill(true);
+        occupancy.slice_mut(s![.., 16, 16]).fill(true);
+        let options = FitOptions {
+            min_scale: Some(4.0),
+            ..Default::default()
+        };
+
+        let d0 = generalized_dimensions(&occupancy, &[0.0], &options)[0];
+        let full = generalized_dimensions(&occupancy, &[0.0], &FitOptions::default())[0];
+        assert!((d0 - estimate(&occupancy, &options).fractal_dimension).abs() < 1e-12);
+        assert!((d0 - full).abs() > 0.01, "{d0} and {full}");
+    }
+
+    #[test]
+    fn singularity_spectra_follow_the_fit_window() {
+        let mut occupancy = Array3::from_elem((32, 32, 32), false);
+        occupancy.slice_mut(s![..8, ..8, 0]).fill(true);
+        occupancy.slice_mut(s![.., 16, 16]).fill(true);
+        let options = FitOptions {
+            min_scale: Some(4.0),
+            ..Default::default()
+        };
+
+        // At q = 0 every occupied box weighs the same, so f(alpha) is D0
+        let point = &singularity_spectrum(&occupancy, &[0.0], &options)[0];
+        let d0 = generalized_dimensions(&occupancy, &[0.0], &options)[0];
+        assert!(
+            (point[2] - d0).abs() < 1e-12,
+            "f = {} and D0 = {d0}",
+            point[2]
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use ndarray::{Array3, Axis};
+
+/// Chord-length distributions of the occupied phase along X, Y and Z: the
+/// number of runs of consecutive occupied voxels of every length along the
+/// lines of the frame parallel to an axis. Chords cut by the faces of the
+/// frame are counted at their visible length. The points are returned as
+/// rows of axis (0 to 2), chord length and count, shortest first.
+pub fn distributions(occupancy: &Array3<bool>) -> Vec<Vec<usize>> {
+    let mut rows = Vec::new();
+    for axis in 0..3 {
+        let mut counts = vec![0usize; occupancy.len_of(Axis(axis)) + 1];
+        for lane in occupancy.lanes(Axis(axis)) {
+            let mut run = 0;
+            for &v in lane.iter().chain([&false]) {
+                if v {
+                    run += 1;
+                } else if run > 0 {
+                    counts[run] += 1;
+                    run = 0;
+                }
+            }
+        }
+        rows.extend(
+            counts
+                .into_iter()
+                .enumerate()
+                .filter(|&(_, count)| count > 0)
+                .map(|(length, count)| vec![axis, length, count]),
+        );
+    }
+    rows
+}
@@ -0,0 +1,294 @@
+use clap::ValueEnum;
+use ndarray::{s, Array3};
+
+/// Which neighbours of a voxel it is connected to
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub enum Connectivity {
+    // Face neighbours only
+    #[value(name = "6")]
+    #[serde(rename = "6")]
+    Faces,
+    // Face and edge neighbours
+    #[value(name = "18")]
+    #[serde(rename = "18")]
+    Edges,
+    // Face, edge and corner neighbours
+    #[value(name = "26")]
+    #[serde(rename = "26")]
+    Corners,
+}
+
+impl Connectivity {
+    /// Offsets of the connected neighbours of a voxel
+    fn offsets(self) -> Vec<[isize; 3]> {
+        let mut offsets = Vec::new();
+        for dx in -1..=1isize {
+            for dy in -1..=1isize {
+                for dz in -1..=1isize {
+                    let distance = dx.abs() + dy.abs() + dz.abs();
+                    let connected = match self {
+                        Connectivity::Faces => distance == 1,
+                        Connectivity::Edges => distance == 1 || distance == 2,
+                        Connectivity::Corners => distance > 0,
+                    };
+                    if connected {
+                        offsets.push([dx, dy, dz]);
+                    }
+                }
+            }
+        }
+        offsets
+    }
+}
+
+/// Labels the connected clusters of occupied voxels from 1, largest first;
+/// empty voxels are 0. Returns the labels and the size of every cluster.
+pub fn label(occupancy: &Array3<bool>, connectivity: Connectivity) -> (Array3<u32>, Vec<usize>) {
+    let shape = occupancy.shape();
+    let offsets = connectivity.offsets();
+    let mut labels = Array3::<u32>::zeros(occupancy.dim());
+    let mut sizes = Vec::new();
+    let mut stack = Vec::new();
+    for ((x, y, z), &occupied) in occupancy.indexed_iter() {
+        if !occupied || labels[[x, y, z]] != 0 {
+            continue;
+        }
+        sizes.push(0);
+        let label = sizes.len() as u32;
+        labels[[x, y, z]] = label;
+        stack.push([x, y, z]);
+        // Flood fill the cluster
+        while let Some(voxel) = stack.pop() {
+            sizes[label as usize - 1] += 1;
+            for offset in &offsets {
+                let Some(neighbour) = neighbour(voxel, *offset, shape) else {
+                    continue;
+                };
+                if occupancy[neighbour] && labels[neighbour] == 0 {
+                    labels[neighbour] = label;
+                    stack.push(neighbour);
+                }
+            }
+        }
+    }
+
+    // Relabel by decreasing size
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i]));
+    let mut relabel = vec![0; sizes.len() + 1];
+    for (rank, &i) in order.iter().enumerate() {
+        relabel[i + 1] = rank as u32 + 1;
+    }
+    labels.mapv_inplace(|l| relabel[l as usize]);
+    let sizes = order.iter().map(|&i| sizes[i]).collect();
+
+    (labels, sizes)
+}
+
+/// The voxel at `offset` from `voxel`, if it is inside the frame.
+fn neighbour(voxel: [usize; 3], offset: [isize; 3], shape: &[usize]) -> Option<[usize; 3]> {
+    let mut neighbour = [0; 3];
+    for i in 0..3 {
+        neighbour[i] = voxel[i].checked_add_signed(offset[i])?;
+        if neighbour[i] >= shape[i] {
+            return None;
+        }
+    }
+    Some(neighbour)
+}
+
+/// Occupancy of the largest cluster alone, in the whole frame.
+pub fn largest(occupancy: &Array3<bool>, connectivity: Connectivity) -> Array3<bool> {
+    let (labels, _) = label(occupancy, connectivity);
+    labels.mapv(|l| l == 1)
+}
+
+/// Shift along X, Y and Z that, rolling the frame over its periodic
+/// boundaries, brings the largest cluster under periodic boundaries to the
+/// centre of the frame in one piece. Each cluster is unwrapped to the
+/// minimum images its voxels are reached at; along an axis it wraps all the
+/// way around, no shift can join it and it is left where it is.
+pub fn periodic_shift(occupancy: &Array3<bool>, connectivity: Connectivity) -> [usize; 3] {
+    let (x_max, y_max, z_max) = occupancy.dim();
+    let shape = [x_max, y_max, z_max];
+    let offsets = connectivity.offsets();
+    let mut seen = Array3::from_elem(occupancy.dim(), false);
+    let mut stack = Vec::new();
+    // Size and unwrapped bounding box of the largest cluster so far
+    let mut largest = (0, [0isize; 3], [0isize; 3]);
+    for ((x, y, z), &occupied) in occupancy.indexed_iter() {
+        if !occupied || seen[[x, y, z]] {
+            continue;
+        }
+        seen[[x, y, z]] = true;
+        let start = [x, y, z].map(|c| c as isize);
+        stack.push(([x, y, z], start));
+        let (mut size, mut lo, mut hi) = (0, start, start);
+        while let Some((voxel, unwrapped)) = stack.pop() {
+            size += 1;
+            for i in 0..3 {
+                lo[i] = lo[i].min(unwrapped[i]);
+                hi[i] = hi[i].max(unwrapped[i]);
+            }
+            for offset in &offsets {
+                let neighbour: [usize; 3] = std::array::from_fn(|i| {
+                    (voxel[i] as isize + offset[i]).rem_euclid(shape[i] as isize) as usize
+                });
+                if occupancy[neighbour] && !seen[neighbour] {
+                    seen[neighbour] = true;
+                    let unwrapped = std::array::from_fn(|i| unwrapped[i] + offset[i]);
+                    stack.push((neighbour, unwrapped));
+                }
+            }
+        }
+        if size > largest.0 {
+            largest = (size, lo, hi);
+        }
+    }
+
+    let (_, lo, hi) = largest;
+    std::array::from_fn(|i| {
+        let n = shape[i] as isize;
+        if hi[i] - lo[i] + 1 >= n {
+            return 0;
+        }
+        (n / 2 - (lo[i] + hi[i]).div_euclid(2)).rem_euclid(n) as usize
+    })
+}
+
+/// Number of occupied voxels with every count of connected occupied
+/// neighbours, from 0 up to the number of neighbours a voxel has.
+pub fn coordination(occupancy: &Array3<bool>, connectivity: Connectivity) -> Vec<usize> {
+    let shape = occupancy.shape();
+    let offsets = connectivity.offsets();
+    let mut counts = vec![0; offsets.len() + 1];
+    for ((x, y, z), _) in occupancy.indexed_iter().filter(|(_, &v)| v) {
+        let bonds = offsets
+            .iter()
+            .filter_map(|&offset| neighbour([x, y, z], offset, shape))
+            .filter(|&n| occupancy[n])
+            .count();
+        counts[bonds] += 1;
+    }
+    counts
+}
+
+/// Whether some cluster touches both faces of the frame across the X, Y and
+/// Z axis, i.e. percolates along it.
+pub fn spanning(occupancy: &Array3<bool>, connectivity: Connectivity) -> [bool; 3] {
+    let (labels, sizes) = label(occupancy, connectivity);
+    let shape = occupancy.shape();
+    // Faces of the frame every cluster touches, low and high per axis
+    let mut touched = vec![[[false; 2]; 3]; sizes.len()];
+    for ((x, y, z), &label) in labels.indexed_iter() {
+        if label == 0 {
+            continue;
+        }
+        for (i, c) in [x, y, z].into_iter().enumerate() {
+            touched[label as usize - 1][i][0] |= c == 0;
+            touched[label as usize - 1][i][1] |= c + 1 == shape[i];
+        }
+    }
+
+    std::array::from_fn(|i| touched.iter().any(|faces| faces[i] == [true, true]))
+}
+
+/// Occupancy of every cluster of at least `min_size` voxels, largest first,
+/// each cropped to its bounding box.
+pub fn clusters(
+    occupancy: &Array3<bool>,
+    connectivity: Connectivity,
+    min_size: usize,
+) -> Vec<Array3<bool>> {
+    let (labels, sizes) = label(occupancy, connectivity);
+    let count = sizes.iter().take_while(|&&size| size >= min_size).count();
+    let mut bounds = vec![([usize::MAX; 3], [0; 3]); count];
+    for ((x, y, z), &label) in labels.indexed_iter() {
+        if label == 0 || label as usize > count {
+            continue;
+        }
+        let (lo, hi) = &mut bounds[label as usize - 1];
+        for (i, c) in [x, y, z].into_iter().enumerate() {
+            lo[i] = lo[i].min(c);
+            hi[i] = hi[i].max(c + 1);
+        }
+    }
+
+    bounds
+        .into_iter()
+        .enumerate()
+        .map(|(i, (lo, hi))| {
+            labels
+                .slice(s![lo[0]..hi[0], lo[1]..hi[1], lo[2]..hi[2]])
+                .mapv(|l| l as usize == i + 1)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separate_blobs_are_separate_clusters() {
+        let mut occupancy = Array3::from_elem((8, 8, 8), false);
+        occupancy.slice_mut(s![1..3, 1..3, 1..3]).fill(true);
+        occupancy.slice_mut(s![5..7, 5..7, 6..7]).fill(true);
+        let (labels, sizes) = label(&occupancy, Connectivity::Corners);
+        assert_eq!(sizes, [8, 4]);
+        assert_eq!(labels[[1, 1, 1]], 1);
+        assert_eq!(labels[[6, 6, 6]], 2);
+        assert_eq!(labels[[0, 0, 0]], 0);
+    }
+
+    #[test]
+    fn corners_join_only_with_corner_connectivity() {
+        let mut occupancy = Array3::from_elem((4, 4, 4), false);
+        occupancy[[1, 1, 1]] = true;
+        occupancy[[2, 2, 2]] = true;
+        assert_eq!(label(&occupancy, Connectivity::Corners).1, [2]);
+        assert_eq!(label(&occupancy, Connectivity::Edges).1, [1, 1]);
+        assert_eq!(label(&occupancy, Connectivity::Faces).1, [1, 1]);
+    }
+
+    #[test]
+    fn clusters_across_a_periodic_face_are_joined() {
+        // A rod cut in two by the faces across X
+        let mut occupancy = Array3::from_elem((16, 16, 16), false);
+        occupancy.slice_mut(s![..3, 8, 8]).fill(true);
+        occupancy.slice_mut(s![13.., 8, 8]).fill(true);
+        assert_eq!(label(&occupancy, Connectivity::Faces).1, [3, 3]);
+
+        let shift = periodic_shift(&occupancy, Connectivity::Faces);
+        let mut rolled = Array3::from_elem((16, 16, 16), false);
+        for ((x, y, z), _) in occupancy.indexed_iter().filter(|(_, &v)| v) {
+            rolled[[
+                (x + shift[0]) % 16,
+                (y + shift[1]) % 16,
+                (z + shift[2]) % 16,
+            ]] = true;
+        }
+        let (labels, sizes) = label(&rolled, Connectivity::Faces);
+        assert_eq!(sizes, [6]);
+        // In one piece about the centre, away from the faces
+        let xs: Vec<usize> = labels
+            .indexed_iter()
+            .filter(|(_, &l)| l == 1)
+            .map(|((x, _, _), _)| x)
+            .collect();
+        assert_eq!(xs, [6, 7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn rod_spans_its_own_axis() {
+        let mut occupancy = Array3::from_elem((8, 8, 8), false);
+        occupancy.slice_mut(s![.., 2..4, 2..4]).fill(true);
+        assert_eq!(
+            spanning(&occupancy, Connectivity::Faces),
+            [true, false, false]
+        );
+        // A gap breaks it
+        occupancy.slice_mut(s![4, .., ..]).fill(false);
+        assert_eq!(spanning(&occupancy, Connectivity::Faces), [false; 3]);
+    }
+}
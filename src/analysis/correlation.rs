@@ -0,0 +1,101 @@
+use ndarray::Array3;
+use rayon::prelude::*;
+
+use super::{Estimate, FitOptions, SummedVolume};
+
+/// Correlation dimension D2 after Grassberger and Procaccia: the correlation
+/// sum C(r), the fraction of pairs of occupied voxels that are neighbours at
+/// scale r, scales as r^D2. The neighbours of a voxel are the occupied voxels
+/// of the cube of edge 2r that spans r voxels before it and r - 1 after it
+/// along every axis, the voxel itself included, so that the cube holds
+/// exactly 2r voxels of a straight line; they are counted with a summed-volume
+/// table rather than pair by pair. Only the voxels whose cube fits in the
+/// frame are counted from at a radius, so that the boundary does not cut the
+/// cubes short. Radii go from 1 voxel up to a quarter of the shortest edge of
+/// the frame, leaving out flat axes of a single voxel so that a planar frame
+/// gives its 2D dimension.
+pub fn estimate(occupancy: &Array3<bool>, options: &FitOptions) -> Estimate {
+    let occupied = occupancy.iter().filter(|&&v| v).count();
+    let (radii, log_correlations): (Vec<f64>, Vec<f64>) = integral(occupancy)
+        .into_iter()
+        .map(|point| (point[0], point[1].log2()))
+        .unzip();
+
+    Estimate::from_fit(&radii, &log_correlations, &[], occupied, options)
+}
+
+/// Points (radius, C) of the correlation sum at the radii `estimate` fits D2
+/// on, smallest first: the mean number of neighbours of the voxels whose
+/// cube fits in the frame, over the number of occupied voxels. Radii at which
+/// no cube fits are left out.
+pub fn integral(occupancy: &Array3<bool>) -> Vec<Vec<f64>> {
+    let shape = occupancy.shape();
+    let shortest_edge = shape.iter().copied().filter(|&n| n > 1).min().unwrap_or(0);
+    let table = SummedVolume::new(occupancy);
+    let occupied: Vec<_> = occupancy
+        .indexed_iter()
+        .filter(|(_, &v)| v)
+        .map(|((x, y, z), _)| [x, y, z])
+        .collect();
+    let fits = |centre: &[usize; 3], radius: usize| {
+        (0..3).all(|i| shape[i] == 1 || (centre[i] >= radius && centre[i] + radius <= shape[i]))
+    };
+
+    // Smallest radius first, so that the slope against the level is D2
+    let mut points = Vec::new();
+    let mut radius = 1;
+    while radius == 1 || radius <= shortest_edge / 4 {
+        let (centres, neighbours) = occupied
+            .par_iter()
+            .filter(|centre| fits(centre, radius))
+            .map(|&centre| (1, neighbours(&table, centre, radius)))
+            .reduce(|| (0u64, 0u64), |a, b| (a.0 + b.0, a.1 + b.1));
+        if centres > 0 {
+            let mean = neighbours as f64 / centres as f64;
+            points.push(vec![radius as f64, mean / occupied.len() as f64]);
+        }
+        radius *= 2;
+    }
+
+    points
+}
+
+/// Number of occupied voxels in the cube of edge `2 * radius` around
+/// `centre`, which lies inside the frame.
+fn neighbours(table: &SummedVolume, centre: [usize; 3], radius: usize) -> u64 {
+    let shape = table.shape();
+    let lo = std::array::from_fn(|i| centre[i].saturating_sub(radius));
+    let hi = std::array::from_fn(|i| (centre[i] + radius).min(shape[i]));
+
+    table.mass(lo, hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    #[test]
+    fn line_has_dimension_1() {
+        let mut occupancy = Array3::from_elem((64, 64, 1), false);
+        occupancy.slice_mut(s![.., 32, 0]).fill(true);
+        let estimate = estimate(&occupancy, &FitOptions::default());
+        assert!((estimate.fractal_dimension - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn plane_has_dimension_2() {
+        let mut occupancy = Array3::from_elem((64, 64, 64), false);
+        occupancy.slice_mut(s![.., .., 32]).fill(true);
+        let estimate = estimate(&occupancy, &FitOptions::default());
+        assert!((estimate.fractal_dimension - 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn solid_cube_has_dimension_3() {
+        let occupancy = Array3::from_elem((32, 32, 32), true);
+        let estimate = estimate(&occupancy, &FitOptions::default());
+        assert!((estimate.fractal_dimension - 3.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,83 @@
+use ndarray::{Array3, Axis};
+use rustfft::num_complex::Complex;
+use rustfft::{FftDirection, FftPlanner};
+
+/// In-place 3D discrete Fourier transform, one axis at a time. The inverse
+/// transform is not normalised.
+pub fn fft3(data: &mut Array3<Complex<f64>>, direction: FftDirection) {
+    let mut planner = FftPlanner::new();
+    for axis in 0..3 {
+        let fft = planner.plan_fft(data.len_of(Axis(axis)), direction);
+        let mut buffer = Vec::with_capacity(data.len_of(Axis(axis)));
+        for mut lane in data.lanes_mut(Axis(axis)) {
+            buffer.clear();
+            buffer.extend(lane.iter().copied());
+            fft.process(&mut buffer);
+            lane.iter_mut().zip(&buffer).for_each(|(x, &y)| *x = y);
+        }
+    }
+}
+
+/// Number of ordered pairs of occupied voxels at every displacement, from
+/// the inverse transform of the power spectrum of the occupancy. The frame
+/// is padded to twice its size so that displacements do not wrap around;
+/// displacement `d` along an axis of the frame of size `n` is at index `d`
+/// if it is positive and at `2 * n + d` otherwise.
+pub fn autocorrelation(occupancy: &Array3<bool>) -> Array3<f64> {
+    let (x, y, z) = occupancy.dim();
+    let mut data = Array3::<Complex<f64>>::zeros((2 * x, 2 * y, 2 * z));
+    for ((i, j, k), &v) in occupancy.indexed_iter() {
+        data[[i, j, k]] = Complex::new(f64::from(u8::from(v)), 0.0);
+    }
+    fft3(&mut data, FftDirection::Forward);
+    data.mapv_inplace(|c| Complex::new(c.norm_sqr(), 0.0));
+    fft3(&mut data, FftDirection::Inverse);
+
+    let scale = data.len() as f64;
+    // The counts are integers, up to the rounding of the transforms
+    data.mapv(|c| (c.re / scale).round())
+}
+
+/// Radially averaged structure factor S(q) = |rho(q)|^2 / N of the occupied
+/// voxels, as rows of wave number (in radians per voxel) and S. Wave vectors
+/// are binned in shells of the width of the lowest wave number of the
+/// shortest edge, up to the Nyquist wave number of that edge; a fractal
+/// aggregate gives S ~ q^-D between its size and its voxels.
+pub fn structure_factor(occupancy: &Array3<bool>) -> Vec<Vec<f64>> {
+    let (x, y, z) = occupancy.dim();
+    let shape = [x, y, z];
+    let mut data = occupancy.mapv(|v| Complex::new(f64::from(u8::from(v)), 0.0));
+    fft3(&mut data, FftDirection::Forward);
+
+    let occupied = occupancy.iter().filter(|&&v| v).count() as f64;
+    let shortest_edge = shape.iter().copied().min().unwrap_or(0);
+    let step = 2.0 * std::f64::consts::PI / shortest_edge as f64;
+    let shells = shortest_edge / 2;
+    let (mut sums, mut counts) = (vec![0.0; shells + 1], vec![0usize; shells + 1]);
+    for ((i, j, k), c) in data.indexed_iter() {
+        // Wave numbers above the Nyquist one stand for negative ones
+        let q: f64 = [i, j, k]
+            .iter()
+            .zip(&shape)
+            .map(|(&index, &n)| {
+                let wave = if index <= n / 2 {
+                    index as f64
+                } else {
+                    index as f64 - n as f64
+                };
+                (2.0 * std::f64::consts::PI * wave / n as f64).powi(2)
+            })
+            .sum::<f64>()
+            .sqrt();
+        let shell = (q / step).round() as usize;
+        if (1..=shells).contains(&shell) {
+            sums[shell] += c.norm_sqr() / occupied;
+            counts[shell] += 1;
+        }
+    }
+
+    (1..=shells)
+        .filter(|&shell| counts[shell] > 0)
+        .map(|shell| vec![shell as f64 * step, sums[shell] / counts[shell] as f64])
+        .collect()
+}
@@ -0,0 +1,192 @@
+use clap::ValueEnum;
+use fractal_analysis::get_inclination;
+
+use super::fit_statistics;
+
+/// How the line through the log-log data is fitted
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Regression {
+    // Ordinary least squares
+    #[default]
+    Ols,
+    // Weighted least squares, each point weighted by its count
+    Wls,
+    // Theil-Sen, the median of the slopes between all pairs of points,
+    // robust to outlying scales
+    TheilSen,
+}
+
+impl Regression {
+    /// Slope and intercept of the line through `log_counts` against the
+    /// level.
+    pub fn fit(self, log_counts: &[f64]) -> (f64, f64) {
+        match self {
+            Regression::Ols => ols_fit(log_counts),
+            Regression::Wls => weighted_fit(log_counts),
+            Regression::TheilSen => median_fit(log_counts),
+        }
+    }
+}
+
+/// Which part of the log-log data the dimension is fitted on
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FitOptions {
+    /// Smallest and largest scale, in voxels, that enter the fit
+    pub min_scale: Option<f64>,
+    pub max_scale: Option<f64>,
+    pub regression: Regression,
+    /// Narrow the scales down to the most linear run of at least this many
+    /// points
+    pub auto_points: Option<usize>,
+}
+
+impl FitOptions {
+    /// Indices of the points whose scale lies within the range. Scales grow
+    /// or shrink steadily along the data, so the points form one run.
+    pub fn select(&self, scales: &[f64]) -> std::ops::Range<usize> {
+        let inside = |&s: &f64| {
+            self.min_scale.is_none_or(|min| s >= min) && self.max_scale.is_none_or(|max| s <= max)
+        };
+        let start = scales.iter().position(inside).unwrap_or(scales.len());
+        let len = scales[start..].iter().take_while(|s| inside(s)).count();
+        start..start + len
+    }
+
+    /// Indices of the points the dimension is fitted on: those whose scale
+    /// lies within the range, narrowed down to the most linear run of
+    /// `log_counts` with `auto_points`.
+    pub fn fitted(&self, scales: &[f64], log_counts: &[f64]) -> std::ops::Range<usize> {
+        let fitted = self.select(scales);
+        match self.auto_points {
+            Some(min_points) => {
+                let region = scaling_region(&log_counts[fitted.clone()], min_points);
+                fitted.start + region.start..fitted.start + region.end
+            }
+            None => fitted,
+        }
+    }
+}
+
+/// Least-squares line, through the means of the level and the count.
+fn ols_fit(log_counts: &[f64]) -> (f64, f64) {
+    let slope = get_inclination(log_counts);
+    let mean_level = (log_counts.len() as f64 - 1.0) / 2.0;
+    let mean_count = log_counts.iter().sum::<f64>() / log_counts.len() as f64;
+    (slope, mean_count - slope * mean_level)
+}
+
+/// Weighted least-squares line, each point weighted by the count 2^y it is
+/// the logarithm of, as the relative error of a count shrinks as one over
+/// its square root. It passes through the weighted means.
+fn weighted_fit(log_counts: &[f64]) -> (f64, f64) {
+    let weights: Vec<f64> = log_counts.iter().map(|&y| y.exp2()).collect();
+    let total: f64 = weights.iter().sum();
+    let mean_x = weights
+        .iter()
+        .enumerate()
+        .map(|(x, w)| w * x as f64)
+        .sum::<f64>()
+        / total;
+    let mean_y = weights
+        .iter()
+        .zip(log_counts)
+        .map(|(w, y)| w * y)
+        .sum::<f64>()
+        / total;
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for (x, (w, y)) in weights.iter().zip(log_counts).enumerate() {
+        sxy += w * (x as f64 - mean_x) * (y - mean_y);
+        sxx += w * (x as f64 - mean_x).powi(2);
+    }
+    let slope = sxy / sxx;
+    (slope, mean_y - slope * mean_x)
+}
+
+/// Theil-Sen line: the median of the slopes between every pair of points,
+/// and the median of the intercepts of the points under that slope.
+fn median_fit(log_counts: &[f64]) -> (f64, f64) {
+    let mut slopes = Vec::new();
+    for (i, a) in log_counts.iter().enumerate() {
+        for (j, b) in log_counts.iter().enumerate().skip(i + 1) {
+            slopes.push((b - a) / (j - i) as f64);
+        }
+    }
+    let slope = median(slopes);
+    let intercepts = log_counts
+        .iter()
+        .enumerate()
+        .map(|(x, y)| y - slope * x as f64)
+        .collect();
+    (slope, median(intercepts))
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    match values.len() {
+        0 => f64::NAN,
+        n if n % 2 == 1 => values[n / 2],
+        n => (values[n / 2 - 1] + values[n / 2]) / 2.0,
+    }
+}
+
+/// The run of at least `min_points` successive points of `log_counts` that
+/// lies closest to a line, by the adjusted R² of its fit, which keeps short
+/// runs from winning by chance. The longer run wins a tie; all points are
+/// kept if there are too few of them.
+pub fn scaling_region(log_counts: &[f64], min_points: usize) -> std::ops::Range<usize> {
+    let min_points = min_points.max(3);
+    let mut best = (f64::NEG_INFINITY, 0..log_counts.len());
+    for len in min_points..=log_counts.len() {
+        for start in 0..=log_counts.len() - len {
+            let points = &log_counts[start..start + len];
+            let (slope, intercept) = ols_fit(points);
+            let (r_squared, _, _) = fit_statistics(points, slope, intercept);
+            let n = len as f64;
+            let adjusted = 1.0 - (1.0 - r_squared) * (n - 1.0) / (n - 2.0);
+            // A perfectly flat run has no R²; it lies on a line all the same
+            let adjusted = if adjusted.is_nan() { 1.0 } else { adjusted };
+            if adjusted >= best.0 {
+                best = (adjusted, start..start + len);
+            }
+        }
+    }
+    best.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: [f64; 5] = [1.0, 3.5, 6.0, 8.5, 11.0];
+
+    #[test]
+    fn every_regression_fits_a_line_exactly() {
+        for regression in [Regression::Ols, Regression::Wls, Regression::TheilSen] {
+            let (slope, intercept) = regression.fit(&LINE);
+            assert!((slope - 2.5).abs() < 1e-9, "{regression:?}");
+            assert!((intercept - 1.0).abs() < 1e-9, "{regression:?}");
+            let (r_squared, rms, _) = fit_statistics(&LINE, slope, intercept);
+            assert!((r_squared - 1.0).abs() < 1e-9, "{regression:?}");
+            assert!(rms < 1e-9, "{regression:?}");
+        }
+    }
+
+    #[test]
+    fn theil_sen_ignores_an_outlier() {
+        let mut counts = LINE;
+        counts[4] = 30.0;
+        let (slope, intercept) = Regression::TheilSen.fit(&counts);
+
+        assert_eq!((slope, intercept), (2.5, 1.0));
+        // The residuals are those of its own line, all but one of them 0
+        let (_, rms, _) = fit_statistics(&counts, slope, intercept);
+        assert!((rms - 19.0 / 5f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scaling_region_finds_the_straight_run() {
+        let counts = [0.0, 3.0, 6.0, 9.0, 9.5, 9.7];
+        assert_eq!(scaling_region(&counts, 3), 0..4);
+    }
+}
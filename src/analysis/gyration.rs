@@ -0,0 +1,78 @@
+use ndarray::Array3;
+
+/// Shape of the aggregate from its gyration tensor
+pub struct Gyration {
+    /// Eigenvalues of the tensor, largest first, in squared voxels; their sum
+    /// is the squared radius of gyration
+    pub eigenvalues: [f64; 3],
+    /// Relative shape anisotropy: 0 for a sphere (or any isotropic
+    /// aggregate), 1 for a rod
+    pub asphericity: f64,
+    /// Positive for prolate (elongated) shapes, up to 2 for a rod, and
+    /// negative for oblate (flattened) ones, down to -1/4 for a disc
+    pub prolateness: f64,
+}
+
+/// Radius of gyration of the occupied voxels about their centre of mass, in
+/// voxels; NaN for an empty frame.
+pub fn radius(occupancy: &Array3<bool>) -> f64 {
+    let t = tensor(occupancy);
+    (t[0][0] + t[1][1] + t[2][2]).max(0.0).sqrt()
+}
+
+pub fn shape(occupancy: &Array3<bool>) -> Gyration {
+    let eigenvalues = symmetric_eigenvalues(tensor(occupancy));
+    let trace: f64 = eigenvalues.iter().sum();
+    let mean = trace / 3.0;
+    let deviations = eigenvalues.map(|l| l - mean);
+
+    Gyration {
+        eigenvalues,
+        asphericity: 1.5 * deviations.iter().map(|d| d * d).sum::<f64>() / (trace * trace),
+        prolateness: 27.0 * deviations.iter().product::<f64>() / trace.powi(3),
+    }
+}
+
+/// Gyration tensor of the occupied voxels: the covariance of their
+/// coordinates about the centre of mass.
+fn tensor(occupancy: &Array3<bool>) -> [[f64; 3]; 3] {
+    let (mut n, mut sum, mut products) = (0.0, [0.0; 3], [[0.0; 3]; 3]);
+    for ((x, y, z), _) in occupancy.indexed_iter().filter(|(_, &v)| v) {
+        let p = [x as f64, y as f64, z as f64];
+        n += 1.0;
+        for i in 0..3 {
+            sum[i] += p[i];
+            for j in 0..3 {
+                products[i][j] += p[i] * p[j];
+            }
+        }
+    }
+
+    std::array::from_fn(|i| std::array::from_fn(|j| products[i][j] / n - sum[i] * sum[j] / (n * n)))
+}
+
+/// Eigenvalues of a symmetric 3x3 matrix, largest first, with the
+/// trigonometric solution of its characteristic polynomial.
+fn symmetric_eigenvalues(a: [[f64; 3]; 3]) -> [f64; 3] {
+    let off_diagonal = a[0][1].powi(2) + a[0][2].powi(2) + a[1][2].powi(2);
+    if off_diagonal == 0.0 {
+        let mut eigenvalues = [a[0][0], a[1][1], a[2][2]];
+        eigenvalues.sort_by(|x, y| y.total_cmp(x));
+        return eigenvalues;
+    }
+    let q = (a[0][0] + a[1][1] + a[2][2]) / 3.0;
+    let p = ((0..3).map(|i| (a[i][i] - q).powi(2)).sum::<f64>() + 2.0 * off_diagonal) / 6.0;
+    let p = p.sqrt();
+    // b = (a - q I) / p, whose determinant gives the angle of the roots
+    let b: [[f64; 3]; 3] = std::array::from_fn(|i| {
+        std::array::from_fn(|j| (a[i][j] - if i == j { q } else { 0.0 }) / p)
+    });
+    let det = b[0][0] * (b[1][1] * b[2][2] - b[1][2] * b[2][1])
+        - b[0][1] * (b[1][0] * b[2][2] - b[1][2] * b[2][0])
+        + b[0][2] * (b[1][0] * b[2][1] - b[1][1] * b[2][0]);
+    let phi = (det / 2.0).clamp(-1.0, 1.0).acos() / 3.0;
+    let largest = q + 2.0 * p * phi.cos();
+    let smallest = q + 2.0 * p * (phi + 2.0 * std::f64::consts::PI / 3.0).cos();
+
+    [largest, 3.0 * q - largest - smallest, smallest]
+}
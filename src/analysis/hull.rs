@@ -0,0 +1,195 @@
+use ndarray::Array3;
+
+type Point = [i64; 3];
+
+/// Volume, in voxels, of the convex hull of the occupied voxels taken as unit
+/// cubes; 0 for an empty or flat aggregate.
+pub fn volume(occupancy: &Array3<bool>) -> f64 {
+    let points = candidates(occupancy);
+    let Some(mut faces) = tetrahedron(&points) else {
+        return 0.0;
+    };
+
+    // Incremental hull: every point that sees some faces replaces them with
+    // a fan of faces from the horizon to the point
+    for &p in &points {
+        let visible: Vec<bool> = faces.iter().map(|f| orientation(f, p) > 0).collect();
+        if !visible.contains(&true) {
+            continue;
+        }
+        let mut horizon = Vec::new();
+        for (face, _) in faces.iter().zip(&visible).filter(|(_, &v)| v) {
+            for k in 0..3 {
+                let edge = (face[k], face[(k + 1) % 3]);
+                // An edge is on the horizon if the face across it is hidden
+                let shared = faces.iter().zip(&visible).any(|(other, &v)| {
+                    v && (0..3).any(|j| (other[j], other[(j + 1) % 3]) == (edge.1, edge.0))
+                });
+                if !shared {
+                    horizon.push(edge);
+                }
+            }
+        }
+        let mut kept: Vec<[Point; 3]> = faces
+            .into_iter()
+            .zip(visible)
+            .filter(|(_, v)| !v)
+            .map(|(f, _)| f)
+            .collect();
+        kept.extend(horizon.into_iter().map(|(a, b)| [a, b, p]));
+        faces = kept;
+    }
+
+    let origin = faces[0][0];
+    let six_volume: i64 = faces
+        .iter()
+        .map(|f| determinant(sub(f[0], origin), sub(f[1], origin), sub(f[2], origin)))
+        .sum();
+    six_volume as f64 / 6.0
+}
+
+/// Corners of the voxels that can be vertices of the hull: the vertices of
+/// the planar hull, in every plane of constant X, of the corners at the ends
+/// of the occupied columns along Z.
+fn candidates(occupancy: &Array3<bool>) -> Vec<Point> {
+    let (x_max, y_max, _) = occupancy.dim();
+    let mut points = Vec::new();
+    for x in 0..x_max {
+        for y in 0..y_max {
+            let column = occupancy.slice(ndarray::s![x, y, ..]);
+            let Some(low) = column.iter().position(|&v| v) else {
+                continue;
+            };
+            let high = column.iter().rposition(|&v| v).unwrap_or(low) + 1;
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                for z in [low, high] {
+                    points.push([(x + dx) as i64, (y + dy) as i64, z as i64]);
+                }
+            }
+        }
+    }
+    points.sort_unstable();
+    points.dedup();
+
+    // Sorted by X, so every plane is a run of points sorted by Y and Z
+    points
+        .chunk_by(|a, b| a[0] == b[0])
+        .flat_map(|plane| {
+            let x = plane[0][0];
+            planar_hull(plane.iter().map(|p| [p[1], p[2]]).collect())
+                .into_iter()
+                .map(move |[y, z]| [x, y, z])
+        })
+        .collect()
+}
+
+/// Vertices of the convex hull of sorted planar points, with Andrew's monotone
+/// chain; points on its edges are left out.
+fn planar_hull(points: Vec<[i64; 2]>) -> Vec<[i64; 2]> {
+    if points.len() < 3 {
+        return points;
+    }
+    let turn = |o: [i64; 2], a: [i64; 2], b: [i64; 2]| {
+        (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+    };
+    let mut hull: Vec<[i64; 2]> = Vec::new();
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for p in pass {
+            while hull.len() >= start + 2
+                && turn(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // The last point of a chain starts the next one
+        hull.pop();
+    }
+    hull
+}
+
+/// Faces of a first tetrahedron spanned by the points, oriented outwards,
+/// or `None` if the points are flat.
+fn tetrahedron(points: &[Point]) -> Option<Vec<[Point; 3]>> {
+    let &a = points.first()?;
+    let &b = points.iter().find(|&&p| p != a)?;
+    let &c = points
+        .iter()
+        .find(|&&p| cross(sub(b, a), sub(p, a)) != [0, 0, 0])?;
+    let &d = points
+        .iter()
+        .find(|&&p| determinant(sub(b, a), sub(c, a), sub(p, a)) != 0)?;
+    // Orient a, b, c so that d lies behind it
+    let (b, c) = if determinant(sub(b, a), sub(c, a), sub(d, a)) > 0 {
+        (c, b)
+    } else {
+        (b, c)
+    };
+    Some(vec![[a, b, c], [a, d, b], [b, d, c], [c, d, a]])
+}
+
+/// Positive if `p` lies in front of the outward face `f`.
+fn orientation(f: &[Point; 3], p: Point) -> i64 {
+    determinant(sub(f[1], f[0]), sub(f[2], f[0]), sub(p, f[0]))
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: Point, b: Point) -> Point {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn determinant(a: Point, b: Point, c: Point) -> i64 {
+    let [x, y, z] = cross(b, c);
+    a[0] * x + a[1] * y + a[2] * z
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    #[test]
+    fn hull_of_a_box_is_the_box() {
+        let mut occupancy = Array3::from_elem((5, 5, 5), false);
+        assert_eq!(volume(&occupancy), 0.0);
+        occupancy[[2, 2, 2]] = true;
+        assert_eq!(volume(&occupancy), 1.0);
+        occupancy.slice_mut(s![1..3, 1..4, 0..4]).fill(true);
+        assert_eq!(volume(&occupancy), 24.0);
+    }
+
+    #[test]
+    fn hull_bridges_a_corner() {
+        // An L of three voxels spans a 2×2×1 square less half a voxel
+        let mut occupancy = Array3::from_elem((2, 2, 1), false);
+        for idx in [[0, 0, 0], [1, 0, 0], [0, 1, 0]] {
+            occupancy[idx] = true;
+        }
+        assert_eq!(volume(&occupancy), 3.5);
+    }
+
+    #[test]
+    fn hull_of_two_distant_voxels() {
+        // A unit cube swept along the diagonal from one corner of a 3×3×3
+        // box to the other: 1 voxel, and the √3 shadow of the cube over the
+        // 2√3 it travels
+        let mut occupancy = Array3::from_elem((3, 3, 3), false);
+        occupancy[[0, 0, 0]] = true;
+        occupancy[[2, 2, 2]] = true;
+        let hull = volume(&occupancy);
+        assert_eq!(hull, 7.0);
+        // Mirroring the aggregate does not change its hull
+        occupancy.invert_axis(ndarray::Axis(0));
+        let mirrored = occupancy.as_standard_layout().into_owned();
+        assert_eq!(volume(&mirrored), hull);
+    }
+}
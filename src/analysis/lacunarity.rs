@@ -0,0 +1,71 @@
+use ndarray::Array3;
+use rayon::prelude::*;
+
+use super::SummedVolume;
+
+/// Gliding-box lacunarity curve after Allain and Cloitre: a cubic box of
+/// edge r is slid over every position inside the frame, and the lacunarity
+/// at r is the ratio of the second moment of the box masses to their squared
+/// mean. It is 1 for a uniformly filled frame and grows with the gaps of the
+/// aggregate. Box edges go from 1 voxel up to half the shortest edge of the
+/// frame, and the points are returned as rows of box edge and lacunarity.
+pub fn curve(occupancy: &Array3<bool>) -> Vec<Vec<f64>> {
+    let table = SummedVolume::new(occupancy);
+    let shape = table.shape();
+    let shortest_edge = shape.iter().copied().min().unwrap_or(0);
+
+    let mut points = Vec::new();
+    let mut size = 1;
+    while size == 1 || size <= shortest_edge / 2 {
+        let positions = shape.map(|n| n + 1 - size.min(n));
+        let (sum, sum_squares) = (0..positions[0])
+            .into_par_iter()
+            .map(|x| {
+                let (mut sum, mut sum_squares) = (0.0, 0.0);
+                for y in 0..positions[1] {
+                    for z in 0..positions[2] {
+                        let mass = table.mass([x, y, z], [x + size, y + size, z + size]) as f64;
+                        sum += mass;
+                        sum_squares += mass * mass;
+                    }
+                }
+                (sum, sum_squares)
+            })
+            .reduce(|| (0.0, 0.0), |a, b| (a.0 + b.0, a.1 + b.1));
+        let boxes = positions.iter().product::<usize>() as f64;
+        points.push(vec![size as f64, sum_squares * boxes / (sum * sum)]);
+        size *= 2;
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    #[test]
+    fn solid_cube_has_no_gaps() {
+        let occupancy = Array3::from_elem((16, 16, 16), true);
+        let points = curve(&occupancy);
+        assert_eq!(points.len(), 4);
+        for point in points {
+            assert!((point[1] - 1.0).abs() < 1e-12, "{point:?}");
+        }
+    }
+
+    #[test]
+    fn line_is_lacunar() {
+        let mut occupancy = Array3::from_elem((16, 16, 16), false);
+        occupancy.slice_mut(s![.., 8, 8]).fill(true);
+        let points = curve(&occupancy);
+        // Of the (17 - r)² columns of boxes of edge r, r² hold r voxels of
+        // the line and the others none
+        for point in points {
+            let expected = ((17.0 - point[0]) / point[0]).powi(2);
+            assert!((point[1] - expected).abs() < 1e-9, "{point:?}");
+        }
+    }
+}
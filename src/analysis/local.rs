@@ -0,0 +1,68 @@
+use ndarray::{s, Array3};
+use rayon::prelude::*;
+
+use super::{box_counting, FitOptions};
+
+/// Box-counting dimension of every cubic window of edge `window` that fits
+/// in the frame, with windows `step` voxels apart along each axis. Windows
+/// overlap when `step` is below `window`; an empty window has a NaN
+/// dimension.
+pub fn dimension_map(occupancy: &Array3<bool>, window: usize, step: usize) -> Array3<f64> {
+    let shape = occupancy.shape();
+    let positions: Vec<usize> = shape
+        .iter()
+        .map(|&n| {
+            if n < window {
+                0
+            } else {
+                (n - window) / step + 1
+            }
+        })
+        .collect();
+    let dimensions: Vec<f64> = (0..positions.iter().product::<usize>())
+        .into_par_iter()
+        .map(|i| {
+            let x = i / (positions[1] * positions[2]) * step;
+            let y = i / positions[2] % positions[1] * step;
+            let z = i % positions[2] * step;
+            let subvolume = occupancy.slice(s![x..x + window, y..y + window, z..z + window]);
+            if subvolume.iter().any(|&v| v) {
+                box_counting::estimate(&subvolume.to_owned(), &FitOptions::default())
+                    .fractal_dimension
+            } else {
+                f64::NAN
+            }
+        })
+        .collect();
+
+    Array3::from_shape_vec((positions[0], positions[1], positions[2]), dimensions)
+        .expect("one dimension per window")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_on_a_plane_have_dimension_2() {
+        let mut occupancy = Array3::from_elem((32, 32, 32), false);
+        occupancy.slice_mut(s![.., .., 4]).fill(true);
+        let map = dimension_map(&occupancy, 16, 16);
+        assert_eq!(map.dim(), (2, 2, 2));
+        for ((_, _, z), &d) in map.indexed_iter() {
+            if z == 0 {
+                assert!((d - 2.0).abs() < 0.01, "D = {d}");
+            } else {
+                assert!(d.is_nan());
+            }
+        }
+    }
+
+    #[test]
+    fn windows_of_a_solid_cube_have_dimension_3() {
+        let occupancy = Array3::from_elem((32, 32, 32), true);
+        let map = dimension_map(&occupancy, 16, 8);
+        assert_eq!(map.dim(), (3, 3, 3));
+        assert!(map.iter().all(|&d| (d - 3.0).abs() < 0.01));
+    }
+}
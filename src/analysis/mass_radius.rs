@@ -0,0 +1,98 @@
+use ndarray::Array3;
+
+use super::{Estimate, FitOptions};
+
+/// Mass-radius dimension: the mass M(r) of the aggregate within a sphere of
+/// radius r around its centre of mass, or around `centre`, scales as r^D.
+/// Radii go from 1 voxel up to the largest sphere that fits in the frame and
+/// does not reach beyond the farthest occupied voxel; radii at which the
+/// sphere is still empty, around a hollow centre, are skipped.
+pub fn estimate(
+    occupancy: &Array3<bool>,
+    centre: Option<[usize; 3]>,
+    options: &FitOptions,
+) -> Estimate {
+    let occupied: Vec<[f64; 3]> = occupancy
+        .indexed_iter()
+        .filter(|(_, &v)| v)
+        .map(|((x, y, z), _)| [x as f64, y as f64, z as f64])
+        .collect();
+    let centre = match centre {
+        Some(centre) => centre.map(|c| c as f64),
+        None => {
+            let n = occupied.len() as f64;
+            std::array::from_fn(|i| occupied.iter().map(|p| p[i]).sum::<f64>() / n)
+        }
+    };
+    let mut distances: Vec<f64> = occupied
+        .iter()
+        .map(|p| {
+            (0..3)
+                .map(|i| (p[i] - centre[i]).powi(2))
+                .sum::<f64>()
+                .sqrt()
+        })
+        .collect();
+    distances.sort_by(f64::total_cmp);
+
+    let shape = occupancy.shape();
+    // Flat axes of a single voxel do not bound the radius of a planar frame
+    let inside = (0..3)
+        .filter(|&i| shape[i] > 1)
+        .map(|i| centre[i].min(shape[i] as f64 - 1.0 - centre[i]))
+        .fold(f64::INFINITY, f64::min);
+    let farthest = distances.last().copied().unwrap_or(0.0);
+
+    // Smallest radius first, so that the slope against the level is D
+    let (mut radii, mut log_masses) = (Vec::new(), Vec::new());
+    let mut radius = 1.0;
+    while radius == 1.0 || (radius <= inside && radius <= farthest) {
+        let mass = distances.partition_point(|&d| d <= radius);
+        if mass > 0 {
+            radii.push(radius);
+            log_masses.push((mass as f64).log2());
+        }
+        radius *= 2.0;
+    }
+
+    Estimate::from_fit(&radii, &log_masses, &[], occupied.len(), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    fn dimension(occupancy: &Array3<bool>) -> f64 {
+        // The lattice skews the mass within a sphere of radius 1
+        let options = FitOptions {
+            min_scale: Some(2.0),
+            ..Default::default()
+        };
+        estimate(occupancy, None, &options).fractal_dimension
+    }
+
+    #[test]
+    fn line_has_dimension_1() {
+        let mut occupancy = Array3::from_elem((64, 64, 64), false);
+        occupancy.slice_mut(s![.., 32, 32]).fill(true);
+        let d = dimension(&occupancy);
+        assert!((d - 1.0).abs() < 0.01, "D = {d}");
+    }
+
+    #[test]
+    fn plane_has_dimension_2() {
+        let mut occupancy = Array3::from_elem((64, 64, 64), false);
+        occupancy.slice_mut(s![.., .., 32]).fill(true);
+        let d = dimension(&occupancy);
+        assert!((d - 2.0).abs() < 0.05, "D = {d}");
+    }
+
+    #[test]
+    fn solid_cube_has_dimension_3() {
+        let occupancy = Array3::from_elem((64, 64, 64), true);
+        let d = dimension(&occupancy);
+        assert!((d - 3.0).abs() < 0.05, "D = {d}");
+    }
+}
@@ -0,0 +1,106 @@
+use ndarray::Array3;
+
+/// The four Minkowski functionals of the aggregate, taking its occupied
+/// voxels as closed unit cubes
+pub struct Minkowski {
+    pub volume: usize,
+    /// Area of the faces between occupied and empty voxels
+    pub surface_area: f64,
+    /// Integral mean curvature, in units of pi voxels
+    pub mean_curvature: f64,
+    /// Number of components, minus tunnels, plus cavities
+    pub euler_characteristic: i64,
+}
+
+/// Minkowski functionals from the numbers of cubes, faces, edges and vertices
+/// of the cubical complex of the occupied voxels, after Michielsen and De
+/// Raedt.
+pub fn functionals(occupancy: &Array3<bool>) -> Minkowski {
+    let [cubes, faces, edges, vertices] = cells(occupancy);
+
+    Minkowski {
+        volume: cubes as usize,
+        surface_area: (2 * faces - 6 * cubes) as f64,
+        // Twice the mean breadth, so that an a×b×c box gives a + b + c
+        mean_curvature: (3 * cubes - 2 * faces + edges) as f64,
+        euler_characteristic: vertices - edges + faces - cubes,
+    }
+}
+
+/// Numbers of distinct cubes, faces, edges and vertices of the occupied
+/// voxels. A cell of the lattice belongs to the complex if any voxel around
+/// it is occupied.
+fn cells(occupancy: &Array3<bool>) -> [i64; 4] {
+    let (x_max, y_max, z_max) = occupancy.dim();
+    let occupied = |x: isize, y: isize, z: isize| {
+        x >= 0
+            && y >= 0
+            && z >= 0
+            && (x as usize) < x_max
+            && (y as usize) < y_max
+            && (z as usize) < z_max
+            && occupancy[[x as usize, y as usize, z as usize]]
+    };
+    // A cell at the lattice point (x, y, z), spanning the voxels one step
+    // back along the axes in `span`, belongs to the complex if any of them is
+    // occupied
+    let touched = |x: isize, y: isize, z: isize, span: [bool; 3]| {
+        let steps = |s: bool| if s { 0..=0 } else { -1..=0 };
+        steps(span[0]).any(|dx| {
+            steps(span[1]).any(|dy| steps(span[2]).any(|dz| occupied(x + dx, y + dy, z + dz)))
+        })
+    };
+
+    let mut counts = [0i64; 4];
+    for x in 0..=x_max as isize {
+        for y in 0..=y_max as isize {
+            for z in 0..=z_max as isize {
+                // The cells whose lowest corner is the lattice point, by the
+                // axes they extend along
+                for span in 0..8u8 {
+                    let span = [span & 1 != 0, span & 2 != 0, span & 4 != 0];
+                    let along = span.iter().filter(|&&s| s).count();
+                    let fits = (!span[0] || x < x_max as isize)
+                        && (!span[1] || y < y_max as isize)
+                        && (!span[2] || z < z_max as isize);
+                    if fits && touched(x, y, z, span) {
+                        counts[3 - along] += 1;
+                    }
+                }
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    /// An a×b×c box of voxels, one voxel in from the faces of the frame
+    fn cuboid([a, b, c]: [usize; 3]) -> Array3<bool> {
+        let mut occupancy = Array3::from_elem((a + 2, b + 2, c + 2), false);
+        occupancy.slice_mut(s![1..=a, 1..=b, 1..=c]).fill(true);
+        occupancy
+    }
+
+    #[test]
+    fn box_functionals_match_their_closed_forms() {
+        for [a, b, c] in [[1, 1, 1], [24, 24, 24], [32, 32, 1], [32, 1, 1], [3, 5, 7]] {
+            let m = functionals(&cuboid([a, b, c]));
+            assert_eq!(m.volume, a * b * c);
+            assert_eq!(m.surface_area, (2 * (a * b + b * c + c * a)) as f64);
+            assert_eq!(m.mean_curvature, (a + b + c) as f64, "{a}×{b}×{c}");
+            assert_eq!(m.euler_characteristic, 1);
+        }
+    }
+
+    #[test]
+    fn a_hollow_box_has_a_cavity() {
+        let mut occupancy = cuboid([5, 5, 5]);
+        occupancy[[3, 3, 3]] = false;
+        assert_eq!(functionals(&occupancy).euler_characteristic, 2);
+    }
+}
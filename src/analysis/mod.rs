@@ -0,0 +1,611 @@
+use std::ops::Range;
+
+use clap::ValueEnum;
+use ndarray::{Array3, CowArray, Ix3};
+use rayon::prelude::*;
+
+use crate::input::{Frame, Voxel};
+
+mod bootstrap;
+mod box_counting;
+mod chords;
+mod clusters;
+mod correlation;
+mod fft;
+mod fit;
+mod gyration;
+mod hull;
+mod lacunarity;
+mod local;
+mod mass_radius;
+mod minkowski;
+mod pair_correlation;
+mod sandbox;
+mod skeleton;
+mod succolarity;
+mod summed_volume;
+mod threshold;
+mod tortuosity;
+mod zbox;
+
+pub use clusters::Connectivity;
+pub use fit::{FitOptions, Regression};
+pub use gyration::Gyration;
+pub use minkowski::Minkowski;
+pub use pair_correlation::Backend;
+pub use skeleton::Skeleton;
+use summed_volume::SummedVolume;
+pub use threshold::{parse_range, Comparison, Cut, Threshold};
+pub use tortuosity::Phase;
+
+/// Which voxels `occupancy` keeps, for the provenance columns, unless a
+/// frame is binarized with a `Threshold` of its own
+pub const THRESHOLD_RULE: &str = "value >= 2, or true";
+
+/// How the fractal dimension of a frame is estimated
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Method {
+    // Box counting on merged Morton (Z-order) keys, the fast default
+    Zbox,
+    // Grid box counting at box sizes 1, 2, 4, ... voxels, the reference method
+    BoxCounting,
+    // Correlation dimension D2 from the pair-correlation integral
+    Correlation,
+    // Mass-radius dimension from the mass within growing spheres
+    MassRadius,
+    // Sandbox dimension from the mean mass within spheres around random
+    // occupied voxels
+    Sandbox,
+}
+
+impl Method {
+    /// The estimator and its parameters, for the provenance columns
+    pub fn description(self) -> &'static str {
+        match self {
+            Method::Zbox => "zbox-merge, key_bits=32, coordinate_bits=8",
+            Method::BoxCounting => "box-counting, box sizes 2^k up to half the shortest edge",
+            Method::Correlation => {
+                "correlation, cubes of edge 2r, radii 2^k up to a quarter of the shortest edge"
+            }
+            Method::MassRadius => "mass-radius, radii 2^k within the frame and the aggregate",
+            Method::Sandbox => {
+                "sandbox, random occupied seeds, radii 2^k up to a quarter of the shortest edge"
+            }
+        }
+    }
+}
+
+/// Results of the box-counting analysis of one frame
+pub struct Estimate {
+    pub fractal_dimension: f64,
+    /// Mean lacunarity over the box sizes the dimension is fitted on, NaN
+    /// for estimators without boxes
+    pub lacunarity: f64,
+    pub occupied_voxels: usize,
+    /// Coefficient of determination of the log-log fit
+    pub r_squared: f64,
+    /// Root mean square residual of the log-log fit, in log2 box counts
+    pub rms_residual: f64,
+    /// Standard error of the fitted dimension
+    pub std_error: f64,
+    /// 95% confidence interval of the fitted dimension
+    pub confidence_interval: [f64; 2],
+    /// Smallest and largest scale the dimension is fitted on, in voxels
+    pub scale_range: [f64; 2],
+    /// Every scale measured, in the order of the method, with the log2 count
+    /// at it: of boxes, of pairs or of the mass within a radius
+    pub counts: Vec<[f64; 2]>,
+    /// Which of the `counts` the dimension is fitted on
+    pub fitted: Range<usize>,
+}
+
+impl Estimate {
+    /// Fits the dimension as the slope of `log_counts` at successive
+    /// `scales`, each twice or half the last, on the scales picked by
+    /// `options`, and gathers the fit statistics. `lacunarities`, if any, go
+    /// with the scales.
+    fn from_fit(
+        scales: &[f64],
+        log_counts: &[f64],
+        lacunarities: &[f64],
+        occupied_voxels: usize,
+        options: &FitOptions,
+    ) -> Self {
+        let fitted = options.fitted(scales, log_counts);
+        let scale_range = if fitted.is_empty() {
+            [f64::NAN; 2]
+        } else {
+            let (first, last) = (scales[fitted.start], scales[fitted.end - 1]);
+            [first.min(last), first.max(last)]
+        };
+        let counts = scales
+            .iter()
+            .zip(log_counts)
+            .map(|(&scale, &log_count)| [scale, log_count])
+            .collect();
+        let log_counts = &log_counts[fitted.clone()];
+        let lacunarities = lacunarities.get(fitted.clone()).unwrap_or_default();
+        let (fractal_dimension, intercept) = options.regression.fit(log_counts);
+        let (r_squared, rms_residual, std_error) =
+            fit_statistics(log_counts, fractal_dimension, intercept);
+        let half_width = t_quantile_95(log_counts.len().saturating_sub(2)) * std_error;
+
+        Estimate {
+            fractal_dimension,
+            lacunarity: lacunarities.iter().sum::<f64>() / lacunarities.len() as f64,
+            occupied_voxels,
+            r_squared,
+            rms_residual,
+            std_error,
+            confidence_interval: [
+                fractal_dimension - half_width,
+                fractal_dimension + half_width,
+            ],
+            scale_range,
+            counts,
+            fitted,
+        }
+    }
+}
+
+/// How frames are analysed
+pub struct Settings {
+    pub method: Method,
+    /// Centre of the mass-radius spheres, instead of the centre of mass
+    pub centre: Option<[usize; 3]>,
+    /// Most occupied voxels the sandboxes are centred on
+    pub sandboxes: usize,
+    /// Empty voxels the zbox analysis surrounds every frame with
+    pub padding: Option<Padding>,
+    pub fit: FitOptions,
+}
+
+/// Empty box the zbox analysis embeds a frame in, against the low corner, so
+/// that the Morton keys scale every axis alike
+#[derive(Clone, Copy, Debug)]
+pub enum Padding {
+    /// The smallest cube with an edge of a power of two voxels holding the
+    /// frame; a flat axis stays flat
+    PowerOfTwo,
+    /// X×Y×Z voxels, no smaller than the frame
+    Size([usize; 3]),
+}
+
+impl Padding {
+    /// Shape of a frame of `shape` once padded
+    pub fn shape(self, shape: [usize; 3]) -> [usize; 3] {
+        match self {
+            Padding::PowerOfTwo => {
+                let edge = shape.iter().max().map_or(1, |n| n.next_power_of_two());
+                shape.map(|n| if n == 1 { 1 } else { edge })
+            }
+            Padding::Size(size) => size,
+        }
+    }
+}
+
+/// Generalized dimension D_q of the frame for every `q`, from grid box
+/// counting on the box sizes the dimension is fitted on.
+pub fn generalized_dimensions(frame: &Frame, qs: &[f64], settings: &Settings) -> Vec<f64> {
+    box_counting::generalized_dimensions(&occupancy(frame), qs, &settings.fit)
+}
+
+/// Information dimension D1 of the frame, the q -> 1 limit of the
+/// generalized dimensions, from the Shannon entropy of the box occupation
+/// probabilities.
+pub fn information_dimension(frame: &Frame, settings: &Settings) -> f64 {
+    box_counting::generalized_dimensions(&occupancy(frame), &[1.0], &settings.fit)[0]
+}
+
+/// Points (q, alpha, f(alpha)) of the singularity spectrum of the frame,
+/// from grid box counting on the box sizes the dimension is fitted on.
+pub fn singularity_spectrum(frame: &Frame, qs: &[f64], settings: &Settings) -> Vec<Vec<f64>> {
+    box_counting::singularity_spectrum(&occupancy(frame), qs, &settings.fit)
+}
+
+/// Points (box edge, lacunarity) of the gliding-box lacunarity curve of the
+/// frame.
+pub fn lacunarity_curve(frame: &Frame) -> Vec<Vec<f64>> {
+    lacunarity::curve(&occupancy(frame))
+}
+
+/// Local box-counting dimensions of the frame in windows of edge `window`,
+/// `step` voxels apart, as a frame of its own.
+pub fn local_dimensions(frame: &Frame, window: usize, step: usize) -> Frame<'static> {
+    CowArray::from(local::dimension_map(&occupancy(frame), window, step)).into()
+}
+
+/// Points (distance, g) of the radial pair-correlation function of the
+/// frame.
+pub fn pair_correlation(frame: &Frame, backend: Backend) -> Vec<Vec<f64>> {
+    pair_correlation::curve(&occupancy(frame), backend)
+}
+
+/// Points (radius, C) of the correlation integral that `--method correlation`
+/// fits: the fraction of pairs of occupied voxels that are neighbours within
+/// a cube of edge twice the radius, a voxel paired with itself included.
+pub fn correlation_integral(frame: &Frame) -> Vec<Vec<f64>> {
+    correlation::integral(&occupancy(frame))
+}
+
+/// Points (q, S) of the radially averaged structure factor of the frame.
+pub fn structure_factor(frame: &Frame) -> Vec<Vec<f64>> {
+    fft::structure_factor(&occupancy(frame))
+}
+
+/// Points (axis, length, count) of the chord-length distributions of the
+/// frame along X, Y and Z.
+pub fn chord_lengths(frame: &Frame) -> Vec<Vec<usize>> {
+    chords::distributions(&occupancy(frame))
+}
+
+pub fn estimate(frame: &Frame, settings: &Settings) -> Estimate {
+    match settings.method {
+        Method::Zbox => zbox::estimate(frame, settings.padding, &settings.fit),
+        Method::BoxCounting => box_counting::estimate(&occupancy(frame), &settings.fit),
+        Method::Correlation => correlation::estimate(&occupancy(frame), &settings.fit),
+        Method::MassRadius => {
+            mass_radius::estimate(&occupancy(frame), settings.centre, &settings.fit)
+        }
+        Method::Sandbox => sandbox::estimate(&occupancy(frame), settings.sandboxes, &settings.fit),
+    }
+}
+
+/// Succolarity of the pores of the frame for flow along X, Y and Z, from
+/// the low face of the frame to the high one.
+pub fn succolarity(frame: &Frame) -> [f64; 3] {
+    succolarity::succolarity(&occupancy(frame))
+}
+
+/// Branch statistics of the curve skeleton of the aggregate.
+pub fn skeleton(frame: &Frame) -> Skeleton {
+    skeleton::skeleton(&occupancy(frame))
+}
+
+/// Geometric tortuosity of `phase` across X, Y and Z.
+pub fn tortuosity(frame: &Frame, phase: Phase) -> [f64; 3] {
+    tortuosity::tortuosity(&occupancy(frame), phase)
+}
+
+/// Mean and standard deviation of the fractal dimension over `samples`
+/// bootstrap replicates of the aggregate, with its occupied voxels drawn with
+/// replacement; replicate i is drawn from seed `seed + i`.
+pub fn bootstrap(frame: &Frame, settings: &Settings, samples: usize, seed: u64) -> (f64, f64) {
+    let occupancy = occupancy(frame);
+    let dimensions: Vec<f64> = (0..samples as u64)
+        .into_par_iter()
+        .map(|i| {
+            let replicate = bootstrap::resample(&occupancy, seed.wrapping_add(i));
+            estimate(&Frame::Bool(CowArray::from(replicate)), settings).fractal_dimension
+        })
+        .collect();
+    let n = dimensions.len() as f64;
+    let mean = dimensions.iter().sum::<f64>() / n;
+    let variance = dimensions.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / (n - 1.0);
+
+    (mean, variance.sqrt())
+}
+
+/// Box-counting dimensions of the projections of the aggregate along X, Y
+/// and Z, whatever the method; they differ for anisotropic aggregates.
+pub fn projected_dimensions(frame: &Frame, settings: &Settings) -> [f64; 3] {
+    box_counting::projected_dimensions(&occupancy(frame), &settings.fit)
+}
+
+/// Two-dimensional box-counting estimate of every slice of the frame across
+/// Z, in order, as 2D image analysis of tomography slices would give it.
+pub fn slice_estimates(frame: &Frame, settings: &Settings) -> Vec<Estimate> {
+    let occupancy = occupancy(frame);
+    occupancy
+        .axis_iter(ndarray::Axis(2))
+        .map(|slice| {
+            box_counting::estimate(
+                &slice.to_owned().insert_axis(ndarray::Axis(2)),
+                &settings.fit,
+            )
+        })
+        .collect()
+}
+
+/// Fractal dimension of every tile of a grid of `tiles` tiles along X, Y and
+/// Z over the frame, as the tile position and its dimension; empty tiles are
+/// NaN. Tiles split the edges as evenly as they can.
+pub fn tile_dimensions(
+    frame: &Frame,
+    settings: &Settings,
+    tiles: [usize; 3],
+) -> Vec<([usize; 3], f64)> {
+    let occupancy = occupancy(frame);
+    let shape = occupancy.shape();
+    let bounds = |axis: usize, i: usize| i * shape[axis] / tiles[axis];
+    let mut dimensions = Vec::new();
+    for x in 0..tiles[0] {
+        for y in 0..tiles[1] {
+            for z in 0..tiles[2] {
+                let tile = occupancy.slice(ndarray::s![
+                    bounds(0, x)..bounds(0, x + 1),
+                    bounds(1, y)..bounds(1, y + 1),
+                    bounds(2, z)..bounds(2, z + 1)
+                ]);
+                let dimension = if tile.iter().any(|&v| v) {
+                    estimate(&Frame::Bool(CowArray::from(tile)), settings).fractal_dimension
+                } else {
+                    f64::NAN
+                };
+                dimensions.push(([x, y, z], dimension));
+            }
+        }
+    }
+    dimensions
+}
+
+/// Which voxels of the frame belong to the aggregate
+pub fn occupancy(frame: &Frame) -> Array3<bool> {
+    match frame {
+        Frame::Bool(f) => f.to_owned(),
+        Frame::U8(f) => f.map(|v| v.is_occupied()),
+        Frame::U16(f) => f.map(|v| v.is_occupied()),
+        Frame::I32(f) => f.map(|v| v.is_occupied()),
+        Frame::F32(f) => f.map(|v| v.is_occupied()),
+        Frame::F64(f) => f.map(|v| v.is_occupied()),
+    }
+}
+
+/// Occupied voxels with an empty face neighbour. The outside of the frame
+/// counts as empty, so the voxels on its faces belong to the surface.
+pub fn surface(occupancy: &Array3<bool>) -> Array3<bool> {
+    let (x_max, y_max, z_max) = occupancy.dim();
+    let empty = |x: usize, y: usize, z: usize, dx: isize, dy: isize, dz: isize| {
+        let (x, y, z) = (
+            x.checked_add_signed(dx),
+            y.checked_add_signed(dy),
+            z.checked_add_signed(dz),
+        );
+        match (x, y, z) {
+            (Some(x), Some(y), Some(z)) if x < x_max && y < y_max && z < z_max => {
+                !occupancy[[x, y, z]]
+            }
+            _ => true,
+        }
+    };
+    Array3::from_shape_fn(occupancy.dim(), |(x, y, z)| {
+        occupancy[[x, y, z]]
+            && FACE_NEIGHBOURS
+                .iter()
+                .any(|&[dx, dy, dz]| empty(x, y, z, dx, dy, dz))
+    })
+}
+
+/// Offsets of the six face neighbours of a voxel
+const FACE_NEIGHBOURS: [[isize; 3]; 6] = [
+    [-1, 0, 0],
+    [1, 0, 0],
+    [0, -1, 0],
+    [0, 1, 0],
+    [0, 0, -1],
+    [0, 0, 1],
+];
+
+/// Fractal dimension of the surface of the aggregate, estimated like its
+/// bulk dimension.
+pub fn surface_dimension(frame: &Frame, settings: &Settings) -> f64 {
+    let surface = Frame::Bool(CowArray::from(surface(&occupancy(frame))));
+    estimate(&surface, settings).fractal_dimension
+}
+
+/// Radius of gyration of the aggregate, in voxels
+pub fn radius_of_gyration(frame: &Frame) -> f64 {
+    gyration::radius(&occupancy(frame))
+}
+
+/// Eigenvalues and anisotropy of the gyration tensor of the aggregate
+pub fn gyration(frame: &Frame) -> Gyration {
+    gyration::shape(&occupancy(frame))
+}
+
+/// Connected clusters of at least `min_size` voxels, largest first, each as
+/// a frame cropped to its bounding box. The clusters own their voxels, so
+/// they can go alongside frames of any lifetime.
+pub fn clusters<'a>(frame: &Frame, connectivity: Connectivity, min_size: usize) -> Vec<Frame<'a>> {
+    clusters::clusters(&occupancy(frame), connectivity, min_size)
+        .into_iter()
+        .map(|cluster| CowArray::from(cluster).into())
+        .collect()
+}
+
+/// Sizes of the clusters of the frame, in voxels, largest first.
+pub fn cluster_sizes(frame: &Frame, connectivity: Connectivity) -> Vec<usize> {
+    clusters::label(&occupancy(frame), connectivity).1
+}
+
+/// Histogram of the coordination numbers of the occupied voxels, as rows of
+/// the number of occupied neighbours and the count of voxels with it.
+pub fn coordination_histogram(frame: &Frame, connectivity: Connectivity) -> Vec<Vec<usize>> {
+    clusters::coordination(&occupancy(frame), connectivity)
+        .into_iter()
+        .enumerate()
+        .map(|(neighbours, count)| vec![neighbours, count])
+        .collect()
+}
+
+/// Mean number of occupied neighbours of an occupied voxel; NaN for an empty
+/// frame.
+pub fn mean_coordination(frame: &Frame, connectivity: Connectivity) -> f64 {
+    let counts = clusters::coordination(&occupancy(frame), connectivity);
+    let bonds: usize = counts.iter().enumerate().map(|(n, &c)| n * c).sum();
+    bonds as f64 / counts.iter().sum::<usize>() as f64
+}
+
+/// Whether the aggregate percolates along X, Y and Z: some cluster of it
+/// spans the frame from face to face.
+pub fn percolation(frame: &Frame, connectivity: Connectivity) -> [bool; 3] {
+    clusters::spanning(&occupancy(frame), connectivity)
+}
+
+/// The frame with only its largest cluster left occupied.
+pub fn largest_cluster<'a>(frame: &Frame, connectivity: Connectivity) -> Frame<'a> {
+    CowArray::from(clusters::largest(&occupancy(frame), connectivity)).into()
+}
+
+/// The frame rolled over its periodic boundaries so that its largest cluster,
+/// counted across them, sits in one piece at its centre, unless it wraps all
+/// the way around the frame.
+pub fn recentre_periodic<'a>(frame: &Frame, connectivity: Connectivity) -> Frame<'a> {
+    let shift = clusters::periodic_shift(&occupancy(frame), connectivity);
+    match frame {
+        Frame::Bool(f) => roll(f, shift),
+        Frame::U8(f) => roll(f, shift),
+        Frame::U16(f) => roll(f, shift),
+        Frame::I32(f) => roll(f, shift),
+        Frame::F32(f) => roll(f, shift),
+        Frame::F64(f) => roll(f, shift),
+    }
+}
+
+/// The frame with every voxel moved `shift` voxels up along X, Y and Z,
+/// those beyond the high faces coming back in at the low ones.
+fn roll<'a, T: Voxel>(frame: &CowArray<'_, T, Ix3>, shift: [usize; 3]) -> Frame<'a> {
+    let (x_max, y_max, z_max) = frame.dim();
+    let back = |c: usize, shift: usize, n: usize| (c + n - shift) % n;
+    let rolled = Array3::from_shape_fn(frame.dim(), |(x, y, z)| {
+        frame[[
+            back(x, shift[0], x_max),
+            back(y, shift[1], y_max),
+            back(z, shift[2], z_max),
+        ]]
+    });
+    CowArray::from(rolled).into()
+}
+
+/// Fraction of the bounding box of the aggregate that it occupies; NaN for an
+/// empty frame.
+pub fn solid_fraction(frame: &Frame) -> f64 {
+    let occupancy = occupancy(frame);
+    let (mut lo, mut hi, mut occupied) = ([usize::MAX; 3], [0; 3], 0);
+    for ((x, y, z), _) in occupancy.indexed_iter().filter(|(_, &v)| v) {
+        for (i, c) in [x, y, z].into_iter().enumerate() {
+            lo[i] = lo[i].min(c);
+            hi[i] = hi[i].max(c + 1);
+        }
+        occupied += 1;
+    }
+    let volume: usize = (0..3).map(|i| hi[i].saturating_sub(lo[i])).product();
+    occupied as f64 / volume as f64
+}
+
+/// Fraction of the convex hull of the aggregate that it occupies; NaN for an
+/// empty or flat one.
+pub fn hull_solid_fraction(frame: &Frame) -> f64 {
+    let occupancy = occupancy(frame);
+    let occupied = occupancy.iter().filter(|&&v| v).count();
+    match hull::volume(&occupancy) {
+        0.0 => f64::NAN,
+        volume => occupied as f64 / volume,
+    }
+}
+
+/// Volume, surface area, integral mean curvature and Euler characteristic of
+/// the aggregate.
+pub fn minkowski_functionals(frame: &Frame) -> Minkowski {
+    minkowski::functionals(&occupancy(frame))
+}
+
+/// Euler characteristic of the aggregate: its clusters, less the tunnels
+/// through them, plus the cavities inside them.
+pub fn euler_characteristic(frame: &Frame) -> i64 {
+    minkowski_functionals(frame).euler_characteristic
+}
+
+/// Surface area of the aggregate over its volume, from the faces between
+/// occupied and empty voxels.
+pub fn specific_surface_area(frame: &Frame) -> f64 {
+    let minkowski = minkowski_functionals(frame);
+    minkowski.surface_area / minkowski.volume as f64
+}
+
+/// The voxels of the frame that `threshold` keeps, as a frame of its own
+/// that the analysis takes as it is.
+pub fn binarize<'a>(frame: &Frame, threshold: &Threshold) -> Frame<'a> {
+    CowArray::from(thresholded(frame, threshold)).into()
+}
+
+/// The voxels of the frame that `threshold` keeps.
+pub fn thresholded(frame: &Frame, threshold: &Threshold) -> Array3<bool> {
+    match frame {
+        Frame::Bool(f) => threshold.apply(f.view()),
+        Frame::U8(f) => threshold.apply(f.view()),
+        Frame::U16(f) => threshold.apply(f.view()),
+        Frame::I32(f) => threshold.apply(f.view()),
+        Frame::F32(f) => threshold.apply(f.view()),
+        Frame::F64(f) => threshold.apply(f.view()),
+    }
+}
+
+/// The empty voxels of the frame as the occupied ones of a frame of its own,
+/// to analyse the pores instead of the aggregate.
+pub fn invert<'a>(frame: &Frame) -> Frame<'a> {
+    CowArray::from(occupancy(frame).mapv(|v| !v)).into()
+}
+
+/// The occupancy of the frame as a frame of its own, for exporting
+pub fn mask(frame: &Frame) -> Frame<'static> {
+    CowArray::from(occupancy(frame)).into()
+}
+
+/// R², RMS residual and standard error of the slope of the line of `slope`
+/// and `intercept` fitted through `log_counts` against the box size level.
+fn fit_statistics(log_counts: &[f64], slope: f64, intercept: f64) -> (f64, f64, f64) {
+    let n = log_counts.len() as f64;
+    let mean_level = (n - 1.0) / 2.0;
+    let mean_count = log_counts.iter().sum::<f64>() / n;
+    let (mut ss_res, mut ss_tot, mut ss_level) = (0.0, 0.0, 0.0);
+    for (level, &count) in log_counts.iter().enumerate() {
+        ss_res += (count - intercept - slope * level as f64).powi(2);
+        ss_tot += (count - mean_count).powi(2);
+        ss_level += (level as f64 - mean_level).powi(2);
+    }
+    let std_error = (ss_res / (n - 2.0) / ss_level).sqrt();
+
+    (1.0 - ss_res / ss_tot, (ss_res / n).sqrt(), std_error)
+}
+
+/// Two-sided 95% quantile of Student's t distribution with `df` degrees of
+/// freedom.
+fn t_quantile_95(df: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match df {
+        0 => f64::NAN,
+        1..=30 => TABLE[df - 1],
+        _ => 1.96,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replicates_of_a_line_agree() {
+        // Gaps left by the resampling are finer than the scales of the fit
+        let mut line = Array3::from_elem((64, 64, 64), false);
+        line.slice_mut(ndarray::s![.., 32, 32]).fill(true);
+        let settings = Settings {
+            method: Method::BoxCounting,
+            centre: None,
+            sandboxes: 0,
+            padding: None,
+            fit: FitOptions {
+                min_scale: Some(8.0),
+                ..Default::default()
+            },
+        };
+        let (mean, std) = bootstrap(&Frame::Bool(CowArray::from(line)), &settings, 8, 0);
+        assert!((mean - 1.0).abs() < 1e-9, "D = {mean}");
+        assert_eq!(std, 0.0);
+    }
+}
@@ -0,0 +1,137 @@
+use clap::ValueEnum;
+use ndarray::Array3;
+
+use super::fft;
+
+/// How the pairs of occupied voxels are counted
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    // Autocorrelation by FFT, fast for dense frames
+    Fft,
+    // Every pair one by one, fast for sparse frames
+    Histogram,
+}
+
+/// Radial pair-correlation function g(r) of the occupied voxels, at
+/// distances of 1 voxel up to half the shortest edge of the frame, as rows of
+/// distance and g. Pairs are binned by their distance rounded to whole voxels
+/// and weighted by the number of voxel pairs in the frame that have their
+/// displacement, so that g is 1 for an uncorrelated frame at any distance
+/// despite its edges; for a fractal aggregate it decays as r^(D - 3).
+pub fn curve(occupancy: &Array3<bool>, backend: Backend) -> Vec<Vec<f64>> {
+    let shape = occupancy.dim();
+    let shape = [shape.0, shape.1, shape.2];
+    let max_distance = shape.iter().copied().min().unwrap_or(0) / 2;
+    let volume = shape.iter().product::<usize>() as f64;
+    let occupied = occupancy.iter().filter(|&&v| v).count() as f64;
+    let density = occupied / volume;
+    let bin = |d: [isize; 3]| {
+        let r = d
+            .iter()
+            .map(|&c| (c * c) as f64)
+            .sum::<f64>()
+            .sqrt()
+            .round() as usize;
+        (1..=max_distance).contains(&r).then_some(r)
+    };
+    // Pairs of voxels in the frame with displacement d, occupied at random
+    let expected = |d: [isize; 3]| -> f64 {
+        let overlap: f64 = (0..3)
+            .map(|i| (shape[i] as isize - d[i].abs()) as f64)
+            .product();
+        density * density * overlap
+    };
+
+    let mut sums = vec![0.0; max_distance + 1];
+    let mut displacements = vec![0usize; max_distance + 1];
+    let reach = max_distance as isize;
+    let range = |i: usize| -reach.min(shape[i] as isize - 1)..=reach.min(shape[i] as isize - 1);
+    for dx in range(0) {
+        for dy in range(1) {
+            for dz in range(2) {
+                if let Some(r) = bin([dx, dy, dz]) {
+                    displacements[r] += 1;
+                }
+            }
+        }
+    }
+    match backend {
+        Backend::Fft => {
+            let counts = fft::autocorrelation(occupancy);
+            let index = |d: isize, n: usize| {
+                if d >= 0 {
+                    d as usize
+                } else {
+                    (2 * n as isize + d) as usize
+                }
+            };
+            for dx in range(0) {
+                for dy in range(1) {
+                    for dz in range(2) {
+                        let d = [dx, dy, dz];
+                        if let Some(r) = bin(d) {
+                            let count = counts[[
+                                index(dx, shape[0]),
+                                index(dy, shape[1]),
+                                index(dz, shape[2]),
+                            ]];
+                            sums[r] += count / expected(d);
+                        }
+                    }
+                }
+            }
+        }
+        Backend::Histogram => {
+            let voxels: Vec<[isize; 3]> = occupancy
+                .indexed_iter()
+                .filter(|(_, &v)| v)
+                .map(|((x, y, z), _)| [x as isize, y as isize, z as isize])
+                .collect();
+            for (i, a) in voxels.iter().enumerate() {
+                for b in &voxels[i + 1..] {
+                    let d = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+                    if let Some(r) = bin(d) {
+                        // Both orders of the pair
+                        sums[r] += 2.0 / expected(d);
+                    }
+                }
+            }
+        }
+    }
+
+    (1..=max_distance)
+        .map(|r| vec![r as f64, sums[r] / displacements[r] as f64])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_block_is_uncorrelated() {
+        let occupancy = Array3::from_elem((8, 8, 8), true);
+        for backend in [Backend::Fft, Backend::Histogram] {
+            let points = curve(&occupancy, backend);
+            assert_eq!(points.len(), 4);
+            for point in points {
+                assert!((point[1] - 1.0).abs() < 1e-9, "{backend:?}: {point:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn backends_agree() {
+        let mut occupancy = Array3::from_elem((8, 8, 8), false);
+        for (i, v) in occupancy.iter_mut().enumerate() {
+            *v = i % 7 == 0 || i % 11 == 0;
+        }
+        let fft = curve(&occupancy, Backend::Fft);
+        let histogram = curve(&occupancy, Backend::Histogram);
+        for (a, b) in fft.iter().zip(&histogram) {
+            assert_eq!(a[0], b[0]);
+            assert!((a[1] - b[1]).abs() < 1e-9, "{a:?} and {b:?}");
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use ndarray::Array3;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+use super::{Estimate, FitOptions, SummedVolume};
+
+/// Sandbox dimension: the mass M(r) within a window of edge 2r around an
+/// occupied voxel, from r voxels before it to r - 1 after it along every
+/// axis, averaged over up to `seeds` voxels drawn at random, scales as r^D.
+/// The window holds exactly 2r voxels of a straight line, so the dimension is
+/// not skewed by the voxel in the middle. Only the seeds whose window fits in
+/// the frame enter the average at a radius, so that the boundary does not cut
+/// the windows short. Radii go from 1 voxel up to a quarter of the shortest
+/// edge of the frame, leaving out flat axes of a single voxel. The seeds are
+/// drawn the same way on every run.
+pub fn estimate(occupancy: &Array3<bool>, seeds: usize, options: &FitOptions) -> Estimate {
+    let shape = occupancy.shape();
+    let shortest_edge = shape.iter().copied().filter(|&n| n > 1).min().unwrap_or(0);
+    let occupied: Vec<[usize; 3]> = occupancy
+        .indexed_iter()
+        .filter(|(_, &v)| v)
+        .map(|((x, y, z), _)| [x, y, z])
+        .collect();
+    let mut rng = StdRng::seed_from_u64(0);
+    let picked = rand::seq::index::sample(&mut rng, occupied.len(), seeds.min(occupied.len()));
+    let seeds: Vec<[usize; 3]> = picked.into_iter().map(|i| occupied[i]).collect();
+    let table = SummedVolume::new(occupancy);
+    let fits = |seed: &[usize; 3], radius: usize| {
+        (0..3).all(|i| shape[i] == 1 || (seed[i] >= radius && seed[i] + radius <= shape[i]))
+    };
+
+    // Smallest radius first, so that the slope against the level is D
+    let (mut radii, mut log_masses) = (Vec::new(), Vec::new());
+    let mut radius = 1;
+    while radius == 1 || radius <= shortest_edge / 4 {
+        let masses: Vec<u64> = seeds
+            .par_iter()
+            .filter(|seed| fits(seed, radius))
+            .map(|seed| {
+                let lo = std::array::from_fn(|i| seed[i].saturating_sub(radius));
+                let hi = std::array::from_fn(|i| (seed[i] + radius).min(shape[i]));
+                table.mass(lo, hi)
+            })
+            .collect();
+        if !masses.is_empty() {
+            let mean = masses.iter().sum::<u64>() as f64 / masses.len() as f64;
+            radii.push(radius as f64);
+            log_masses.push(mean.log2());
+        }
+        radius *= 2;
+    }
+
+    Estimate::from_fit(&radii, &log_masses, &[], occupied.len(), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    #[test]
+    fn line_has_dimension_1() {
+        let mut occupancy = Array3::from_elem((64, 64, 1), false);
+        occupancy.slice_mut(s![.., 32, 0]).fill(true);
+        let estimate = estimate(&occupancy, 100, &FitOptions::default());
+        assert!((estimate.fractal_dimension - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn plane_has_dimension_2() {
+        let mut occupancy = Array3::from_elem((64, 64, 64), false);
+        occupancy.slice_mut(s![.., .., 32]).fill(true);
+        let estimate = estimate(&occupancy, 100, &FitOptions::default());
+        assert!((estimate.fractal_dimension - 2.0).abs() < 0.01);
+    }
+}
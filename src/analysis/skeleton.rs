@@ -0,0 +1,307 @@
+use ndarray::Array3;
+
+/// Branch statistics of the curve skeleton of an aggregate
+pub struct Skeleton {
+    /// Number of voxels left by the thinning
+    pub voxels: usize,
+    /// Skeleton voxels with a single neighbour, the tips of the branches
+    pub endpoints: usize,
+    /// Clusters of skeleton voxels with three neighbours or more, where
+    /// branches meet
+    pub junctions: usize,
+    /// Length in voxels of every branch, the runs of skeleton voxels between
+    /// junctions and endpoints, longest first
+    pub branch_lengths: Vec<usize>,
+}
+
+/// Thins the aggregate to its curve skeleton and gathers its branches.
+/// Neighbours are the 26 around a voxel throughout.
+pub fn skeleton(occupancy: &Array3<bool>) -> Skeleton {
+    let skeleton = thin(occupancy);
+    let (x_max, y_max, z_max) = skeleton.dim();
+    let shape = [x_max, y_max, z_max];
+    let voxels: Vec<[usize; 3]> = skeleton
+        .indexed_iter()
+        .filter(|(_, &v)| v)
+        .map(|((x, y, z), _)| [x, y, z])
+        .collect();
+    let degree = |voxel: [usize; 3]| neighbours(voxel, shape).filter(|&n| skeleton[n]).count();
+
+    let mut endpoints = 0;
+    let mut junction = Array3::from_elem(skeleton.dim(), false);
+    for &voxel in &voxels {
+        match degree(voxel) {
+            1 => endpoints += 1,
+            0 | 2 => {}
+            _ => junction[voxel] = true,
+        }
+    }
+    // A branch is a connected run of voxels that are not junctions
+    let branch = Array3::from_shape_fn(skeleton.dim(), |(x, y, z)| {
+        skeleton[[x, y, z]] && !junction[[x, y, z]]
+    });
+    let mut branch_lengths = runs(&branch, &voxels, shape);
+    branch_lengths.sort_unstable_by(|a, b| b.cmp(a));
+
+    Skeleton {
+        voxels: voxels.len(),
+        endpoints,
+        junctions: runs(&junction, &voxels, shape).len(),
+        branch_lengths,
+    }
+}
+
+/// Sizes of the connected runs of the voxels of `mask`, all of them among
+/// `voxels`.
+fn runs(mask: &Array3<bool>, voxels: &[[usize; 3]], shape: [usize; 3]) -> Vec<usize> {
+    let mut sizes = Vec::new();
+    let mut seen = Array3::from_elem(mask.dim(), false);
+    let mut stack = Vec::new();
+    for &voxel in voxels {
+        if !mask[voxel] || seen[voxel] {
+            continue;
+        }
+        seen[voxel] = true;
+        stack.push(voxel);
+        let mut size = 0;
+        while let Some(voxel) = stack.pop() {
+            size += 1;
+            for neighbour in neighbours(voxel, shape) {
+                if mask[neighbour] && !seen[neighbour] {
+                    seen[neighbour] = true;
+                    stack.push(neighbour);
+                }
+            }
+        }
+        sizes.push(size);
+    }
+    sizes
+}
+
+/// Curve skeleton by distance-ordered directional thinning: layer by layer
+/// from the surface inwards, the occupied voxels of the layer on the borders
+/// facing each of the six directions in turn are peeled off one by one, as
+/// long as they are simple points, whose removal changes neither the clusters
+/// nor the tunnels and cavities, and are not the ends of a line. Peeling the
+/// layers in order, and no voxel next to one that has just gone, keeps the
+/// thinning from eating into a branch along its length.
+fn thin(occupancy: &Array3<bool>) -> Array3<bool> {
+    let mut skeleton = occupancy.clone();
+    let (x_max, y_max, z_max) = skeleton.dim();
+    let shape = [x_max, y_max, z_max];
+    let depth = depth(occupancy, shape);
+    let deepest = depth.iter().copied().max().unwrap_or(0);
+    // Subiteration in which a neighbour of every voxel was last removed
+    let mut disturbed = Array3::<u32>::zeros(occupancy.dim());
+    let mut subiteration = 0;
+    let directions = [
+        [-1, 0, 0],
+        [1, 0, 0],
+        [0, -1, 0],
+        [0, 1, 0],
+        [0, 0, -1],
+        [0, 0, 1],
+    ];
+    for layer in 1..=deepest {
+        let voxels: Vec<[usize; 3]> = depth
+            .indexed_iter()
+            .filter(|(_, &d)| d == layer)
+            .map(|((x, y, z), _)| [x, y, z])
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for direction in directions {
+                subiteration += 1;
+                let border: Vec<[usize; 3]> = voxels
+                    .iter()
+                    .copied()
+                    .filter(|&voxel| skeleton[voxel] && !at(&skeleton, voxel, direction, shape))
+                    .collect();
+                // Removing a voxel can make its neighbours non-simple, so each
+                // is checked just before it goes
+                for voxel in border {
+                    if disturbed[voxel] == subiteration {
+                        continue;
+                    }
+                    let cube = neighbourhood(&skeleton, voxel, shape);
+                    let occupied = cube.iter().filter(|&&v| v).count() - 1;
+                    if occupied > 1 && is_simple(&cube) {
+                        skeleton[voxel] = false;
+                        for neighbour in neighbours(voxel, shape) {
+                            disturbed[neighbour] = subiteration;
+                        }
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+    skeleton
+}
+
+/// Depth of every occupied voxel below the surface of the aggregate, 1 for
+/// the voxels next to an empty one or the faces of the frame, in steps of
+/// 26 neighbours; 0 for empty voxels.
+fn depth(occupancy: &Array3<bool>, shape: [usize; 3]) -> Array3<u32> {
+    let mut depth = Array3::<u32>::zeros(occupancy.dim());
+    let mut queue = std::collections::VecDeque::new();
+    for ((x, y, z), &v) in occupancy.indexed_iter() {
+        let voxel = [x, y, z];
+        if v && (neighbours(voxel, shape).count() < 26
+            || neighbours(voxel, shape).any(|n| !occupancy[n]))
+        {
+            depth[voxel] = 1;
+            queue.push_back(voxel);
+        }
+    }
+    while let Some(voxel) = queue.pop_front() {
+        for neighbour in neighbours(voxel, shape) {
+            if occupancy[neighbour] && depth[neighbour] == 0 {
+                depth[neighbour] = depth[voxel] + 1;
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    depth
+}
+
+/// Whether the voxel at `offset` from `voxel` is occupied; the outside of
+/// the frame is empty.
+fn at(occupancy: &Array3<bool>, voxel: [usize; 3], offset: [isize; 3], shape: [usize; 3]) -> bool {
+    let mut neighbour = [0; 3];
+    for i in 0..3 {
+        match voxel[i].checked_add_signed(offset[i]) {
+            Some(c) if c < shape[i] => neighbour[i] = c,
+            _ => return false,
+        }
+    }
+    occupancy[neighbour]
+}
+
+/// The 26 neighbours of `voxel` inside the frame.
+fn neighbours(voxel: [usize; 3], shape: [usize; 3]) -> impl Iterator<Item = [usize; 3]> {
+    (0..27).filter(|&i| i != 13).filter_map(move |i| {
+        let offset = offset(i);
+        let mut neighbour = [0; 3];
+        for axis in 0..3 {
+            neighbour[axis] = voxel[axis].checked_add_signed(offset[axis])?;
+            if neighbour[axis] >= shape[axis] {
+                return None;
+            }
+        }
+        Some(neighbour)
+    })
+}
+
+/// Offset of position `i` of a 3×3×3 neighbourhood, 13 being the centre.
+fn offset(i: usize) -> [isize; 3] {
+    [i / 9, i / 3 % 3, i % 3].map(|c| c as isize - 1)
+}
+
+/// Occupancy of the 3×3×3 neighbourhood of `voxel`.
+fn neighbourhood(occupancy: &Array3<bool>, voxel: [usize; 3], shape: [usize; 3]) -> [bool; 27] {
+    std::array::from_fn(|i| at(occupancy, voxel, offset(i), shape))
+}
+
+/// Whether the centre of the neighbourhood is a simple point, after
+/// Bertrand and Malandain: its occupied 26-neighbours form a single
+/// 26-connected component, and its empty 18-neighbours a single 6-connected
+/// component that touches one of its face neighbours.
+fn is_simple(cube: &[bool; 27]) -> bool {
+    let distance = |i: usize| offset(i).iter().map(|c| c.abs()).sum::<isize>();
+    let occupied: Vec<usize> = (0..27).filter(|&i| i != 13 && cube[i]).collect();
+    let empty: Vec<usize> = (0..27)
+        .filter(|&i| i != 13 && !cube[i] && distance(i) <= 2)
+        .collect();
+
+    let touching = |a: usize, b: usize| {
+        let (a, b) = (offset(a), offset(b));
+        (0..3).all(|i| (a[i] - b[i]).abs() <= 1)
+    };
+    let facing = |a: usize, b: usize| {
+        let (a, b) = (offset(a), offset(b));
+        (0..3).map(|i| (a[i] - b[i]).abs()).sum::<isize>() == 1
+    };
+
+    components(&occupied, touching, |_| true) == 1
+        && components(&empty, facing, |i| distance(i) == 1) == 1
+}
+
+/// Number of components of `points` under `adjacent` that hold a point
+/// `counted`.
+fn components(
+    points: &[usize],
+    adjacent: impl Fn(usize, usize) -> bool,
+    counted: impl Fn(usize) -> bool,
+) -> usize {
+    let mut seen = vec![false; points.len()];
+    let mut count = 0;
+    for start in 0..points.len() {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut holds = false;
+        while let Some(p) = stack.pop() {
+            holds |= counted(points[p]);
+            for q in 0..points.len() {
+                if !seen[q] && adjacent(points[p], points[q]) {
+                    seen[q] = true;
+                    stack.push(q);
+                }
+            }
+        }
+        count += usize::from(holds);
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    #[test]
+    fn empty_frame_has_no_skeleton() {
+        let skeleton = skeleton(&Array3::from_elem((4, 4, 4), false));
+        assert_eq!(skeleton.voxels, 0);
+        assert_eq!(skeleton.endpoints, 0);
+        assert!(skeleton.branch_lengths.is_empty());
+    }
+
+    #[test]
+    fn thick_bar_thins_to_one_branch() {
+        let mut occupancy = Array3::from_elem((5, 5, 13), false);
+        occupancy.slice_mut(s![1..4, 1..4, 1..12]).fill(true);
+        let skeleton = skeleton(&occupancy);
+        assert_eq!(skeleton.endpoints, 2);
+        assert_eq!(skeleton.junctions, 0);
+        assert_eq!(skeleton.branch_lengths, [skeleton.voxels]);
+    }
+
+    #[test]
+    fn cross_has_four_branches_and_a_junction() {
+        let mut occupancy = Array3::from_elem((11, 11, 1), false);
+        occupancy.slice_mut(s![5, 1..10, 0]).fill(true);
+        occupancy.slice_mut(s![1..10, 5, 0]).fill(true);
+        let skeleton = skeleton(&occupancy);
+        assert_eq!(skeleton.voxels, 17);
+        assert_eq!(skeleton.endpoints, 4);
+        assert_eq!(skeleton.junctions, 1);
+        assert_eq!(skeleton.branch_lengths.len(), 4);
+    }
+
+    #[test]
+    fn ring_keeps_its_loop() {
+        let mut occupancy = Array3::from_elem((7, 7, 1), false);
+        occupancy.slice_mut(s![1..6, 1..6, 0]).fill(true);
+        occupancy.slice_mut(s![2..5, 2..5, 0]).fill(false);
+        let skeleton = skeleton(&occupancy);
+        assert!(skeleton.voxels > 0);
+        assert_eq!(skeleton.endpoints, 0);
+        assert_eq!(skeleton.branch_lengths.len(), 1);
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashSet;
+
+use ndarray::{Array3, Axis};
+
+use super::clusters::{self, Connectivity};
+use super::SummedVolume;
+
+/// Succolarity after de Melo and Conci: how far a fluid poured in at the low
+/// face of the frame across X, Y and Z penetrates the empty voxels, from 0
+/// when it cannot enter to 1 when it floods the whole frame. The fluid fills
+/// the pores face-connected to the entry face. The frame is covered with
+/// boxes of 1, 2, 4, ... voxels up to half its shortest edge, leaving out
+/// flat axes, and the flooded fraction of every box is weighted by the
+/// pressure on it, the depth of its centre below the entry face.
+pub fn succolarity(occupancy: &Array3<bool>) -> [f64; 3] {
+    let pores = occupancy.mapv(|v| !v);
+    let (labels, _) = clusters::label(&pores, Connectivity::Faces);
+    let (x_max, y_max, z_max) = occupancy.dim();
+    let shape = [x_max, y_max, z_max];
+    let shortest_edge = shape.iter().copied().filter(|&n| n > 1).min().unwrap_or(1);
+
+    std::array::from_fn(|axis| {
+        let entered: HashSet<u32> = labels
+            .index_axis(Axis(axis), 0)
+            .iter()
+            .copied()
+            .filter(|&l| l != 0)
+            .collect();
+        let flooded = SummedVolume::new(&labels.mapv(|l| entered.contains(&l)));
+
+        let (mut weighted, mut pressure) = (0.0, 0.0);
+        let mut size = 1;
+        while size == 1 || size <= shortest_edge / 2 {
+            // Boxes are cut short at the far faces of the frame
+            let counts = shape.map(|n| n.div_ceil(size));
+            for bx in 0..counts[0] {
+                for by in 0..counts[1] {
+                    for bz in 0..counts[2] {
+                        let lo = [bx, by, bz].map(|b| b * size);
+                        let hi = std::array::from_fn(|i| (lo[i] + size).min(shape[i]));
+                        let volume: usize = (0..3).map(|i| hi[i] - lo[i]).product();
+                        let fraction = flooded.mass(lo, hi) as f64 / volume as f64;
+                        let depth = (lo[axis] + hi[axis]) as f64 / 2.0;
+                        weighted += fraction * depth;
+                        pressure += depth;
+                    }
+                }
+            }
+            size *= 2;
+        }
+
+        weighted / pressure
+    })
+}
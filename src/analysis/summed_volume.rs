@@ -0,0 +1,41 @@
+use ndarray::{s, Array3, Axis};
+
+/// Summed-volume table of the occupancy of a frame, giving the number of
+/// occupied voxels in any box in constant time.
+pub struct SummedVolume(Array3<u64>);
+
+impl SummedVolume {
+    pub fn new(occupancy: &Array3<bool>) -> Self {
+        let (x_max, y_max, z_max) = occupancy.dim();
+        let mut table = Array3::zeros((x_max + 1, y_max + 1, z_max + 1));
+        table
+            .slice_mut(s![1.., 1.., 1..])
+            .assign(&occupancy.mapv(u64::from));
+        for axis in 0..3 {
+            table.accumulate_axis_inplace(Axis(axis), |&prev, cur| *cur += prev);
+        }
+
+        Self(table)
+    }
+
+    /// Size of the frame along X, Y and Z
+    pub fn shape(&self) -> [usize; 3] {
+        let shape = self.0.shape();
+        [shape[0] - 1, shape[1] - 1, shape[2] - 1]
+    }
+
+    /// Number of occupied voxels from `lo` up to, but not including, `hi`.
+    pub fn mass(&self, lo: [usize; 3], hi: [usize; 3]) -> u64 {
+        let at = |x, y, z| self.0[[x, y, z]] as i64;
+        let mass = at(hi[0], hi[1], hi[2])
+            - at(lo[0], hi[1], hi[2])
+            - at(hi[0], lo[1], hi[2])
+            - at(hi[0], hi[1], lo[2])
+            + at(lo[0], lo[1], hi[2])
+            + at(lo[0], hi[1], lo[2])
+            + at(hi[0], lo[1], lo[2])
+            - at(lo[0], lo[1], lo[2]);
+
+        mass as u64
+    }
+}
@@ -0,0 +1,256 @@
+use clap::ValueEnum;
+use ndarray::{Array3, ArrayView3};
+
+use crate::input::Voxel;
+
+/// How a voxel value is compared with the threshold
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Comparison {
+    // At least the threshold
+    Ge,
+    // Above the threshold
+    Gt,
+    // At most the threshold
+    Le,
+    // Below the threshold
+    Lt,
+    // Equal to the threshold
+    Eq,
+    // Other than the threshold
+    Ne,
+}
+
+impl Comparison {
+    fn holds(self, value: f64, cut: f64) -> bool {
+        match self {
+            Comparison::Ge => value >= cut,
+            Comparison::Gt => value > cut,
+            Comparison::Le => value <= cut,
+            Comparison::Lt => value < cut,
+            Comparison::Eq => value == cut,
+            Comparison::Ne => value != cut,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Ge => ">=",
+            Comparison::Gt => ">",
+            Comparison::Le => "<=",
+            Comparison::Lt => "<",
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+        }
+    }
+}
+
+/// Value voxels are compared with
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub enum Cut {
+    Value(f64),
+    /// Chosen for every frame by Otsu's method
+    Otsu,
+}
+
+impl Cut {
+    /// Parses a number, or `otsu`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim() {
+            "otsu" => Ok(Cut::Otsu),
+            value => value
+                .parse()
+                .map(Cut::Value)
+                .map_err(|e| format!("{s:?}: {e}; expected a number or otsu")),
+        }
+    }
+}
+
+/// Which voxel values belong to the aggregate, instead of the built-in rule
+#[derive(Clone, Debug)]
+pub enum Threshold {
+    /// Values that compare so with the cut
+    Compare(Comparison, Cut),
+    /// Values in any of the inclusive ranges, such as several labels of a
+    /// label field
+    Keep(Vec<[f64; 2]>),
+}
+
+impl Threshold {
+    /// Which of the voxels belong to the aggregate
+    pub fn apply<T: Voxel>(&self, voxels: ArrayView3<T>) -> Array3<bool> {
+        match self {
+            Threshold::Compare(comparison, cut) => {
+                let cut = match cut {
+                    Cut::Value(value) => *value,
+                    Cut::Otsu => otsu(voxels.iter().map(|v| v.value())),
+                };
+                voxels.map(|v| comparison.holds(v.value(), cut))
+            }
+            Threshold::Keep(ranges) => voxels.map(|v| {
+                ranges
+                    .iter()
+                    .any(|&[lo, hi]| (lo..=hi).contains(&v.value()))
+            }),
+        }
+    }
+
+    /// The rule, for the provenance columns
+    pub fn rule(&self) -> String {
+        match self {
+            Threshold::Compare(comparison, Cut::Value(value)) => {
+                format!("value {} {value}", comparison.symbol())
+            }
+            Threshold::Compare(comparison, Cut::Otsu) => {
+                format!("value {} Otsu cut of the frame", comparison.symbol())
+            }
+            Threshold::Keep(ranges) => {
+                let ranges: Vec<String> = ranges
+                    .iter()
+                    .map(|&[lo, hi]| {
+                        if lo == hi {
+                            lo.to_string()
+                        } else {
+                            format!("{lo}..={hi}")
+                        }
+                    })
+                    .collect();
+                format!("value in {}", ranges.join(" or "))
+            }
+        }
+    }
+}
+
+/// Otsu's cut of the values: of the 256 bins of the histogram between the
+/// smallest and the largest value, the lower edge of the first bin of the
+/// upper class, where splitting the histogram in two classes maximizes the
+/// variance between them. NaN values are left out, and without any other
+/// the cut is NaN; a uniform frame is cut at its value.
+fn otsu(values: impl Iterator<Item = f64> + Clone) -> f64 {
+    const BINS: usize = 256;
+    let values = values.filter(|v| !v.is_nan());
+    let (lo, hi) = values
+        .clone()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if lo > hi {
+        return f64::NAN;
+    }
+    if lo == hi {
+        // A uniform frame has no two classes to tell apart
+        return hi;
+    }
+    let width = (hi - lo) / BINS as f64;
+    let mut histogram = [0u64; BINS];
+    for v in values {
+        histogram[(((v - lo) / width) as usize).min(BINS - 1)] += 1;
+    }
+
+    let total: u64 = histogram.iter().sum();
+    let weighted_total: f64 = (0..BINS).map(|i| i as f64 * histogram[i] as f64).sum();
+    let (mut below, mut weighted_below) = (0u64, 0.0);
+    let (mut best, mut best_variance) = (0, f64::NEG_INFINITY);
+    for (i, &count) in histogram.iter().enumerate().take(BINS - 1) {
+        below += count;
+        weighted_below += i as f64 * count as f64;
+        let above = total - below;
+        if below == 0 || above == 0 {
+            continue;
+        }
+        let mean_below = weighted_below / below as f64;
+        let mean_above = (weighted_total - weighted_below) / above as f64;
+        let variance = below as f64 * above as f64 * (mean_below - mean_above).powi(2);
+        if variance > best_variance {
+            (best, best_variance) = (i, variance);
+        }
+    }
+
+    lo + (best + 1) as f64 * width
+}
+
+/// Parses a value, or an inclusive range of values such as `3..=7`, for
+/// `Threshold::Keep`.
+pub fn parse_range(s: &str) -> Result<[f64; 2], String> {
+    let number = |n: &str| n.trim().parse::<f64>().map_err(|e| format!("{n:?}: {e}"));
+    match s.split_once("..=") {
+        Some((lo, hi)) => {
+            let (lo, hi) = (number(lo)?, number(hi)?);
+            if lo > hi {
+                return Err(format!("{s:?}: the range is empty"));
+            }
+            Ok([lo, hi])
+        }
+        None => number(s).map(|value| [value, value]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array3;
+
+    use super::*;
+
+    fn kept(threshold: &Threshold, values: &[f64]) -> usize {
+        let frame = Array3::from_shape_vec((values.len(), 1, 1), values.to_vec()).unwrap();
+        threshold.apply(frame.view()).iter().filter(|&&v| v).count()
+    }
+
+    #[test]
+    fn otsu_cuts_at_the_edge_of_the_upper_class() {
+        // With 256 bins of width 1, the lower class ends in bin 2
+        let values = [0.0, 1.0, 2.0, 200.0, 255.0, 256.0];
+        assert_eq!(otsu(values.into_iter()), 3.0);
+        let threshold = Threshold::Compare(Comparison::Ge, Cut::Otsu);
+        assert_eq!(kept(&threshold, &values), 3);
+    }
+
+    #[test]
+    fn otsu_cuts_a_uniform_frame_at_its_value() {
+        let values = [5.0; 4];
+        assert_eq!(otsu(values.into_iter()), 5.0);
+        assert_eq!(
+            kept(&Threshold::Compare(Comparison::Ge, Cut::Otsu), &values),
+            4
+        );
+        assert_eq!(
+            kept(&Threshold::Compare(Comparison::Gt, Cut::Otsu), &values),
+            0
+        );
+    }
+
+    #[test]
+    fn otsu_leaves_nan_out() {
+        let values = [f64::NAN, 0.0, 1.0, 2.0, 200.0, 255.0, 256.0];
+        assert_eq!(otsu(values.into_iter()), 3.0);
+        assert_eq!(
+            kept(&Threshold::Compare(Comparison::Ge, Cut::Otsu), &values),
+            3
+        );
+        assert!(otsu([f64::NAN; 3].into_iter()).is_nan());
+    }
+
+    #[test]
+    fn comparisons_and_ranges() {
+        let values = [0.0, 1.0, 2.0, 3.0];
+        let compare = |comparison, value| Threshold::Compare(comparison, Cut::Value(value));
+        assert_eq!(kept(&compare(Comparison::Ge, 2.0), &values), 2);
+        assert_eq!(kept(&compare(Comparison::Lt, 2.0), &values), 2);
+        assert_eq!(kept(&compare(Comparison::Ne, 2.0), &values), 3);
+        assert_eq!(
+            kept(&Threshold::Keep(vec![[0.0, 0.0], [2.0, 3.0]]), &values),
+            3
+        );
+    }
+
+    #[test]
+    fn parses_cuts_and_ranges() {
+        assert!(matches!(Cut::parse(" otsu"), Ok(Cut::Otsu)));
+        assert!(matches!(Cut::parse("0.5"), Ok(Cut::Value(v)) if v == 0.5));
+        assert!(Cut::parse("half").is_err());
+        assert_eq!(parse_range("3..=7"), Ok([3.0, 7.0]));
+        assert_eq!(parse_range("-1"), Ok([-1.0, -1.0]));
+        assert!(parse_range("7..=3").is_err());
+        assert!(parse_range("3..7").is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+use clap::ValueEnum;
+use ndarray::{Array3, Axis};
+
+/// Which voxels a path across the frame may run through
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    // The empty voxels
+    Pores,
+    // The occupied voxels
+    Aggregate,
+}
+
+/// Geometric tortuosity of `phase` across X, Y and Z: the mean length of the
+/// shortest face-connected paths through it from the low face of the frame to
+/// the voxels of the high face they reach, over the straight distance between
+/// the faces. 1 for straight channels, larger for winding ones, and NaN when
+/// no path crosses the frame or the frame is flat along the axis.
+pub fn tortuosity(occupancy: &Array3<bool>, phase: Phase) -> [f64; 3] {
+    let open = match phase {
+        Phase::Pores => occupancy.mapv(|v| !v),
+        Phase::Aggregate => occupancy.clone(),
+    };
+    let (x_max, y_max, z_max) = open.dim();
+    let shape = [x_max, y_max, z_max];
+
+    std::array::from_fn(|axis| {
+        if shape[axis] < 2 {
+            return f64::NAN;
+        }
+        // Breadth-first search from every open voxel of the low face at once
+        let mut distances = Array3::from_elem(open.dim(), u32::MAX);
+        let mut queue = VecDeque::new();
+        for ((x, y, z), &v) in open.indexed_iter() {
+            if v && [x, y, z][axis] == 0 {
+                distances[[x, y, z]] = 0;
+                queue.push_back([x, y, z]);
+            }
+        }
+        while let Some(voxel) = queue.pop_front() {
+            let distance = distances[voxel];
+            for i in 0..3 {
+                for step in [-1isize, 1] {
+                    let Some(c) = voxel[i].checked_add_signed(step) else {
+                        continue;
+                    };
+                    if c >= shape[i] {
+                        continue;
+                    }
+                    let mut neighbour = voxel;
+                    neighbour[i] = c;
+                    if open[neighbour] && distances[neighbour] == u32::MAX {
+                        distances[neighbour] = distance + 1;
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+        }
+
+        let reached: Vec<f64> = distances
+            .index_axis(Axis(axis), shape[axis] - 1)
+            .iter()
+            .filter(|&&d| d != u32::MAX)
+            .map(|&d| d as f64)
+            .collect();
+        let mean = reached.iter().sum::<f64>() / reached.len() as f64;
+        mean / (shape[axis] - 1) as f64
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    #[test]
+    fn straight_channel_is_not_tortuous() {
+        let mut occupancy = Array3::from_elem((8, 8, 8), true);
+        occupancy.slice_mut(s![.., 3..5, 3..5]).fill(false);
+        let [x, y, z] = tortuosity(&occupancy, Phase::Pores);
+        assert_eq!(x, 1.0);
+        // The channel does not reach the faces across Y and Z
+        assert!(y.is_nan() && z.is_nan());
+        // The solid around it runs straight along the channel, and winds
+        // around it across
+        let [x, y, z] = tortuosity(&occupancy, Phase::Aggregate);
+        assert_eq!(x, 1.0);
+        assert!(y > 1.0 && z > 1.0);
+    }
+
+    #[test]
+    fn stepped_channel_is_longer_than_the_frame() {
+        let mut occupancy = Array3::from_elem((8, 8, 8), true);
+        occupancy.slice_mut(s![..4, 1, 1]).fill(false);
+        occupancy.slice_mut(s![3.., 2, 1]).fill(false);
+        // 7 steps along X and one across
+        assert_eq!(tortuosity(&occupancy, Phase::Pores)[0], 8.0 / 7.0);
+    }
+}
@@ -0,0 +1,147 @@
+use fractal_analysis::*;
+use ndarray::{s, Array3, ArrayView3};
+use rayon::prelude::*;
+
+use super::{Estimate, FitOptions, Padding};
+use crate::input::{Frame, Voxel};
+
+pub fn estimate(frame: &Frame, padding: Option<Padding>, options: &FitOptions) -> Estimate {
+    let frame = match frame {
+        Frame::Bool(f) => binarize(f.view(), padding),
+        Frame::U8(f) => binarize(f.view(), padding),
+        Frame::U16(f) => binarize(f.view(), padding),
+        Frame::I32(f) => binarize(f.view(), padding),
+        Frame::F32(f) => binarize(f.view(), padding),
+        Frame::F64(f) => binarize(f.view(), padding),
+    };
+    if frame.shape()[2] == 1 {
+        return calculate_fractal_dimension_2d(frame, options);
+    }
+    calculate_fractal_dimension_3d(frame, options)
+}
+
+/// The frame as the values of the Morton keys, embedded in the empty box of
+/// `padding`, if any.
+fn binarize<T: Voxel>(frame: ArrayView3<T>, padding: Option<Padding>) -> Array3<u8> {
+    // The keys are computed from the flat buffer, which must be in C order
+    let frame = frame.map(threshold).as_standard_layout().into_owned();
+    let Some(padding) = padding else {
+        return frame;
+    };
+    let (x, y, z) = frame.dim();
+    let [x_max, y_max, z_max] = padding.shape([x, y, z]);
+    let mut padded = Array3::zeros((x_max.max(x), y_max.max(y), z_max.max(z)));
+    padded.slice_mut(s![..x, ..y, ..z]).assign(&frame);
+    padded
+}
+
+fn threshold<T: Voxel>(x: &T) -> u8 {
+    if !x.is_occupied() {
+        return u8::MIN;
+    }
+    u8::MAX
+}
+
+fn calculate_fractal_dimension_3d(frame: Array3<u8>, options: &FitOptions) -> Estimate {
+    let occupied_voxels = frame.iter().filter(|&&v| v == u8::MAX).count();
+    let s = frame.shape();
+    let x_max = s[0];
+    let y_max = s[1];
+    let z_max = s[2];
+    let buf = frame.into_raw_vec().into_par_iter().enumerate();
+
+    let get_key_from_sample = |(flattened_coord, val): (usize, u8)| -> u32 {
+        // the frame is in standard (C) order, so the last axis varies fastest
+        let x = flattened_coord / (y_max * z_max);
+        let y = flattened_coord / z_max % y_max;
+        let z = flattened_coord % z_max;
+
+        let normalise_as_u8 = |q, max, min| ((q - min) * 256 / max) as u8;
+        let norm_x = |x| normalise_as_u8(x, x_max, 0);
+        let norm_y = |y| normalise_as_u8(y, y_max, 0);
+        let norm_z = |z| normalise_as_u8(z, z_max, 0);
+
+        let arr = [norm_x(x), norm_y(y), norm_z(z), val];
+        lindel::morton_encode(arr)
+    };
+
+    let clzs = get_clzs_par(buf, get_key_from_sample).collect::<Vec<_>>();
+    let (tmp, lacun) = get_results_from_clzs(clzs.into_iter());
+    let sample_size = x_max * y_max * z_max;
+    let (_, log_counts, lacunarities) = finalise_results::<32>(tmp, lacun, sample_size, 8);
+
+    fit(
+        [x_max, y_max, z_max],
+        &log_counts,
+        &lacunarities,
+        occupied_voxels,
+        options,
+    )
+}
+
+/// The same for a planar frame of X×Y×1 voxels, with 2D Morton keys of the
+/// two in-plane coordinates and the value.
+fn calculate_fractal_dimension_2d(frame: Array3<u8>, options: &FitOptions) -> Estimate {
+    let occupied_voxels = frame.iter().filter(|&&v| v == u8::MAX).count();
+    let s = frame.shape();
+    let x_max = s[0];
+    let y_max = s[1];
+    let buf = frame.into_raw_vec().into_par_iter().enumerate();
+
+    let get_key_from_sample = |(flattened_coord, val): (usize, u8)| -> u32 {
+        let x = flattened_coord / y_max;
+        let y = flattened_coord % y_max;
+
+        let normalise_as_u8 = |q, max| (q * 256 / max) as u8;
+        let key: u32 =
+            lindel::morton_encode([normalise_as_u8(x, x_max), normalise_as_u8(y, y_max), val]);
+        // The 24 bits of the key go on top, so that its leading zeros count
+        // from its first bit
+        key << 8
+    };
+
+    let clzs = get_clzs_par(buf, get_key_from_sample).collect::<Vec<_>>();
+    let (tmp, lacun) = get_results_from_clzs::<24>(clzs.into_iter());
+    let sample_size = x_max * y_max;
+    let (_, log_counts, lacunarities) = finalise_results::<24>(tmp, lacun, sample_size, 8);
+
+    fit(
+        [x_max, y_max, 1],
+        &log_counts,
+        &lacunarities,
+        occupied_voxels,
+        options,
+    )
+}
+
+/// Fits the counts at every level of the Morton keys of a frame of `shape`.
+fn fit(
+    shape: [usize; 3],
+    log_counts: &[f64],
+    lacunarities: &[f64],
+    occupied_voxels: usize,
+    options: &FitOptions,
+) -> Estimate {
+    let sample_size: usize = shape.iter().product();
+    // finalise_results fits the dimension up to the first box size at which
+    // the count comes close to the sample size
+    let cap = (sample_size as f64).log2();
+    let fitted = log_counts
+        .iter()
+        .position(|&x| x > 0.9 * cap)
+        .unwrap_or(log_counts.len());
+
+    // Level k splits the longest edge into 2^(k + 1) boxes
+    let longest_edge = shape.into_iter().max().unwrap_or(1) as f64;
+    let scales: Vec<f64> = (0..fitted)
+        .map(|level| longest_edge / 2f64.powi(level as i32 + 1))
+        .collect();
+
+    Estimate::from_fit(
+        &scales,
+        &log_counts[..fitted],
+        &lacunarities[..fitted],
+        occupied_voxels,
+        options,
+    )
+}
@@ -0,0 +1,215 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use ::arrow_array::cast::AsArray;
+use ::arrow_array::types::{Float64Type, Int64Type};
+use ::arrow_array::{Array, RecordBatch};
+use ::arrow_ipc::reader::{FileReader, StreamReader};
+use ::arrow_schema::{ArrowError, DataType};
+use anyhow::{anyhow, Result};
+use ndarray::{Array3, CowArray};
+
+use super::points::OCCUPIED;
+use super::{is_stdin, parse_dims, saturate, Frame, FrameSource};
+
+/// Magic number at the start of the Arrow IPC file (as opposed to stream) format
+const FILE_MAGIC: &[u8] = b"ARROW1";
+const SHAPE_KEY: &str = "shape";
+const COORD_COLUMNS: [&str; 3] = ["x", "y", "z"];
+const VALUE_COLUMN: &str = "value";
+
+type Batches = Box<dyn Iterator<Item = Result<RecordBatch, ArrowError>>>;
+
+/// Frames from an Arrow IPC stream or file, one record batch per frame, read
+/// as they arrive so that a running simulation can pipe them in. A batch
+/// either holds the occupied voxels of the frame in integer `x`, `y` and `z`
+/// columns (with an optional `value` column, like Parquet voxel tables), or
+/// the dense frame in C order in its single column. The frame size (X,Y,Z)
+/// comes from the `shape` entry of the schema metadata or from `shape`.
+pub struct ArrowFrames {
+    file_path: PathBuf,
+    shape: [usize; 3],
+    batches: Batches,
+    /// Most recently read batch, which holds frame `read - 1`
+    current: Option<RecordBatch>,
+    read: usize,
+}
+
+impl ArrowFrames {
+    pub fn open(file_path: impl AsRef<Path>, shape: Option<[usize; 3]>) -> Result<Self> {
+        let file_path = file_path.as_ref();
+        let (batches, metadata_shape) = Self::read_batches(file_path)?;
+        let shape = match metadata_shape {
+            Some(dims) => parse_dims::<3>(&dims).map_err(|e| anyhow!("schema {SHAPE_KEY}: {e}"))?,
+            None => shape.ok_or_else(|| {
+                anyhow!("the Arrow schema has no {SHAPE_KEY} metadata; pass --shape")
+            })?,
+        };
+
+        Ok(Self {
+            file_path: file_path.to_path_buf(),
+            shape,
+            batches,
+            current: None,
+            read: 0,
+        })
+    }
+
+    /// Starts reading the batches, returning them with the `shape` metadata
+    /// of the schema.
+    fn read_batches(file_path: &Path) -> Result<(Batches, Option<String>)> {
+        if is_stdin(file_path) {
+            let reader = StreamReader::try_new(std::io::stdin(), None)?;
+            let shape = reader.schema().metadata().get(SHAPE_KEY).cloned();
+            return Ok((Box::new(reader), shape));
+        }
+        let mut file = BufReader::new(File::open(file_path)?);
+        let mut magic = [0; FILE_MAGIC.len()];
+        let is_file_format = file.read_exact(&mut magic).is_ok() && magic == FILE_MAGIC;
+        file.rewind()?;
+        if is_file_format {
+            let reader = FileReader::try_new(file, None)?;
+            let shape = reader.schema().metadata().get(SHAPE_KEY).cloned();
+            return Ok((Box::new(reader), shape));
+        }
+        let reader = StreamReader::try_new(file, None)?;
+        let shape = reader.schema().metadata().get(SHAPE_KEY).cloned();
+
+        Ok((Box::new(reader), shape))
+    }
+
+    /// Reads the next batch, returning whether there was one.
+    fn advance(&mut self) -> Result<bool> {
+        match self.batches.next().transpose()? {
+            Some(batch) => {
+                (self.current, self.read) = (Some(batch), self.read + 1);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn decode<'a>(&self, batch: &RecordBatch) -> Result<Frame<'a>> {
+        let [nx, ny, nz] = self.shape;
+        let schema = batch.schema();
+        let is_sparse = COORD_COLUMNS
+            .iter()
+            .all(|c| schema.column_with_name(c).is_some());
+        if !is_sparse {
+            let [column] = batch.columns() else {
+                return Err(anyhow!(
+                    "expected x, y and z columns or a single column of voxel values"
+                ));
+            };
+            if column.len() != nx * ny * nz {
+                return Err(anyhow!(
+                    "a batch of {} values does not fill a {:?} frame",
+                    column.len(),
+                    self.shape
+                ));
+            }
+            if column.data_type() == &DataType::Boolean {
+                let values = column.as_boolean().iter().map(|v| v.unwrap_or(false));
+                let frame = Array3::from_shape_vec((nx, ny, nz), values.collect())?;
+                return Ok(CowArray::from(frame).into());
+            }
+            let column = ::arrow_cast::cast(column, &DataType::Float64)?;
+            let values = column.as_primitive::<Float64Type>().iter();
+            let frame =
+                Array3::from_shape_vec((nx, ny, nz), values.map(|v| v.unwrap_or(0.0)).collect())?;
+            return Ok(CowArray::from(frame).into());
+        }
+
+        let int_column = |name: &str| -> Result<Option<_>> {
+            let Some(column) = batch.column_by_name(name) else {
+                return Ok(None);
+            };
+            if column.null_count() > 0 {
+                return Err(anyhow!("{name} holds nulls"));
+            }
+            let column =
+                ::arrow_cast::cast(column, &DataType::Int64).map_err(|e| anyhow!("{name}: {e}"))?;
+            Ok(Some(column.as_primitive::<Int64Type>().clone()))
+        };
+        let coords = COORD_COLUMNS
+            .iter()
+            .map(|c| Ok(int_column(c)?.unwrap()))
+            .collect::<Result<Vec<_>>>()?;
+        // Floating point values are kept as they are, integers are narrowed to i32
+        let float_values = match batch.column_by_name(VALUE_COLUMN) {
+            Some(column) if column.data_type().is_floating() => {
+                if column.null_count() > 0 {
+                    return Err(anyhow!("{VALUE_COLUMN} holds nulls"));
+                }
+                let column = ::arrow_cast::cast(column, &DataType::Float64)?;
+                Some(column.as_primitive::<Float64Type>().clone())
+            }
+            _ => None,
+        };
+        let values = match float_values {
+            Some(_) => None,
+            None => int_column(VALUE_COLUMN)?,
+        };
+        let mut frame = Array3::zeros((nx, ny, nz));
+        let mut float_frame = float_values.as_ref().map(|_| Array3::zeros((nx, ny, nz)));
+        for row in 0..batch.num_rows() {
+            let mut idx = [0; 3];
+            for (axis, (c, n)) in coords.iter().zip(self.shape).enumerate() {
+                idx[axis] = usize::try_from(c.value(row))
+                    .ok()
+                    .filter(|&i| i < n)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "{} = {} lies outside the {:?} frame",
+                            COORD_COLUMNS[axis],
+                            c.value(row),
+                            self.shape
+                        )
+                    })?;
+            }
+            match (&mut float_frame, &float_values) {
+                (Some(float_frame), Some(v)) => float_frame[idx] = v.value(row),
+                _ => frame[idx] = values.as_ref().map_or(OCCUPIED, |v| saturate(v.value(row))),
+            }
+        }
+
+        Ok(match float_frame {
+            Some(float_frame) => CowArray::<f64, _>::from(float_frame).into(),
+            None => CowArray::from(frame).into(),
+        })
+    }
+}
+
+impl FrameSource for ArrowFrames {
+    /// Number of frames read so far; the stream may hold more.
+    fn frame_count(&self) -> usize {
+        self.read
+    }
+
+    fn has_frame(&mut self, index: usize) -> Result<bool> {
+        while self.read <= index {
+            if !self.advance()? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        if index + 1 < self.read {
+            if is_stdin(&self.file_path) {
+                return Err(anyhow!("cannot go back to frame {index} of standard input"));
+            }
+            (self.batches, self.current, self.read) =
+                (Self::read_batches(&self.file_path)?.0, None, 0);
+        }
+        if !self.has_frame(index)? {
+            return Err(anyhow!("the Arrow stream ends before frame {index}"));
+        }
+        let batch = self.current.as_ref().unwrap();
+
+        self.decode(batch)
+    }
+}
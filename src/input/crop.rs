@@ -0,0 +1,108 @@
+use anyhow::{anyhow, Result};
+use ndarray::{s, CowArray, Ix3};
+
+use super::{Frame, FrameSource, Voxel};
+
+/// The frames of another source cut down to a region of interest, from
+/// `region[i][0]` up to, but not including, `region[i][1]` along X, Y and Z.
+pub struct CroppedFrames {
+    frames: Box<dyn FrameSource>,
+    region: [[usize; 2]; 3],
+}
+
+impl CroppedFrames {
+    pub fn new(frames: Box<dyn FrameSource>, region: [[usize; 2]; 3]) -> Self {
+        Self { frames, region }
+    }
+}
+
+impl FrameSource for CroppedFrames {
+    fn frame_count(&self) -> usize {
+        self.frames.frame_count()
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        let region = self.region;
+        let frame = self.frames.frame(index)?;
+        let shape = frame.shape();
+        if let Some(axis) = (0..3).find(|&i| region[i][1] > shape[i]) {
+            return Err(anyhow!(
+                "--crop reaches {} along {}, but frame {index} is only {} voxels across",
+                region[axis][1],
+                ["X", "Y", "Z"][axis],
+                shape[axis]
+            ));
+        }
+        Ok(match &frame {
+            Frame::Bool(f) => crop(f, region),
+            Frame::U8(f) => crop(f, region),
+            Frame::U16(f) => crop(f, region),
+            Frame::I32(f) => crop(f, region),
+            Frame::F32(f) => crop(f, region),
+            Frame::F64(f) => crop(f, region),
+        })
+    }
+
+    fn has_frame(&mut self, index: usize) -> Result<bool> {
+        self.frames.has_frame(index)
+    }
+}
+
+fn crop<'a, T: Voxel>(frame: &CowArray<'_, T, Ix3>, [x, y, z]: [[usize; 2]; 3]) -> Frame<'a> {
+    let region = frame.slice(s![x[0]..x[1], y[0]..y[1], z[0]..z[1]]);
+    CowArray::from(region.to_owned()).into()
+}
+
+/// Parses a region of interest such as `0:64,0:64,16:48`.
+pub fn parse_region(s: &str) -> Result<[[usize; 2]; 3], String> {
+    let ranges = s
+        .split(',')
+        .map(|range| {
+            let (lo, hi) = range
+                .split_once(':')
+                .ok_or_else(|| format!("{range:?}: expected a range such as 0:64"))?;
+            let number = |n: &str| n.trim().parse::<usize>().map_err(|e| format!("{n:?}: {e}"));
+            let (lo, hi) = (number(lo)?, number(hi)?);
+            if lo >= hi {
+                return Err(format!("{range:?}: the range is empty"));
+            }
+            Ok([lo, hi])
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    ranges
+        .try_into()
+        .map_err(|r: Vec<_>| format!("expected ranges along X, Y and Z, got {}", r.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array4;
+
+    use super::*;
+
+    #[test]
+    fn regions_parse() {
+        assert_eq!(
+            parse_region("0:64, 0:64,16:48"),
+            Ok([[0, 64], [0, 64], [16, 48]])
+        );
+        assert!(parse_region("0:64,0:64").is_err());
+        assert!(parse_region("0:64,8:8,0:1").is_err());
+        assert!(parse_region("0-64,0:64,0:64").is_err());
+    }
+
+    #[test]
+    fn frames_are_cut_to_the_region() {
+        let voxels =
+            Array4::from_shape_fn((1, 4, 4, 4), |(_, x, y, z)| (16 * x + 4 * y + z) as i32);
+        let mut frames = CroppedFrames::new(Box::new(voxels), [[1, 3], [0, 4], [2, 3]]);
+        let Frame::I32(frame) = frames.frame(0).unwrap() else {
+            panic!("cropping keeps the element type");
+        };
+        assert_eq!(frame.dim(), (2, 4, 1));
+        assert_eq!(frame[[0, 0, 0]], 18);
+
+        let mut frames = CroppedFrames::new(frames.frames, [[0, 5], [0, 4], [0, 4]]);
+        assert!(frames.frame(0).is_err());
+    }
+}
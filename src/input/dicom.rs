@@ -0,0 +1,172 @@
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use dicom_dictionary_std::tags;
+use dicom_object::{DefaultDicomObject, InMemDicomObject, Tag};
+use ndarray::Array4;
+
+use super::sorted_files_with_extension;
+
+/// Offset of the `DICM` magic, after the preamble of a DICOM file
+const MAGIC_OFFSET: usize = 128;
+
+/// Whether `path` starts like a DICOM file, whatever its extension.
+pub fn is_dicom(path: &Path) -> bool {
+    let mut head = [0; MAGIC_OFFSET + 4];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut head))
+        .is_ok_and(|_| &head[MAGIC_OFFSET..] == b"DICM")
+}
+
+/// Loads a DICOM series: a directory with one slice per file, or a single
+/// (possibly multi-frame) file. Stored values are rescaled to modality units
+/// (e.g. Hounsfield units) with the rescale slope and intercept. Slices are
+/// ordered along the slice normal, or by instance number if they carry no
+/// position, and grouped into frames by their temporal position if the
+/// series has one. Frames are (slices, rows, columns).
+pub fn load_aggregate_data_dicom(path: impl AsRef<Path>) -> Result<Array4<f32>> {
+    let path = path.as_ref();
+    let files = if path.is_dir() {
+        let files = sorted_files_with_extension(path, &[])?;
+        files.into_iter().filter(|f| is_dicom(f)).collect()
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut slices = Vec::new();
+    for file in &files {
+        let obj = dicom_object::open_file(file).map_err(|e| anyhow!("{}: {e}", file.display()))?;
+        slices.extend(read_slices(&obj).map_err(|e| anyhow!("{}: {e}", file.display()))?);
+    }
+    let first = slices
+        .first()
+        .ok_or_else(|| anyhow!("no DICOM files in {}", path.display()))?;
+    let (rows, cols) = (first.rows, first.cols);
+    if slices.iter().any(|s| (s.rows, s.cols) != (rows, cols)) {
+        return Err(anyhow!("slices of {} differ in size", path.display()));
+    }
+    // Sorting is stable, so slices without a position keep their file order
+    slices.sort_by(|a, b| {
+        a.temporal_position
+            .cmp(&b.temporal_position)
+            .then(a.position.total_cmp(&b.position))
+    });
+
+    let mut frames = 1;
+    for pair in slices.windows(2) {
+        frames += (pair[0].temporal_position != pair[1].temporal_position) as usize;
+    }
+    if slices.len() % frames != 0 {
+        return Err(anyhow!(
+            "{} slices do not split evenly into {frames} temporal positions",
+            slices.len()
+        ));
+    }
+    let depth = slices.len() / frames;
+    let data = slices.into_iter().flat_map(|s| s.values).collect();
+
+    Ok(Array4::from_shape_vec((frames, depth, rows, cols), data)?)
+}
+
+struct Slice {
+    rows: usize,
+    cols: usize,
+    /// Temporal position identifier, 0 if absent
+    temporal_position: i64,
+    /// Distance along the slice normal, or instance number
+    position: f64,
+    values: Vec<f32>,
+}
+
+/// Decodes the rescaled slices of one file; multi-frame files hold several.
+fn read_slices(obj: &DefaultDicomObject) -> Result<Vec<Slice>> {
+    let int = |tag: Tag| -> Result<Option<i64>> {
+        match obj.element_opt(tag)? {
+            Some(e) => Ok(Some(e.to_int::<i64>()?)),
+            None => Ok(None),
+        }
+    };
+    let float = |tag: Tag| -> Result<Option<f64>> {
+        match obj.element_opt(tag)? {
+            Some(e) => Ok(Some(e.to_float64()?)),
+            None => Ok(None),
+        }
+    };
+    let rows = int(tags::ROWS)?.ok_or_else(|| anyhow!("no Rows attribute"))? as usize;
+    let cols = int(tags::COLUMNS)?.ok_or_else(|| anyhow!("no Columns attribute"))? as usize;
+    if int(tags::SAMPLES_PER_PIXEL)?.unwrap_or(1) != 1 {
+        return Err(anyhow!("expected grayscale images"));
+    }
+    let bits = int(tags::BITS_ALLOCATED)?.unwrap_or(16);
+    let signed = int(tags::PIXEL_REPRESENTATION)?.unwrap_or(0) == 1;
+    let count = int(tags::NUMBER_OF_FRAMES)?.unwrap_or(1).max(1) as usize;
+    let slope = float(tags::RESCALE_SLOPE)?.unwrap_or(1.0);
+    let intercept = float(tags::RESCALE_INTERCEPT)?.unwrap_or(0.0);
+    let temporal_position = int(tags::TEMPORAL_POSITION_IDENTIFIER)?.unwrap_or(0);
+    let position = match slice_location(obj)? {
+        Some(location) => location,
+        None => int(tags::INSTANCE_NUMBER)?.unwrap_or(0) as f64,
+    };
+
+    let pixels = obj.element(tags::PIXEL_DATA)?;
+    let bytes = match pixels.value().primitive() {
+        Some(value) => value.to_bytes(),
+        None => return Err(anyhow!("compressed pixel data is not supported")),
+    };
+    let size = match bits {
+        8 | 16 | 32 => bits as usize / 8,
+        _ => return Err(anyhow!("unsupported BitsAllocated {bits}")),
+    };
+    let len = rows * cols * count * size;
+    let bytes = bytes
+        .get(..len)
+        .ok_or_else(|| anyhow!("pixel data is shorter than {count} {rows}x{cols} images"))?;
+    let values: Vec<f32> = bytes
+        .chunks_exact(size)
+        .map(|b| {
+            let stored = match (size, signed) {
+                (1, false) => b[0] as f64,
+                (1, true) => b[0] as i8 as f64,
+                (2, false) => u16::from_le_bytes([b[0], b[1]]) as f64,
+                (2, true) => i16::from_le_bytes([b[0], b[1]]) as f64,
+                (_, false) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                (_, true) => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            };
+            (stored * slope + intercept) as f32
+        })
+        .collect();
+
+    Ok(values
+        .chunks_exact(rows * cols)
+        .enumerate()
+        .map(|(i, values)| Slice {
+            rows,
+            cols,
+            temporal_position,
+            position: position + i as f64,
+            values: values.to_vec(),
+        })
+        .collect())
+}
+
+/// Projects the image position onto the slice normal, which is the cross
+/// product of the row and column directions of the image orientation.
+fn slice_location(obj: &InMemDicomObject) -> Result<Option<f64>> {
+    let (Some(position), Some(orientation)) = (
+        obj.element_opt(tags::IMAGE_POSITION_PATIENT)?,
+        obj.element_opt(tags::IMAGE_ORIENTATION_PATIENT)?,
+    ) else {
+        return Ok(None);
+    };
+    let (p, o) = (
+        position.to_multi_float64()?,
+        orientation.to_multi_float64()?,
+    );
+    let ([px, py, pz], [rx, ry, rz, cx, cy, cz]) = (&p[..], &o[..]) else {
+        return Ok(None);
+    };
+    let normal = [ry * cz - rz * cy, rz * cx - rx * cz, rx * cy - ry * cx];
+
+    Ok(Some(px * normal[0] + py * normal[1] + pz * normal[2]))
+}
@@ -0,0 +1,126 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use ndarray::{s, Array3, ArrayView3, CowArray};
+
+use super::{Frame, FrameSource};
+
+/// How the voxels of a block are merged into one
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pooling {
+    // A block is occupied when half of its voxels are or more
+    Mean,
+    // A block is occupied when any of its voxels is
+    Max,
+}
+
+/// Which voxels of a frame belong to the aggregate
+type Occupancy = Box<dyn Fn(&Frame) -> Array3<bool>>;
+
+/// The frames of another source coarse-grained by `factor` along every axis,
+/// each block of `factor`³ voxels merged into one. The voxels are binarized
+/// by `occupancy` first, so that the blocks do not depend on how the
+/// aggregate is coded. Blocks at the far faces of a frame are cut short, and
+/// flat axes stay flat.
+pub struct DownsampledFrames {
+    frames: Box<dyn FrameSource>,
+    factor: usize,
+    pooling: Pooling,
+    occupancy: Occupancy,
+}
+
+impl DownsampledFrames {
+    pub fn new(
+        frames: Box<dyn FrameSource>,
+        factor: usize,
+        pooling: Pooling,
+        occupancy: impl Fn(&Frame) -> Array3<bool> + 'static,
+    ) -> Self {
+        Self {
+            frames,
+            factor,
+            pooling,
+            occupancy: Box::new(occupancy),
+        }
+    }
+}
+
+impl FrameSource for DownsampledFrames {
+    fn frame_count(&self) -> usize {
+        self.frames.frame_count()
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        let (factor, pooling) = (self.factor, self.pooling);
+        let occupied = (self.occupancy)(&self.frames.frame(index)?);
+        let (x, y, z) = occupied.dim();
+        let coarse = (x.div_ceil(factor), y.div_ceil(factor), z.div_ceil(factor));
+        let pooled = Array3::from_shape_fn(coarse, |(x, y, z)| {
+            let block = block(occupied.view(), [x, y, z], factor);
+            match pooling {
+                Pooling::Mean => 2 * block.iter().filter(|&&v| v).count() >= block.len(),
+                Pooling::Max => block.iter().any(|&v| v),
+            }
+        });
+
+        Ok(CowArray::from(pooled).into())
+    }
+
+    fn has_frame(&mut self, index: usize) -> Result<bool> {
+        self.frames.has_frame(index)
+    }
+}
+
+/// The voxels of `frame` merged into the coarse voxel `[x, y, z]`; never
+/// empty, as every block starts inside the frame.
+fn block(
+    frame: ArrayView3<'_, bool>,
+    [x, y, z]: [usize; 3],
+    factor: usize,
+) -> ArrayView3<'_, bool> {
+    let (x_max, y_max, z_max) = frame.dim();
+    frame.slice_move(s![
+        x * factor..((x + 1) * factor).min(x_max),
+        y * factor..((y + 1) * factor).min(y_max),
+        z * factor..((z + 1) * factor).min(z_max)
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array4;
+
+    use super::*;
+
+    /// One 4×2×2 frame whose first block of 2×2×2 voxels, coded 2, is half
+    /// full and whose second holds a single voxel
+    fn frames() -> Box<dyn FrameSource> {
+        let mut voxels = Array4::<u8>::zeros((1, 4, 2, 2));
+        voxels.slice_mut(s![0, 0, .., ..]).fill(2);
+        voxels[[0, 3, 1, 1]] = 2;
+        Box::new(voxels)
+    }
+
+    fn pooled(pooling: Pooling) -> Vec<bool> {
+        let occupancy = |frame: &Frame| match frame {
+            Frame::U8(f) => f.map(|&v| v >= 2),
+            _ => unreachable!(),
+        };
+        let mut frames = DownsampledFrames::new(frames(), 2, pooling, occupancy);
+        let Frame::Bool(frame) = frames.frame(0).unwrap() else {
+            panic!("pooled frames are binary");
+        };
+        assert_eq!(frame.dim(), (2, 1, 1));
+        frame.iter().copied().collect()
+    }
+
+    #[test]
+    fn mean_keeps_blocks_that_are_half_full() {
+        assert_eq!(pooled(Pooling::Mean), [true, false]);
+    }
+
+    #[test]
+    fn max_keeps_blocks_with_any_voxel() {
+        assert_eq!(pooled(Pooling::Max), [true, true]);
+    }
+}
@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use ::hdf5::types::{FloatSize, TypeDescriptor};
+use ::hdf5::{Dataset, H5Type};
+use anyhow::{anyhow, Result};
+use ndarray::Ix4;
+
+use super::{FrameSource, Voxel};
+
+/// Loads a 4D dataset from an HDF5 file. If no dataset path is given, the file
+/// must contain exactly one dataset at its root. Floating point datasets keep
+/// their values, others are read as `i32`. `reversed` reverses the order of
+/// the dimensions, for arrays written in column-major order.
+pub fn load_aggregate_data_hdf5(
+    file_path: impl AsRef<Path>,
+    dataset: Option<&str>,
+    reversed: bool,
+) -> Result<Box<dyn FrameSource>> {
+    let file = ::hdf5::File::open(file_path)?;
+    let dataset = match dataset {
+        Some(name) => file.dataset(name)?,
+        None => {
+            let mut datasets = file.datasets()?;
+            if datasets.len() != 1 {
+                let names: Vec<_> = datasets.iter().map(|d| d.name()).collect();
+                return Err(anyhow!(
+                    "expected exactly one dataset at the file root, found {names:?}; pick one with --dataset"
+                ));
+            }
+            datasets.remove(0)
+        }
+    };
+    if dataset.ndim() != 4 {
+        return Err(anyhow!("expected 4D array"));
+    }
+
+    match dataset.dtype()?.to_descriptor()? {
+        TypeDescriptor::Float(FloatSize::U4) => read::<f32>(&dataset, reversed),
+        TypeDescriptor::Float(FloatSize::U8) => read::<f64>(&dataset, reversed),
+        _ => read::<i32>(&dataset, reversed),
+    }
+}
+
+fn read<T: H5Type + Voxel>(dataset: &Dataset, reversed: bool) -> Result<Box<dyn FrameSource>> {
+    let array = dataset.read::<T, Ix4>()?;
+    Ok(Box::new(if reversed {
+        array.reversed_axes()
+    } else {
+        array
+    }))
+}
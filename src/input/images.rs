@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use ndarray::Array4;
+
+use super::points::OCCUPIED;
+use super::sorted_files_with_extension;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "pgm", "ppm", "pnm", "pbm"];
+
+/// Loads a sequence of 2D images. Without a `pattern` every image is one flat
+/// frame of shape (1, height, width). A pattern such as `t{t}_z{z}.png` groups
+/// the images into Z-stacks instead, one per `{t}` value ordered by `{z}`.
+/// Pixels whose gray level is at least `threshold` (a fraction of the full
+/// range) are part of the aggregate.
+pub fn load_aggregate_data_images(
+    path: impl AsRef<Path>,
+    pattern: Option<&str>,
+    threshold: f64,
+) -> Result<Array4<i32>> {
+    let path = path.as_ref();
+    let files = if path.is_dir() {
+        sorted_files_with_extension(path, IMAGE_EXTENSIONS)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let stacks = match pattern {
+        Some(pattern) => group_by_pattern(&files, pattern)?,
+        None => files.into_iter().map(|file| vec![file]).collect(),
+    };
+
+    let mut data = Vec::new();
+    let mut frame_shape = None;
+    for stack in &stacks {
+        for file in stack {
+            let image = image::open(file)
+                .map_err(|e| anyhow!("{}: {e}", file.display()))?
+                .into_luma16();
+            let shape = [stack.len(), image.height() as usize, image.width() as usize];
+            match frame_shape {
+                None => frame_shape = Some(shape),
+                Some(s) if s != shape => {
+                    return Err(anyhow!(
+                        "{} makes a frame of shape {:?}, expected {:?}",
+                        file.display(),
+                        shape,
+                        s
+                    ))
+                }
+                _ => {}
+            }
+            let cutoff = threshold * u16::MAX as f64;
+            data.extend(image.into_raw().into_iter().map(|v| {
+                if v as f64 >= cutoff {
+                    OCCUPIED
+                } else {
+                    0
+                }
+            }));
+        }
+    }
+    let [z, y, x] = frame_shape.ok_or_else(|| anyhow!("no images in {}", path.display()))?;
+
+    Ok(Array4::from_shape_vec((stacks.len(), z, y, x), data)?)
+}
+
+/// Groups the files whose names match `pattern` by their `{t}` value, each
+/// group sorted by `{z}`. Files that do not match are ignored.
+fn group_by_pattern(files: &[PathBuf], pattern: &str) -> Result<Vec<Vec<PathBuf>>> {
+    let mut frames: BTreeMap<usize, BTreeMap<usize, PathBuf>> = BTreeMap::new();
+    for file in files {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        let Some((t, z)) = match_pattern(pattern, &name) else {
+            continue;
+        };
+        if let Some(other) = frames.entry(t).or_default().insert(z, file.clone()) {
+            return Err(anyhow!(
+                "{} and {} are the same slice",
+                other.display(),
+                file.display()
+            ));
+        }
+    }
+    if frames.is_empty() {
+        return Err(anyhow!("no file names match {pattern:?}"));
+    }
+
+    Ok(frames
+        .into_values()
+        .map(|slices| slices.into_values().collect())
+        .collect())
+}
+
+/// Matches a file name against a pattern with `{t}` and `{z}` placeholders
+/// for runs of digits, returning their values (0 where absent).
+fn match_pattern(pattern: &str, name: &str) -> Option<(usize, usize)> {
+    let (mut pattern, mut name) = (pattern, name);
+    let (mut t, mut z) = (0, 0);
+    loop {
+        let Some(start) = pattern.find('{') else {
+            return (pattern == name).then_some((t, z));
+        };
+        name = name.strip_prefix(&pattern[..start])?;
+        let (slot, rest) = match &pattern[start..] {
+            p if p.starts_with("{t}") => (&mut t, &p[3..]),
+            p if p.starts_with("{z}") => (&mut z, &p[3..]),
+            _ => return None,
+        };
+        let digits = name
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(name.len());
+        *slot = name[..digits].parse().ok()?;
+        (pattern, name) = (rest, &name[digits..]);
+    }
+}
@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use ndarray::Array4;
+
+use super::points::{voxelize, Lattice};
+
+/// Loads a LAMMPS text dump trajectory, one frame per `ITEM: TIMESTEP`
+/// block. Atoms are binned onto a lattice spanning the simulation box; if
+/// `atom_types` is not empty, only atoms of those types are kept. Scaled
+/// (`xs`) coordinates are mapped back into the box, unwrapped (`xu`) ones are
+/// used as they are.
+pub fn load_aggregate_data_lammps(
+    file_path: impl AsRef<Path>,
+    lattice: Lattice,
+    atom_types: &[u32],
+) -> Result<Array4<i32>> {
+    let text = std::fs::read_to_string(file_path)?;
+    let mut lines = text.lines().enumerate().peekable();
+    let mut points = Vec::new();
+    let mut frames = 0;
+    let mut bounds = [[f64::INFINITY; 3], [f64::NEG_INFINITY; 3]];
+    let mut box_lo = [0.0; 3];
+    let mut box_hi = [1.0; 3];
+    let mut atom_count = 0;
+
+    while let Some((number, line)) = lines.next() {
+        let context = || format!("line {}", number + 1);
+        let Some(item) = line.trim().strip_prefix("ITEM:") else {
+            continue;
+        };
+        let item = item.trim();
+        if item.starts_with("TIMESTEP") {
+            frames += 1;
+        } else if item.starts_with("NUMBER OF ATOMS") {
+            let (_, count) = lines.next().ok_or_else(|| anyhow!("truncated dump"))?;
+            atom_count = count.trim().parse().with_context(context)?;
+        } else if item.starts_with("BOX BOUNDS") {
+            for axis in 0..3 {
+                let (_, bound) = lines.next().ok_or_else(|| anyhow!("truncated dump"))?;
+                // Triclinic boxes carry a third (tilt) factor, which is ignored
+                let mut values = bound.split_whitespace().map(str::parse::<f64>);
+                box_lo[axis] = values.next().ok_or_else(|| anyhow!("missing bound"))??;
+                box_hi[axis] = values.next().ok_or_else(|| anyhow!("missing bound"))??;
+                bounds[0][axis] = bounds[0][axis].min(box_lo[axis]);
+                bounds[1][axis] = bounds[1][axis].max(box_hi[axis]);
+            }
+        } else if let Some(columns) = item.strip_prefix("ATOMS") {
+            if frames == 0 {
+                return Err(anyhow!("ATOMS section before the first TIMESTEP"));
+            }
+            let columns: Vec<_> = columns.split_whitespace().collect();
+            let find = |names: &[&str]| columns.iter().position(|c| names.contains(c));
+            let type_column = find(&["type"]);
+            let mut coordinate_columns = [(0, false); 3];
+            for (axis, name) in ["x", "y", "z"].iter().enumerate() {
+                coordinate_columns[axis] = if let Some(c) = find(&[name, &format!("{name}u")]) {
+                    (c, false)
+                } else if let Some(c) = find(&[&format!("{name}s"), &format!("{name}su")]) {
+                    (c, true)
+                } else {
+                    return Err(anyhow!("{}: no {name} coordinate column", context()));
+                };
+            }
+            if !atom_types.is_empty() && type_column.is_none() {
+                return Err(anyhow!(
+                    "{}: atom type filtering needs a type column",
+                    context()
+                ));
+            }
+
+            for _ in 0..atom_count {
+                let (number, atom) = lines.next().ok_or_else(|| anyhow!("truncated dump"))?;
+                let fields: Vec<_> = atom.split_whitespace().collect();
+                let field = |c: usize| {
+                    fields
+                        .get(c)
+                        .ok_or_else(|| anyhow!("line {}: missing column", number + 1))
+                };
+                if let Some(c) = type_column {
+                    let atom_type = field(c)?
+                        .parse::<u32>()
+                        .with_context(|| format!("line {}", number + 1))?;
+                    if !atom_types.is_empty() && !atom_types.contains(&atom_type) {
+                        continue;
+                    }
+                }
+                let mut position = [0.0; 3];
+                for (axis, &(c, scaled)) in coordinate_columns.iter().enumerate() {
+                    let value = field(c)?
+                        .parse::<f64>()
+                        .with_context(|| format!("line {}", number + 1))?;
+                    position[axis] = if scaled {
+                        box_lo[axis] + value * (box_hi[axis] - box_lo[axis])
+                    } else {
+                        value
+                    };
+                }
+                points.push((frames - 1, position));
+            }
+        }
+    }
+    if frames == 0 {
+        return Err(anyhow!("no TIMESTEP found in the dump"));
+    }
+
+    voxelize(&points, frames, lattice, Some(bounds))
+}
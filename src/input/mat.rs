@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use matfile::{MatFile, NumericData};
+use ndarray::ShapeBuilder;
+
+use super::{load_aggregate_data_hdf5, saturate, FrameSource, Values};
+
+/// HDF5 signature, found at the end of the 512 byte MAT v7.3 header
+const HDF5_SIGNATURE: &[u8] = b"\x89HDF\r\n\x1a\n";
+
+/// Loads a 4D numeric variable from a MAT file. Dimensions keep their MATLAB
+/// order, so the first one is taken as time like for the other formats.
+/// Version 7.3 files are HDF5 underneath and need the `hdf5` feature.
+pub fn load_aggregate_data_mat(
+    file_path: impl AsRef<Path>,
+    variable: Option<&str>,
+) -> Result<Box<dyn FrameSource>> {
+    let file_path = file_path.as_ref();
+    let bytes = std::fs::read(file_path)?;
+    if bytes.get(512..520) == Some(HDF5_SIGNATURE) {
+        // HDF5 stores MATLAB's column-major arrays with their dimensions reversed
+        return load_aggregate_data_hdf5(file_path, variable, true);
+    }
+
+    let mat = MatFile::parse(&bytes[..])?;
+    let array = match variable {
+        Some(name) => mat
+            .find_by_name(name)
+            .ok_or_else(|| anyhow!("no variable named {name}"))?,
+        None => {
+            let candidates: Vec<_> = mat.arrays().iter().filter(|a| a.ndims() == 4).collect();
+            match candidates[..] {
+                [array] => array,
+                _ => {
+                    let names: Vec<_> = candidates.iter().map(|a| a.name()).collect();
+                    return Err(anyhow!(
+                        "expected exactly one 4D variable, found {names:?}; pick one with --variable"
+                    ));
+                }
+            }
+        }
+    };
+    let shape = match array.size()[..] {
+        [t, x, y, z] => [t, x, y, z],
+        _ => return Err(anyhow!("expected 4D array")),
+    };
+
+    let ints = |v: Vec<i32>| Values::I32(v);
+    let data = match array.data() {
+        NumericData::Int8 { real, .. } => ints(real.iter().map(|&x| x.into()).collect()),
+        NumericData::UInt8 { real, .. } => ints(real.iter().map(|&x| x.into()).collect()),
+        NumericData::Int16 { real, .. } => ints(real.iter().map(|&x| x.into()).collect()),
+        NumericData::UInt16 { real, .. } => ints(real.iter().map(|&x| x.into()).collect()),
+        NumericData::Int32 { real, .. } => ints(real.clone()),
+        NumericData::UInt32 { real, .. } => ints(real.iter().map(|&x| saturate(x)).collect()),
+        NumericData::Int64 { real, .. } => ints(real.iter().map(|&x| saturate(x)).collect()),
+        NumericData::UInt64 { real, .. } => ints(real.iter().map(|&x| saturate(x)).collect()),
+        NumericData::Single { real, .. } => Values::F32(real.clone()),
+        NumericData::Double { real, .. } => Values::F64(real.clone()),
+    };
+
+    data.into_frames(shape.f())
+}
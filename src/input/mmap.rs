@@ -0,0 +1,204 @@
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use memmap2::Mmap;
+use ndarray::{Array3, CowArray, Ix3};
+use npyz::{DType, Endianness, NpyHeader, Order, TypeChar};
+use zip::{CompressionMethod, ZipArchive};
+
+use super::{Dtype, Frame, FrameSource, Voxel};
+
+/// Memory layout of a numeric NPY array; 3D arrays are a single frame and
+/// one channel is picked from 5D (T, C, X, Y, Z) arrays.
+pub(super) struct NpyLayout {
+    pub shape: [usize; 4],
+    channels: usize,
+    channel: usize,
+    dtype: Dtype,
+    is_bool: bool,
+    big_endian: bool,
+    pub fortran_order: bool,
+}
+
+impl NpyLayout {
+    pub fn from_header(header: &NpyHeader, channel: Option<usize>) -> Result<Self> {
+        let dims: Vec<usize> = header.shape().iter().map(|&d| d as usize).collect();
+        let (shape, channels) = match (&dims[..], channel) {
+            (&[x, y, z], None) => ([1, x, y, z], 1),
+            (&[t, x, y, z], None) => ([t, x, y, z], 1),
+            (&[t, c, x, y, z], Some(_)) => ([t, x, y, z], c),
+            (_, channel) => return Err(shape_error(dims.len(), channel)),
+        };
+        let channel = channel.unwrap_or(0);
+        if channel >= channels {
+            return Err(anyhow!("--channel {channel} is out of range 0..{channels}"));
+        }
+        let DType::Plain(ty) = header.dtype() else {
+            return Err(anyhow!("expected a numeric array"));
+        };
+        let dtype = match (ty.type_char(), ty.size_field()) {
+            (TypeChar::Bool | TypeChar::Uint, 1) => Dtype::U8,
+            (TypeChar::Int, 1) => Dtype::I8,
+            (TypeChar::Uint, 2) => Dtype::U16,
+            (TypeChar::Int, 2) => Dtype::I16,
+            (TypeChar::Uint, 4) => Dtype::U32,
+            (TypeChar::Int, 4) => Dtype::I32,
+            (TypeChar::Uint, 8) => Dtype::U64,
+            (TypeChar::Int, 8) => Dtype::I64,
+            (TypeChar::Float, 4) => Dtype::F32,
+            (TypeChar::Float, 8) => Dtype::F64,
+            _ => return Err(anyhow!("unsupported element type {ty}")),
+        };
+
+        Ok(Self {
+            shape,
+            channels,
+            channel,
+            dtype,
+            is_bool: ty.type_char() == TypeChar::Bool,
+            big_endian: ty.endianness() == Endianness::Big,
+            fortran_order: header.order() == Order::Fortran,
+        })
+    }
+
+    /// Size of one frame, with all its channels, in bytes
+    pub fn frame_len(&self) -> usize {
+        self.channels * self.shape[1..].iter().product::<usize>() * self.dtype.size()
+    }
+
+    /// Decodes frame `index` from the raw bytes `data` of the array, keeping
+    /// the element type where `Frame` can hold it.
+    pub fn decode_frame<'a>(&self, data: &[u8], index: usize) -> Frame<'a> {
+        let be = self.big_endian;
+        match self.dtype {
+            Dtype::U8 if self.is_bool => self.decode_as(data, index, |b| b[0] != 0).into(),
+            Dtype::U8 => self.decode_as(data, index, |b| b[0]).into(),
+            Dtype::U16 => self
+                .decode_as(data, index, |b| match be {
+                    true => u16::from_be_bytes([b[0], b[1]]),
+                    false => u16::from_le_bytes([b[0], b[1]]),
+                })
+                .into(),
+            Dtype::F32 => self
+                .decode_as(data, index, |b| match be {
+                    true => f32::from_be_bytes(b.try_into().unwrap()),
+                    false => f32::from_le_bytes(b.try_into().unwrap()),
+                })
+                .into(),
+            Dtype::F64 => self
+                .decode_as(data, index, |b| match be {
+                    true => f64::from_be_bytes(b.try_into().unwrap()),
+                    false => f64::from_le_bytes(b.try_into().unwrap()),
+                })
+                .into(),
+            dtype => self
+                .decode_as(data, index, |b| match be {
+                    true => dtype.decode_be(b),
+                    false => dtype.decode_le(b),
+                })
+                .into(),
+        }
+    }
+
+    fn decode_as<'a, T: Voxel>(
+        &self,
+        data: &[u8],
+        index: usize,
+        decode: impl Fn(&[u8]) -> T,
+    ) -> CowArray<'a, T, Ix3> {
+        let [nt, nx, ny, nz] = self.shape;
+        let (nc, c) = (self.channels, self.channel);
+        let size = self.dtype.size();
+        let frame = Array3::from_shape_fn((nx, ny, nz), |(x, y, z)| {
+            let i = if self.fortran_order {
+                index + nt * (c + nc * (x + nx * (y + ny * z)))
+            } else {
+                (((index * nc + c) * nx + x) * ny + y) * nz + z
+            };
+            decode(&data[i * size..(i + 1) * size])
+        });
+
+        CowArray::from(frame)
+    }
+}
+
+/// A 3D or 4D array mapped into memory straight from an uncompressed `.npy`
+/// file or NPZ member. Frames are decoded on request, so the array may be
+/// larger than the available RAM.
+pub struct MappedNpy {
+    mmap: Mmap,
+    data_start: usize,
+    layout: NpyLayout,
+}
+
+impl MappedNpy {
+    /// Maps a `.npy` file.
+    pub fn open(file_path: impl AsRef<Path>, channel: Option<usize>) -> Result<Self> {
+        Self::map(&File::open(file_path)?, 0, channel)
+    }
+
+    /// Maps the NPZ member called `name`, or returns `None` if it is compressed.
+    pub fn open_npz_member(
+        file_path: impl AsRef<Path>,
+        name: &str,
+        channel: Option<usize>,
+    ) -> Result<Option<Self>> {
+        let file = File::open(file_path)?;
+        let mut zip = ZipArchive::new(&file)?;
+        let member = zip.by_name(&format!("{name}.npy"))?;
+        if member.compression() != CompressionMethod::Stored {
+            return Ok(None);
+        }
+        let start = member.data_start() as usize;
+        drop(member);
+
+        Ok(Some(Self::map(&file, start, channel)?))
+    }
+
+    /// Maps the NPY stream that starts `start` bytes into `file`.
+    fn map(file: &File, start: usize, channel: Option<usize>) -> Result<Self> {
+        // SAFETY: the mapping is read-only; like any reader we assume that the
+        // file is not truncated or rewritten while it is being processed.
+        let mmap = unsafe { Mmap::map(file)? };
+        let npy = mmap
+            .get(start..)
+            .ok_or_else(|| anyhow!("NPY stream starts past the end of the file"))?;
+        let mut data = npy;
+        let layout = NpyLayout::from_header(&NpyHeader::from_reader(&mut data)?, channel)?;
+        let data_start = start + npy.len() - data.len();
+        if data.len() < layout.shape[0] * layout.frame_len() {
+            return Err(anyhow!(
+                "NPY data is shorter than its shape {:?}",
+                layout.shape
+            ));
+        }
+
+        Ok(Self {
+            mmap,
+            data_start,
+            layout,
+        })
+    }
+}
+
+impl FrameSource for MappedNpy {
+    fn frame_count(&self) -> usize {
+        self.layout.shape[0]
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        let data = &self.mmap[self.data_start..];
+
+        Ok(self.layout.decode_frame(data, index))
+    }
+}
+
+/// Explains why an array with `ndim` dimensions cannot be used with `channel`.
+pub(super) fn shape_error(ndim: usize, channel: Option<usize>) -> anyhow::Error {
+    match (ndim, channel) {
+        (5, None) => anyhow!("5D arrays are (T, C, X, Y, Z); pick a channel with --channel"),
+        (_, Some(_)) => anyhow!("--channel needs a 5D array, got {ndim}D"),
+        _ => anyhow!("expected 3D or 4D array"),
+    }
+}
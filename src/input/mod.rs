@@ -0,0 +1,714 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use ndarray::{Array3, Array4, Axis, CowArray, Ix3, Ix4, StrideShape};
+
+mod arrow;
+mod crop;
+mod dicom;
+mod downsample;
+#[cfg(feature = "hdf5")]
+mod hdf5;
+mod images;
+mod lammps;
+mod mat;
+mod mmap;
+mod mrc;
+#[cfg(feature = "netcdf")]
+mod netcdf;
+mod npz;
+mod nrrd;
+mod parquet;
+mod planar;
+mod points;
+mod raw;
+#[cfg(feature = "remote")]
+mod remote;
+mod sparse;
+mod tiff;
+mod vtk;
+mod zarr;
+
+#[cfg(feature = "hdf5")]
+pub use self::hdf5::load_aggregate_data_hdf5;
+#[cfg(feature = "netcdf")]
+pub use self::netcdf::NetcdfFrames;
+#[cfg(feature = "remote")]
+pub use self::remote::Download;
+pub use self::tiff::load_aggregate_data_tiff;
+pub use arrow::ArrowFrames;
+pub use crop::{parse_region, CroppedFrames};
+pub use dicom::load_aggregate_data_dicom;
+pub use downsample::{DownsampledFrames, Pooling};
+pub use images::load_aggregate_data_images;
+pub use lammps::load_aggregate_data_lammps;
+pub use mat::load_aggregate_data_mat;
+pub use mmap::MappedNpy;
+pub use mrc::load_aggregate_data_mrc;
+pub use npz::{list_arrays, load_aggregate_data_npy, open_npz, read_times, NpyFrames};
+pub use nrrd::load_aggregate_data_nrrd;
+pub use parquet::load_aggregate_data_parquet;
+pub use planar::PlanarFrames;
+pub use points::{load_aggregate_data_points, Lattice};
+pub use raw::RawFrames;
+pub use sparse::SparseFrames;
+pub use vtk::load_aggregate_data_vtk;
+pub use zarr::ZarrFrames;
+
+/// Supported simulation output formats
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    Npz,
+    Hdf5,
+    Raw,
+    Tiff,
+    Vtk,
+    Zarr,
+    Netcdf,
+    Points,
+    Lammps,
+    Npy,
+    Mrc,
+    Nrrd,
+    Mat,
+    Images,
+    Parquet,
+    Dicom,
+    Arrow,
+}
+
+impl InputFormat {
+    /// Guesses the input format from the file extension. Directories are
+    /// Zarr stores if they carry Zarr metadata, otherwise they are classified
+    /// by the extension of the first file in them. Standard input is assumed
+    /// to carry an NPZ archive.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        if is_stdin(path) {
+            return Ok(Self::Npz);
+        }
+        let is_zarr = [".zarray", ".zgroup", "zarr.json"]
+            .iter()
+            .any(|meta| path.join(meta).is_file());
+        if is_zarr {
+            return Ok(Self::Zarr);
+        }
+        if path.is_dir() {
+            let first = sorted_files_with_extension(path, &[])?.into_iter().next();
+            return match first {
+                Some(file) => Self::from_path(&file),
+                None => Err(anyhow!("{} is an empty directory", path.display())),
+            };
+        }
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("npz") => Ok(Self::Npz),
+            Some("npy") => Ok(Self::Npy),
+            Some("h5" | "hdf5" | "he5") => Ok(Self::Hdf5),
+            Some("raw" | "bin") => Ok(Self::Raw),
+            Some("gz" | "zst" | "zstd")
+                if Self::from_path(&path.with_extension(""))? == Self::Raw =>
+            {
+                Ok(Self::Raw)
+            }
+            Some("tif" | "tiff") => Ok(Self::Tiff),
+            Some("vtk" | "vti" | "pvti") => Ok(Self::Vtk),
+            Some("nc" | "nc4" | "cdf") => Ok(Self::Netcdf),
+            Some("xyz" | "csv" | "txt") => Ok(Self::Points),
+            Some("lammpstrj" | "dump") => Ok(Self::Lammps),
+            Some("mrc" | "mrcs" | "map" | "ccp4") => Ok(Self::Mrc),
+            Some("nrrd" | "nhdr") => Ok(Self::Nrrd),
+            Some("mat") => Ok(Self::Mat),
+            Some("png" | "pgm" | "ppm" | "pnm" | "pbm") => Ok(Self::Images),
+            Some("parquet" | "pq") => Ok(Self::Parquet),
+            Some("dcm" | "dicom") => Ok(Self::Dicom),
+            Some("arrow" | "arrows" | "ipc" | "feather") => Ok(Self::Arrow),
+            // DICOM slices often have no extension at all
+            _ if dicom::is_dicom(path) => Ok(Self::Dicom),
+            _ => Err(anyhow!(
+                "cannot infer the input format of {}; pass --format explicitly",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Whether `path` is `-`, which stands for standard input.
+pub fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Whether `path` is an `http://`, `https://` or `s3://` URL.
+pub fn is_remote(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    ["http://", "https://", "s3://"]
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
+}
+
+/// Expands glob patterns such as `run_*.npz` that the shell left alone, with
+/// the matches in natural order. Other paths, URLs and `-` are kept as is.
+pub fn expand_globs(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        let pattern = path.to_string_lossy();
+        if is_remote(path) || path.exists() || !pattern.contains(['*', '?', '[']) {
+            expanded.push(path.clone());
+            continue;
+        }
+        let mut matches = glob::glob(&pattern)?.collect::<Result<Vec<_>, _>>()?;
+        if matches.is_empty() {
+            return Err(anyhow!("no files match {pattern}"));
+        }
+        matches.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+        expanded.extend(matches);
+    }
+
+    Ok(expanded)
+}
+
+/// Reads a whole input file, or standard input if `path` is `-`.
+pub fn read_input(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let path = path.as_ref();
+    if is_stdin(path) {
+        let mut buf = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut buf)?;
+        return Ok(buf);
+    }
+
+    Ok(std::fs::read(path)?)
+}
+
+/// Element types that frames keep in memory
+pub trait Voxel: Copy + Send + Sync + 'static {
+    /// Whether the voxel belongs to the aggregate
+    fn is_occupied(self) -> bool;
+
+    /// The voxel as a number, for a threshold of its own; 1 or 0 for a bool
+    fn value(self) -> f64;
+
+    fn into_frame(frame: CowArray<'_, Self, Ix3>) -> Frame<'_>;
+}
+
+impl Voxel for bool {
+    fn is_occupied(self) -> bool {
+        self
+    }
+
+    fn value(self) -> f64 {
+        f64::from(u8::from(self))
+    }
+
+    fn into_frame(frame: CowArray<'_, Self, Ix3>) -> Frame<'_> {
+        Frame::Bool(frame)
+    }
+}
+
+impl Voxel for u8 {
+    fn is_occupied(self) -> bool {
+        self >= 2
+    }
+
+    fn value(self) -> f64 {
+        f64::from(self)
+    }
+
+    fn into_frame(frame: CowArray<'_, Self, Ix3>) -> Frame<'_> {
+        Frame::U8(frame)
+    }
+}
+
+impl Voxel for u16 {
+    fn is_occupied(self) -> bool {
+        self >= 2
+    }
+
+    fn value(self) -> f64 {
+        f64::from(self)
+    }
+
+    fn into_frame(frame: CowArray<'_, Self, Ix3>) -> Frame<'_> {
+        Frame::U16(frame)
+    }
+}
+
+impl Voxel for i32 {
+    fn is_occupied(self) -> bool {
+        self >= 2
+    }
+
+    fn value(self) -> f64 {
+        f64::from(self)
+    }
+
+    fn into_frame(frame: CowArray<'_, Self, Ix3>) -> Frame<'_> {
+        Frame::I32(frame)
+    }
+}
+
+impl Voxel for f32 {
+    fn is_occupied(self) -> bool {
+        self >= 2.0
+    }
+
+    fn value(self) -> f64 {
+        f64::from(self)
+    }
+
+    fn into_frame(frame: CowArray<'_, Self, Ix3>) -> Frame<'_> {
+        Frame::F32(frame)
+    }
+}
+
+impl Voxel for f64 {
+    fn is_occupied(self) -> bool {
+        self >= 2.0
+    }
+
+    fn value(self) -> f64 {
+        self
+    }
+
+    fn into_frame(frame: CowArray<'_, Self, Ix3>) -> Frame<'_> {
+        Frame::F64(frame)
+    }
+}
+
+/// A 3D frame in the element type it was stored with. Other integer types
+/// are widened or narrowed to `i32`.
+pub enum Frame<'a> {
+    Bool(CowArray<'a, bool, Ix3>),
+    U8(CowArray<'a, u8, Ix3>),
+    U16(CowArray<'a, u16, Ix3>),
+    I32(CowArray<'a, i32, Ix3>),
+    F32(CowArray<'a, f32, Ix3>),
+    F64(CowArray<'a, f64, Ix3>),
+}
+
+impl Frame<'_> {
+    /// Size of the frame along X, Y and Z
+    pub fn shape(&self) -> [usize; 3] {
+        let shape = match self {
+            Frame::Bool(f) => f.shape(),
+            Frame::U8(f) => f.shape(),
+            Frame::U16(f) => f.shape(),
+            Frame::I32(f) => f.shape(),
+            Frame::F32(f) => f.shape(),
+            Frame::F64(f) => f.shape(),
+        };
+        [shape[0], shape[1], shape[2]]
+    }
+}
+
+impl<'a, T: Voxel> From<CowArray<'a, T, Ix3>> for Frame<'a> {
+    fn from(frame: CowArray<'a, T, Ix3>) -> Self {
+        T::into_frame(frame)
+    }
+}
+
+/// A sequence of 3D frames that can be decoded one at a time
+pub trait FrameSource {
+    /// Total number of frames
+    fn frame_count(&self) -> usize;
+
+    /// Returns the frame at `index`, decoding it if it is not held in memory.
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>>;
+
+    /// Whether there is a frame at `index`. Streams of unknown length read
+    /// up to it to find out.
+    fn has_frame(&mut self, index: usize) -> Result<bool> {
+        Ok(index < self.frame_count())
+    }
+}
+
+impl<T: Voxel> FrameSource for Array4<T> {
+    fn frame_count(&self) -> usize {
+        self.len_of(Axis(0))
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        Ok(CowArray::from(self.index_axis(Axis(0), index)).into())
+    }
+}
+
+/// Flat voxel values as a reader decodes them: integers narrowed to `i32`,
+/// saturating, and floating point values kept as they are, so that a
+/// threshold of its own sees them whole.
+pub enum Values {
+    I32(Vec<i32>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+}
+
+impl Values {
+    /// Joins the values of several frames or pieces, widening them all to
+    /// the widest type among them.
+    pub fn concat(parts: Vec<Values>) -> Values {
+        let widest = parts.iter().map(Values::rank).max().unwrap_or(0);
+        match widest {
+            0 => Values::I32(parts.into_iter().flat_map(Values::into_i32).collect()),
+            1 => Values::F32(parts.into_iter().flat_map(Values::into_f32).collect()),
+            _ => Values::F64(parts.into_iter().flat_map(Values::into_f64).collect()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Values::I32(v) => v.len(),
+            Values::F32(v) => v.len(),
+            Values::F64(v) => v.len(),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Values::I32(_) => 0,
+            Values::F32(_) => 1,
+            Values::F64(_) => 2,
+        }
+    }
+
+    fn into_i32(self) -> Vec<i32> {
+        match self {
+            Values::I32(v) => v,
+            Values::F32(v) => v.into_iter().map(|x| x as i32).collect(),
+            Values::F64(v) => v.into_iter().map(|x| x as i32).collect(),
+        }
+    }
+
+    fn into_f32(self) -> Vec<f32> {
+        match self {
+            Values::I32(v) => v.into_iter().map(|x| x as f32).collect(),
+            Values::F32(v) => v,
+            Values::F64(v) => v.into_iter().map(|x| x as f32).collect(),
+        }
+    }
+
+    pub fn into_f64(self) -> Vec<f64> {
+        match self {
+            Values::I32(v) => v.into_iter().map(f64::from).collect(),
+            Values::F32(v) => v.into_iter().map(f64::from).collect(),
+            Values::F64(v) => v,
+        }
+    }
+
+    /// The values as a single frame of `shape`, in C order.
+    pub fn into_frame<'a>(self, shape: [usize; 3]) -> Result<Frame<'a>> {
+        Ok(match self {
+            Values::I32(v) => CowArray::from(Array3::from_shape_vec(shape, v)?).into(),
+            Values::F32(v) => CowArray::from(Array3::from_shape_vec(shape, v)?).into(),
+            Values::F64(v) => CowArray::from(Array3::from_shape_vec(shape, v)?).into(),
+        })
+    }
+
+    /// The values as frames of `shape`, time first.
+    pub fn into_frames(self, shape: impl Into<StrideShape<Ix4>>) -> Result<Box<dyn FrameSource>> {
+        Ok(match self {
+            Values::I32(v) => Box::new(Array4::from_shape_vec(shape, v)?),
+            Values::F32(v) => Box::new(Array4::from_shape_vec(shape, v)?),
+            Values::F64(v) => Box::new(Array4::from_shape_vec(shape, v)?),
+        })
+    }
+}
+
+/// Lists the files in `dir` whose extension is one of `extensions` (any file
+/// if empty), in natural file name order (`frame_2` before `frame_10`).
+pub fn sorted_files_with_extension(dir: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let wanted = match ext {
+            Some(ext) => extensions.is_empty() || extensions.contains(&ext.as_str()),
+            None => extensions.is_empty(),
+        };
+        if path.is_file() && wanted {
+            files.push(path);
+        }
+    }
+    files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+
+    Ok(files)
+}
+
+/// Compares strings treating runs of ASCII digits as numbers.
+fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(ca), Some(cb)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let da = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+            let db = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+            let (na, nb) = (
+                a[..da].trim_start_matches('0'),
+                b[..db].trim_start_matches('0'),
+            );
+            let ord = na.len().cmp(&nb.len()).then_with(|| na.cmp(nb));
+            if ord.is_ne() {
+                return ord;
+            }
+            (a, b) = (&a[da..], &b[db..]);
+        } else {
+            if ca != cb {
+                return ca.cmp(&cb);
+            }
+            (a, b) = (&a[ca.len_utf8()..], &b[cb.len_utf8()..]);
+        }
+    }
+}
+
+/// Element types of headerless inputs
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dtype {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+}
+
+impl Dtype {
+    /// Size of one element in bytes
+    pub fn size(self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::U64 | Self::I64 | Self::F64 => 8,
+        }
+    }
+
+    /// Decodes C-order elements, in the given byte order, keeping floating
+    /// point values as they are.
+    pub fn decode_all(self, bytes: &[u8], big_endian: bool) -> Values {
+        let elements = bytes.chunks_exact(self.size());
+        let word = |b: &[u8]| -> [u8; 8] {
+            let mut word = [0; 8];
+            word[..b.len()].copy_from_slice(b);
+            if big_endian {
+                word[..b.len()].reverse();
+            }
+            word
+        };
+        match self {
+            Self::F32 => Values::F32(
+                elements
+                    .map(|b| f32::from_le_bytes(word(b)[..4].try_into().unwrap()))
+                    .collect(),
+            ),
+            Self::F64 => Values::F64(elements.map(|b| f64::from_le_bytes(word(b))).collect()),
+            _ => Values::I32(
+                elements
+                    .map(|b| self.decode_le(&word(b)[..b.len()]))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Decodes one little-endian element. `bytes` must be exactly `self.size()` long.
+    /// Values outside the range of `i32` saturate, so they stay occupied, and
+    /// floating point values are truncated; `decode_all` keeps them.
+    pub fn decode_le(self, bytes: &[u8]) -> i32 {
+        match self {
+            Self::U8 => bytes[0] as i32,
+            Self::I8 => bytes[0] as i8 as i32,
+            Self::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as i32,
+            Self::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as i32,
+            Self::U32 => saturate(u32::from_le_bytes(bytes.try_into().unwrap())),
+            Self::I32 => i32::from_le_bytes(bytes.try_into().unwrap()),
+            Self::U64 => saturate(u64::from_le_bytes(bytes.try_into().unwrap())),
+            Self::I64 => saturate(i64::from_le_bytes(bytes.try_into().unwrap())),
+            Self::F32 => f32::from_le_bytes(bytes.try_into().unwrap()) as i32,
+            Self::F64 => f64::from_le_bytes(bytes.try_into().unwrap()) as i32,
+        }
+    }
+
+    /// Decodes one big-endian element. `bytes` must be exactly `self.size()` long.
+    pub fn decode_be(self, bytes: &[u8]) -> i32 {
+        let mut buf = [0; 8];
+        let le = &mut buf[..bytes.len()];
+        le.copy_from_slice(bytes);
+        le.reverse();
+        self.decode_le(le)
+    }
+}
+
+/// Byte order of headerless inputs
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ByteOrder {
+    Little,
+    Big,
+}
+
+/// Narrows an integer to `i32`, clamping it to the range of `i32`.
+pub fn saturate<T: TryInto<i32> + PartialOrd + Default + Copy>(v: T) -> i32 {
+    v.try_into()
+        .unwrap_or(if v < T::default() { i32::MIN } else { i32::MAX })
+}
+
+/// Parses `N` comma separated dimensions such as `10,64,64,64`.
+pub fn parse_dims<const N: usize>(s: &str) -> Result<[usize; N], String> {
+    let dims = s
+        .split(',')
+        .map(|d| d.trim().parse::<usize>().map_err(|e| format!("{d:?}: {e}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    dims.try_into()
+        .map_err(|d: Vec<_>| format!("expected {N} dimensions, got {}", d.len()))
+}
+
+#[cfg(not(feature = "hdf5"))]
+pub fn load_aggregate_data_hdf5(
+    _file_path: impl AsRef<Path>,
+    _dataset: Option<&str>,
+    _reversed: bool,
+) -> Result<Box<dyn FrameSource>> {
+    Err(anyhow!(
+        "HDF5 support is not compiled in; rebuild with `--features hdf5`"
+    ))
+}
+
+/// Stand-in for the NetCDF reader in builds without the `netcdf` feature
+#[cfg(not(feature = "netcdf"))]
+pub struct NetcdfFrames(std::convert::Infallible);
+
+#[cfg(not(feature = "netcdf"))]
+impl NetcdfFrames {
+    pub fn open(_file_path: impl AsRef<Path>, _variable: Option<&str>) -> Result<Self> {
+        Err(anyhow!(
+            "NetCDF support is not compiled in; rebuild with `--features netcdf`"
+        ))
+    }
+}
+
+#[cfg(not(feature = "netcdf"))]
+impl FrameSource for NetcdfFrames {
+    fn frame_count(&self) -> usize {
+        match self.0 {}
+    }
+
+    fn frame(&mut self, _index: usize) -> Result<Frame<'_>> {
+        match self.0 {}
+    }
+}
+
+/// Stand-in for remote downloads in builds without the `remote` feature
+#[cfg(not(feature = "remote"))]
+pub struct Download(std::convert::Infallible);
+
+#[cfg(not(feature = "remote"))]
+impl Download {
+    pub fn fetch(_url: &Path) -> Result<Self> {
+        Err(anyhow!(
+            "remote input support is not compiled in; rebuild with `--features remote`"
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        match self.0 {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_decode_in_either_byte_order() {
+        let bytes = [0x01, 0x02, 0xff, 0xfe];
+        let Values::I32(le) = Dtype::I16.decode_all(&bytes, false) else {
+            panic!("integers decode as i32");
+        };
+        assert_eq!(le, [0x0201, -257]);
+        let Values::I32(be) = Dtype::U16.decode_all(&bytes, true) else {
+            panic!("integers decode as i32");
+        };
+        assert_eq!(be, [0x0102, 0xfffe]);
+        assert_eq!(Dtype::I8.decode_le(&[0xff]), -1);
+        assert_eq!(Dtype::U32.decode_be(&[0, 0, 1, 0]), 256);
+    }
+
+    #[test]
+    fn wide_integers_saturate() {
+        assert_eq!(Dtype::U32.decode_le(&u32::MAX.to_le_bytes()), i32::MAX);
+        assert_eq!(Dtype::I64.decode_le(&i64::MIN.to_le_bytes()), i32::MIN);
+        assert_eq!(Dtype::U64.decode_le(&(1u64 << 40).to_le_bytes()), i32::MAX);
+        assert_eq!(saturate(-5i64), -5);
+    }
+
+    #[test]
+    fn floats_are_kept() {
+        let bytes: Vec<u8> = [0.25f32, -1.5]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        let Values::F32(values) = Dtype::F32.decode_all(&bytes, true) else {
+            panic!("f32 elements decode as f32");
+        };
+        assert_eq!(values, [0.25, -1.5]);
+        let bytes = 0.75f64.to_le_bytes();
+        let Values::F64(values) = Dtype::F64.decode_all(&bytes, false) else {
+            panic!("f64 elements decode as f64");
+        };
+        assert_eq!(values, [0.75]);
+    }
+
+    #[test]
+    fn values_widen_to_the_widest_part() {
+        let values = Values::concat(vec![Values::I32(vec![2]), Values::F32(vec![0.5])]);
+        let Values::F32(values) = values else {
+            panic!("i32 and f32 join as f32");
+        };
+        assert_eq!(values, [2.0, 0.5]);
+        let joined = Values::concat(vec![Values::F64(vec![0.1]), Values::I32(vec![1])]);
+        assert_eq!(joined.len(), 2);
+        assert!(matches!(joined, Values::F64(_)));
+    }
+
+    #[test]
+    fn dimensions_parse() {
+        assert_eq!(parse_dims::<4>("10, 64,64,64"), Ok([10, 64, 64, 64]));
+        assert!(parse_dims::<3>("64,64").is_err());
+        assert!(parse_dims::<3>("64,-1,64").is_err());
+    }
+
+    #[test]
+    fn natural_order_compares_numbers() {
+        let mut names = [
+            "frame10.png",
+            "frame2.png",
+            "frame1.png",
+            "frame02b.png",
+            "Frame3.png",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            [
+                "Frame3.png",
+                "frame1.png",
+                "frame2.png",
+                "frame02b.png",
+                "frame10.png"
+            ]
+        );
+        assert!(natural_cmp("a007", "a7").is_eq());
+        assert!(natural_cmp("a7", "a7x").is_lt());
+    }
+}
@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use super::{sorted_files_with_extension, Dtype, FrameSource, Values};
+use anyhow::{anyhow, Result};
+
+const HEADER_LEN: usize = 1024;
+/// Space group that marks an MRC file as a stack of volumes
+const VOLUME_STACK_SPACE_GROUP: i32 = 401;
+
+/// Loads MRC/CCP4 maps. A single map is one frame, a volume stack (space
+/// group 401) or a directory of maps is a series. Frames keep the file's
+/// (section, row, column) order.
+pub fn load_aggregate_data_mrc(path: impl AsRef<Path>) -> Result<Box<dyn FrameSource>> {
+    let path = path.as_ref();
+    let files = if path.is_dir() {
+        sorted_files_with_extension(path, &["mrc", "map", "ccp4", "mrcs"])?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut data = Vec::new();
+    let mut frame_shape = None;
+    let mut frames = 0;
+    for file in &files {
+        let (frame_count, shape, values) = read_map(file)?;
+        if frame_shape.is_some_and(|s| s != shape) {
+            return Err(anyhow!(
+                "{} differs in size from the other maps",
+                file.display()
+            ));
+        }
+        frame_shape = Some(shape);
+        frames += frame_count;
+        data.push(values);
+    }
+    let [z, y, x] = frame_shape.ok_or_else(|| anyhow!("no MRC maps in {}", path.display()))?;
+
+    Values::concat(data).into_frames((frames, z, y, x))
+}
+
+/// Returns the number of volumes in the map, their (sections, rows, columns)
+/// shape and the flattened values.
+fn read_map(path: &Path) -> Result<(usize, [usize; 3], Values)> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < HEADER_LEN {
+        return Err(anyhow!("{} is too short for an MRC header", path.display()));
+    }
+    // The machine stamp is 0x44 0x4? for little and 0x11 0x11 for big endian files
+    let big_endian = bytes[212] == 0x11;
+    let word = |i: usize| {
+        let b = [
+            bytes[4 * i],
+            bytes[4 * i + 1],
+            bytes[4 * i + 2],
+            bytes[4 * i + 3],
+        ];
+        if big_endian {
+            i32::from_be_bytes(b)
+        } else {
+            i32::from_le_bytes(b)
+        }
+    };
+    let [nx, ny, nz, mode] = [word(0), word(1), word(2), word(3)];
+    let (mz, space_group, extended_header) = (word(9), word(22), word(23));
+    if nx <= 0 || ny <= 0 || nz <= 0 || extended_header < 0 {
+        return Err(anyhow!("{} has an invalid MRC header", path.display()));
+    }
+    let (nx, ny, nz) = (nx as usize, ny as usize, nz as usize);
+
+    let volumes = if space_group == VOLUME_STACK_SPACE_GROUP && mz > 0 && nz % mz as usize == 0 {
+        nz / mz as usize
+    } else {
+        1
+    };
+
+    let size = match mode {
+        0 => 1,
+        1 | 6 => 2,
+        2 => 4,
+        _ => return Err(anyhow!("unsupported MRC mode {mode} in {}", path.display())),
+    };
+    let start = HEADER_LEN + extended_header as usize;
+    let end = start + nx * ny * nz * size;
+    let payload = bytes
+        .get(start..end)
+        .ok_or_else(|| anyhow!("{} is truncated", path.display()))?;
+    let dtype = match mode {
+        0 => Dtype::I8,
+        1 => Dtype::I16,
+        6 => Dtype::U16,
+        _ => Dtype::F32,
+    };
+    let values = dtype.decode_all(payload, big_endian);
+
+    Ok((volumes, [nz / volumes, ny, nx], values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::*;
+
+    /// Writes a map of `nx` columns with the given mode and payload.
+    fn map(name: &str, nx: i32, mode: i32, big_endian: bool, payload: &[u8]) -> std::path::PathBuf {
+        let mut bytes = vec![0; HEADER_LEN];
+        for (i, w) in [nx, 1, 1, mode].into_iter().enumerate() {
+            let w = if big_endian {
+                w.to_be_bytes()
+            } else {
+                w.to_le_bytes()
+            };
+            bytes[4 * i..4 * i + 4].copy_from_slice(&w);
+        }
+        bytes[212] = if big_endian { 0x11 } else { 0x44 };
+        bytes.extend_from_slice(payload);
+        let path = std::env::temp_dir().join(format!("mrc-{}-{name}.mrc", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn float_maps_keep_their_values() {
+        let payload: Vec<u8> = [0.25f32, 2.5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let path = map("float", 2, 2, false, &payload);
+        let mut frames = load_aggregate_data_mrc(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let Frame::F32(frame) = frames.frame(0).unwrap() else {
+            panic!("mode 2 maps are read as f32");
+        };
+        assert_eq!(frame.dim(), (1, 1, 2));
+        assert_eq!(frame.iter().copied().collect::<Vec<_>>(), [0.25, 2.5]);
+    }
+
+    #[test]
+    fn integer_maps_keep_their_sign() {
+        let payload: Vec<u8> = [-3i16, 700].iter().flat_map(|x| x.to_be_bytes()).collect();
+        let path = map("signed", 2, 1, true, &payload);
+        let mut frames = load_aggregate_data_mrc(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let Frame::I32(frame) = frames.frame(0).unwrap() else {
+            panic!("mode 1 maps are read as integers");
+        };
+        assert_eq!(frame.iter().copied().collect::<Vec<_>>(), [-3, 700]);
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use ::netcdf::types::{BasicType, VariableType};
+use anyhow::{anyhow, Result};
+
+use super::{saturate, Frame, FrameSource, Values};
+
+/// A 4D variable of a NetCDF file, read one frame at a time. The first
+/// dimension of the variable is taken as the time axis.
+pub struct NetcdfFrames {
+    file: ::netcdf::File,
+    variable: String,
+    shape: [usize; 4],
+}
+
+impl NetcdfFrames {
+    /// Opens `variable`, or the only 4D variable of the file if none is given.
+    pub fn open(file_path: impl AsRef<Path>, variable: Option<&str>) -> Result<Self> {
+        let file = ::netcdf::open(file_path)?;
+        let variable = match variable {
+            Some(name) => name.to_string(),
+            None => {
+                let candidates: Vec<_> = file
+                    .variables()
+                    .filter(|v| v.dimensions().len() == 4)
+                    .map(|v| v.name())
+                    .collect();
+                match &candidates[..] {
+                    [name] => name.clone(),
+                    _ => {
+                        return Err(anyhow!(
+                            "expected exactly one 4D variable, found {candidates:?}; pick one with --variable"
+                        ))
+                    }
+                }
+            }
+        };
+        let var = file
+            .variable(&variable)
+            .ok_or_else(|| anyhow!("no variable named {variable}"))?;
+        let shape = match var.dimensions() {
+            [t, x, y, z] => [t.len(), x.len(), y.len(), z.len()],
+            _ => return Err(anyhow!("expected 4D array")),
+        };
+
+        Ok(Self {
+            file,
+            variable,
+            shape,
+        })
+    }
+}
+
+impl FrameSource for NetcdfFrames {
+    fn frame_count(&self) -> usize {
+        self.shape[0]
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        let var = self
+            .file
+            .variable(&self.variable)
+            .ok_or_else(|| anyhow!("no variable named {}", self.variable))?;
+        let frame = (index, .., .., ..);
+        let data = match var.vartype() {
+            VariableType::Basic(BasicType::Float) => Values::F32(var.values::<f32, _>(frame)?),
+            VariableType::Basic(BasicType::Double) => Values::F64(var.values::<f64, _>(frame)?),
+            // Wide integers saturate instead of failing with a range error
+            VariableType::Basic(BasicType::Uint | BasicType::Int64 | BasicType::Uint64) => {
+                let data = var.values::<i64, _>(frame)?;
+                Values::I32(data.into_iter().map(saturate).collect())
+            }
+            _ => Values::I32(var.values::<i32, _>(frame)?),
+        };
+        let [_, x, y, z] = self.shape;
+
+        data.into_frame([x, y, z])
+    }
+}
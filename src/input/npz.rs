@@ -0,0 +1,476 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Take};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use flate2::read::DeflateDecoder;
+use ndarray::{Array4, ArrayD, Axis, Ix2, Ix4};
+use npyz::npz::NpzArchive;
+use npyz::{DType, Deserialize, NpyFile, NpyHeader, TypeChar};
+use zip::{CompressionMethod, ZipArchive};
+
+use super::mmap::{shape_error, NpyLayout};
+use super::{
+    is_stdin, read_input, saturate, sorted_files_with_extension, Frame, FrameSource, MappedNpy,
+    SparseFrames,
+};
+
+static ARR_DEFAULT_NAME: &str = "arr_0";
+static COORDS_NAME: &str = "coords";
+static VALUES_NAME: &str = "values";
+static SHAPE_NAME: &str = "shape";
+static TIME_NAME: &str = "time";
+
+/// Opens an NPZ archive. Archives with a `coords` array hold sparse data (see
+/// `load_sparse`), which is used unless another array is picked by name.
+/// Otherwise the array called `array_name` is loaded: `arr_0` by default, or
+/// the only array of the archive besides `time` if there is no `arr_0`. Uncompressed arrays
+/// are memory-mapped and compressed ones inflated one frame at a time, so
+/// only archives read from standard input are held in memory as a whole.
+pub fn open_npz(
+    file_path: impl AsRef<Path>,
+    array_name: Option<&str>,
+    channel: Option<usize>,
+) -> Result<Box<dyn FrameSource>> {
+    let file_path = file_path.as_ref();
+    if is_stdin(file_path) {
+        let npz = NpzArchive::new(std::io::Cursor::new(read_input(file_path)?))?;
+        return open_archive(npz, None, array_name, channel);
+    }
+
+    open_archive(
+        NpzArchive::open(file_path)?,
+        Some(file_path),
+        array_name,
+        channel,
+    )
+}
+
+/// Loads an array of `npz`, mapping it from `file_path` when possible.
+fn open_archive<R: Read + Seek>(
+    mut npz: NpzArchive<R>,
+    file_path: Option<&Path>,
+    array_name: Option<&str>,
+    channel: Option<usize>,
+) -> Result<Box<dyn FrameSource>> {
+    let has_coords = npz.array_names().any(|name| name == COORDS_NAME);
+    if has_coords && array_name.is_none_or(|name| name == COORDS_NAME) {
+        return load_sparse(&mut npz);
+    }
+    let name = match array_name {
+        Some(name) => name.to_string(),
+        None => default_array_name(&npz)?,
+    };
+    if let Some(file_path) = file_path {
+        if npz.array_names().any(|n| n == name) {
+            if let Some(mapped) = MappedNpy::open_npz_member(file_path, &name, channel)? {
+                return Ok(Box::new(mapped));
+            }
+            if let Some(frames) = NpzFrames::open(file_path, &name, channel)? {
+                return Ok(Box::new(frames));
+            }
+        }
+    }
+
+    load_aggregate_data(&mut npz, &name, channel)
+}
+
+fn load_aggregate_data<R: Read + Seek>(
+    npz: &mut NpzArchive<R>,
+    name: &str,
+    channel: Option<usize>,
+) -> Result<Box<dyn FrameSource>> {
+    let arr = npz
+        .by_name(name)?
+        .ok_or_else(|| anyhow!("Could not load array by name {}", name))?;
+
+    read_frames(arr, channel)
+}
+
+/// Loads an Nx4 `coords` array of (t, x, y, z) indices of occupied voxels,
+/// with optional per-voxel `values` and the dense `shape` of the data.
+/// Floating point values are kept as they are, integers are narrowed to i32.
+fn load_sparse<R: Read + Seek>(npz: &mut NpzArchive<R>) -> Result<Box<dyn FrameSource>> {
+    let mut by_name = |name: &str| -> Result<Option<ArrayD<i64>>> {
+        npz.by_name(name)?
+            .map(|arr| read_npy_i64(arr).map_err(|e| anyhow!("{name}: {e}")))
+            .transpose()
+    };
+    let coords = by_name(COORDS_NAME)?
+        .ok_or_else(|| anyhow!("no array named {COORDS_NAME}"))?
+        .into_dimensionality::<Ix2>()
+        .ok()
+        .filter(|c| c.ncols() == 4)
+        .ok_or_else(|| anyhow!("{COORDS_NAME} must be an Nx4 array"))?;
+    let shape = match by_name(SHAPE_NAME)? {
+        Some(shape) => {
+            let dims: Vec<usize> = shape.iter().map(|&d| d as usize).collect();
+            let dims: [usize; 4] = dims
+                .try_into()
+                .map_err(|_| anyhow!("{SHAPE_NAME} must have 4 entries"))?;
+            Some(dims)
+        }
+        None => None,
+    };
+
+    let values = match npz.by_name(VALUES_NAME)? {
+        Some(arr) => {
+            let floats =
+                matches!(arr.dtype(), DType::Plain(ty) if ty.type_char() == TypeChar::Float);
+            let values = if floats {
+                read_npy_f64(arr)
+            } else {
+                read_npy_i64(arr).map(|v| v.mapv(|v| f64::from(saturate(v))))
+            };
+            let values = values.map_err(|e| anyhow!("{VALUES_NAME}: {e}"))?;
+            if values.shape() != [coords.nrows()] {
+                return Err(anyhow!(
+                    "{VALUES_NAME} must hold one value per row of {COORDS_NAME}"
+                ));
+            }
+            Some((values, floats))
+        }
+        None => None,
+    };
+
+    let entries = coords.outer_iter().enumerate().map(|(i, row)| {
+        let value = values.as_ref().map(|(v, _)| v[i]);
+        ([row[0], row[1], row[2], row[3]], value)
+    });
+    Ok(if values.as_ref().is_some_and(|&(_, floats)| floats) {
+        Box::new(SparseFrames::<f64>::new(entries, shape)?)
+    } else {
+        let entries = entries.map(|(coord, value)| (coord, value.map(|v| v as i32)));
+        Box::new(SparseFrames::<i32>::new(entries, shape)?)
+    })
+}
+
+fn default_array_name<R: Read + Seek>(npz: &NpzArchive<R>) -> Result<String> {
+    let names: Vec<_> = npz.array_names().filter(|&n| n != TIME_NAME).collect();
+    if names.contains(&ARR_DEFAULT_NAME) {
+        return Ok(ARR_DEFAULT_NAME.to_string());
+    }
+    match names[..] {
+        [name] => Ok(name.to_string()),
+        _ => Err(anyhow!(
+            "no array named {ARR_DEFAULT_NAME} among {names:?}; pick one with --array-name"
+        )),
+    }
+}
+
+/// Reads the 1D `time` array of an NPZ archive, the physical time of every
+/// frame, if there is one. Standard input cannot be read twice, so it is not
+/// looked at.
+pub fn read_times(file_path: impl AsRef<Path>) -> Result<Option<Vec<f64>>> {
+    let file_path = file_path.as_ref();
+    if is_stdin(file_path) {
+        return Ok(None);
+    }
+    let mut npz = NpzArchive::open(file_path)?;
+    let Some(arr) = npz.by_name(TIME_NAME)? else {
+        return Ok(None);
+    };
+    if arr.shape().len() != 1 {
+        return Err(anyhow!("{TIME_NAME} must be a 1D array"));
+    }
+    let DType::Plain(ty) = arr.dtype() else {
+        return Err(anyhow!("{TIME_NAME} must be a numeric array"));
+    };
+    let times = match (ty.type_char(), ty.size_field()) {
+        (TypeChar::Float, 4) => read_npy::<f32, _>(arr)?.mapv(f64::from),
+        (TypeChar::Float, 8) => read_npy::<f64, _>(arr)?,
+        _ => read_npy_i64(arr)?.mapv(|t| t as f64),
+    };
+
+    Ok(Some(times.into_raw_vec()))
+}
+
+/// Describes every array of an NPZ archive as `(name, shape, dtype)`.
+pub fn list_arrays(file_path: impl AsRef<Path>) -> Result<Vec<(String, Vec<u64>, String)>> {
+    let mut npz = NpzArchive::new(std::io::Cursor::new(read_input(file_path)?))?;
+    let mut names: Vec<String> = npz.array_names().map(String::from).collect();
+    names.sort();
+    let mut arrays = Vec::with_capacity(names.len());
+    for name in names {
+        if let Some(arr) = npz.by_name(&name)? {
+            let (shape, dtype) = (
+                arr.shape().to_vec(),
+                arr.dtype().descr().trim_matches('\'').to_string(),
+            );
+            arrays.push((name, shape, dtype));
+        }
+    }
+
+    Ok(arrays)
+}
+
+/// Loads a single 3D or 4D *.npy file.
+pub fn load_aggregate_data_npy(
+    file_path: impl AsRef<Path>,
+    channel: Option<usize>,
+) -> Result<Box<dyn FrameSource>> {
+    let file = std::io::Cursor::new(read_input(file_path)?);
+
+    read_frames(NpyFile::new(file)?, channel)
+}
+
+/// Decodes a whole NPY stream into frames of its own element type where
+/// `Frame` can hold it, and of `i32` otherwise.
+fn read_frames<R: Read>(arr: NpyFile<R>, channel: Option<usize>) -> Result<Box<dyn FrameSource>> {
+    let DType::Plain(ty) = arr.dtype() else {
+        return Err(anyhow!("expected a numeric array"));
+    };
+    Ok(match (ty.type_char(), ty.size_field()) {
+        (TypeChar::Bool, 1) => Box::new(into_4d(read_npy::<bool, _>(arr)?, channel)?),
+        (TypeChar::Uint, 1) => Box::new(into_4d(read_npy::<u8, _>(arr)?, channel)?),
+        (TypeChar::Uint, 2) => Box::new(into_4d(read_npy::<u16, _>(arr)?, channel)?),
+        (TypeChar::Int, 4) => Box::new(into_4d(read_npy::<i32, _>(arr)?, channel)?),
+        (TypeChar::Float, 4) => Box::new(into_4d(read_npy::<f32, _>(arr)?, channel)?),
+        (TypeChar::Float, 8) => Box::new(into_4d(read_npy::<f64, _>(arr)?, channel)?),
+        _ => Box::new(into_4d(read_npy_i64(arr)?.mapv(saturate), channel)?),
+    })
+}
+
+/// Decodes an NPY stream, honouring its memory order.
+fn read_npy<T: Deserialize, R: Read>(arr: NpyFile<R>) -> Result<ArrayD<T>> {
+    use ndarray::ShapeBuilder;
+    let shape: Vec<usize> = arr.shape().iter().map(|&d| d as usize).collect();
+    let order = arr.order();
+    let data: Vec<T> = arr.into_vec()?;
+    let true_shape = shape.set_f(order == npyz::Order::Fortran);
+
+    Ok(ArrayD::from_shape_vec(true_shape, data)?)
+}
+
+/// Decodes a floating point NPY stream into `f64`.
+fn read_npy_f64<R: Read>(arr: NpyFile<R>) -> Result<ArrayD<f64>> {
+    let DType::Plain(ty) = arr.dtype() else {
+        return Err(anyhow!("expected a numeric array"));
+    };
+    Ok(match (ty.type_char(), ty.size_field()) {
+        (TypeChar::Float, 4) => read_npy::<f32, _>(arr)?.mapv(f64::from),
+        (TypeChar::Float, 8) => read_npy::<f64, _>(arr)?,
+        _ => return Err(anyhow!("unsupported element type {ty}")),
+    })
+}
+
+/// Decodes a numeric NPY stream of any element type into `i64`.
+fn read_npy_i64<R: Read>(arr: NpyFile<R>) -> Result<ArrayD<i64>> {
+    let DType::Plain(ty) = arr.dtype() else {
+        return Err(anyhow!("expected a numeric array"));
+    };
+    Ok(match (ty.type_char(), ty.size_field()) {
+        (TypeChar::Bool, 1) => read_npy::<bool, _>(arr)?.mapv(i64::from),
+        (TypeChar::Int, 1) => read_npy::<i8, _>(arr)?.mapv(i64::from),
+        (TypeChar::Int, 2) => read_npy::<i16, _>(arr)?.mapv(i64::from),
+        (TypeChar::Int, 4) => read_npy::<i32, _>(arr)?.mapv(i64::from),
+        (TypeChar::Int, 8) => read_npy::<i64, _>(arr)?,
+        (TypeChar::Uint, 1) => read_npy::<u8, _>(arr)?.mapv(i64::from),
+        (TypeChar::Uint, 2) => read_npy::<u16, _>(arr)?.mapv(i64::from),
+        (TypeChar::Uint, 4) => read_npy::<u32, _>(arr)?.mapv(i64::from),
+        (TypeChar::Uint, 8) => read_npy::<u64, _>(arr)?.mapv(|v| v.min(i64::MAX as u64) as i64),
+        (TypeChar::Float, 4) => read_npy::<f32, _>(arr)?.mapv(|v| v as i64),
+        (TypeChar::Float, 8) => read_npy::<f64, _>(arr)?.mapv(|v| v as i64),
+        _ => return Err(anyhow!("unsupported element type {ty}")),
+    })
+}
+
+/// Converts to (T, X, Y, Z), taking a 3D array as a single frame and picking
+/// `channel` from a 5D (T, C, X, Y, Z) array.
+fn into_4d<T>(arr: ArrayD<T>, channel: Option<usize>) -> Result<Array4<T>> {
+    let ndim = arr.ndim();
+    let arr = match (ndim, channel) {
+        (3, None) => arr.insert_axis(Axis(0)),
+        (4, None) => arr,
+        (5, Some(c)) if c < arr.len_of(Axis(1)) => arr.index_axis_move(Axis(1), c),
+        (5, Some(c)) => {
+            let channels = arr.len_of(Axis(1));
+            return Err(anyhow!("--channel {c} is out of range 0..{channels}"));
+        }
+        _ => return Err(shape_error(ndim, channel)),
+    };
+    arr.into_dimensionality::<Ix4>()
+        .map_err(|_| shape_error(ndim, channel))
+}
+
+/// A deflate-compressed NPZ member in C order, inflated frame by frame.
+/// Frames are cheapest to request in order; going back restarts the stream.
+pub struct NpzFrames {
+    file_path: PathBuf,
+    data_start: u64,
+    compressed_size: u64,
+    layout: NpyLayout,
+    stream: DeflateDecoder<BufReader<Take<File>>>,
+    next_frame: usize,
+    buf: Vec<u8>,
+}
+
+impl NpzFrames {
+    /// Opens the NPZ member called `name`, or returns `None` if it cannot be
+    /// streamed because it is not deflated or not in C order.
+    pub fn open(
+        file_path: impl AsRef<Path>,
+        name: &str,
+        channel: Option<usize>,
+    ) -> Result<Option<Self>> {
+        let file_path = file_path.as_ref();
+        let mut zip = ZipArchive::new(File::open(file_path)?)?;
+        let member = zip.by_name(&format!("{name}.npy"))?;
+        if member.compression() != CompressionMethod::Deflated {
+            return Ok(None);
+        }
+        let (data_start, compressed_size) = (member.data_start(), member.compressed_size());
+        drop(member);
+
+        let (header, stream) = Self::inflate(file_path, data_start, compressed_size)?;
+        let layout = NpyLayout::from_header(&header, channel)?;
+        if layout.fortran_order {
+            return Ok(None);
+        }
+        let buf = vec![0; layout.frame_len()];
+
+        Ok(Some(Self {
+            file_path: file_path.to_path_buf(),
+            data_start,
+            compressed_size,
+            layout,
+            stream,
+            next_frame: 0,
+            buf,
+        }))
+    }
+
+    /// Starts inflating the member, returning its header and a stream
+    /// positioned at the first frame.
+    fn inflate(
+        file_path: &Path,
+        data_start: u64,
+        compressed_size: u64,
+    ) -> Result<(NpyHeader, DeflateDecoder<BufReader<Take<File>>>)> {
+        let mut file = File::open(file_path)?;
+        file.seek(SeekFrom::Start(data_start))?;
+        let mut stream = DeflateDecoder::new(BufReader::new(file.take(compressed_size)));
+        let header = NpyHeader::from_reader(&mut stream)?;
+
+        Ok((header, stream))
+    }
+}
+
+impl FrameSource for NpzFrames {
+    fn frame_count(&self) -> usize {
+        self.layout.shape[0]
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        if index < self.next_frame {
+            let (_, stream) =
+                Self::inflate(&self.file_path, self.data_start, self.compressed_size)?;
+            (self.stream, self.next_frame) = (stream, 0);
+        }
+        while self.next_frame <= index {
+            self.stream.read_exact(&mut self.buf)?;
+            self.next_frame += 1;
+        }
+
+        Ok(self.layout.decode_frame(&self.buf, 0))
+    }
+}
+
+/// A directory of 3D *.npy files, one per frame in file name order. Each file
+/// is only read when its frame is requested.
+pub struct NpyFrames {
+    files: Vec<PathBuf>,
+    current: Option<Box<dyn FrameSource>>,
+}
+
+impl NpyFrames {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let files = sorted_files_with_extension(dir, &["npy"])?;
+        if files.is_empty() {
+            return Err(anyhow!("no *.npy files in {}", dir.display()));
+        }
+
+        Ok(Self {
+            files,
+            current: None,
+        })
+    }
+}
+
+impl FrameSource for NpyFrames {
+    fn frame_count(&self) -> usize {
+        self.files.len()
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        let path = &self.files[index];
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let npy = NpyFile::new(file)?;
+        if npy.shape().len() != 3 {
+            return Err(anyhow!("{}: expected 3D array", path.display()));
+        }
+
+        self.current.insert(read_frames(npy, None)?).frame(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use npyz::npz::NpzWriter;
+    use npyz::{AutoSerialize, WriterBuilder};
+
+    use super::*;
+
+    /// Writes a sparse archive of two voxels with the given values.
+    fn sparse<T: AutoSerialize + Copy>(name: &str, values: [T; 2]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("npz-{}-{name}.npz", std::process::id()));
+        let mut npz = NpzWriter::create(&path).unwrap();
+        let mut arrays = |name: &str, shape: &[u64], data: &[i64]| {
+            let mut wtr = npz
+                .array::<i64>(name, Default::default())
+                .unwrap()
+                .default_dtype()
+                .shape(shape)
+                .begin_nd()
+                .unwrap();
+            wtr.extend(data.iter().copied()).unwrap();
+            wtr.finish().unwrap();
+        };
+        arrays(COORDS_NAME, &[2, 4], &[0, 0, 0, 0, 0, 0, 0, 1]);
+        arrays(SHAPE_NAME, &[4], &[1, 1, 1, 2]);
+        let mut wtr = npz
+            .array::<T>(VALUES_NAME, Default::default())
+            .unwrap()
+            .default_dtype()
+            .shape(&[2])
+            .begin_nd()
+            .unwrap();
+        wtr.extend(values).unwrap();
+        wtr.finish().unwrap();
+        npz.zip_writer().finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn sparse_float_values_are_kept() {
+        let path = sparse("float", [0.25f32, 2.5]);
+        let mut frames = open_npz(&path, None, None).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let Frame::F64(frame) = frames.frame(0).unwrap() else {
+            panic!("float values are read as f64");
+        };
+        assert_eq!(frame.iter().copied().collect::<Vec<_>>(), [0.25, 2.5]);
+    }
+
+    #[test]
+    fn sparse_integer_values_are_narrowed() {
+        let path = sparse("int", [3i64, i64::MAX]);
+        let mut frames = open_npz(&path, None, None).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let Frame::I32(frame) = frames.frame(0).unwrap() else {
+            panic!("integer values are read as i32");
+        };
+        assert_eq!(frame.iter().copied().collect::<Vec<_>>(), [3, i32::MAX]);
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+use super::{Dtype, FrameSource, Values};
+use anyhow::{anyhow, Context, Result};
+
+/// Loads a 3D (one frame) or 4D (slowest axis is time) NRRD volume, with the
+/// data either attached or in a detached file (`.nhdr`). Raw, gzip and ASCII
+/// encodings are supported.
+pub fn load_aggregate_data_nrrd(file_path: impl AsRef<Path>) -> Result<Box<dyn FrameSource>> {
+    let file_path = file_path.as_ref();
+    let bytes = std::fs::read(file_path)?;
+    if !bytes.starts_with(b"NRRD") {
+        return Err(anyhow!("{} is not an NRRD file", file_path.display()));
+    }
+
+    // The header ends at the first blank line (or at the end of a detached header)
+    let mut fields = HashMap::new();
+    let mut offset = 0;
+    for (i, line) in bytes.split(|&b| b == b'\n').enumerate() {
+        offset += line.len() + 1;
+        let line = std::str::from_utf8(line)?.trim_end_matches('\r');
+        if i == 0 || line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            break;
+        }
+        // Key-value pairs use `:=` and are not needed here
+        if let Some((field, value)) = line.split_once(": ") {
+            fields.insert(field.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    let field = |name: &str| {
+        fields
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!("NRRD header lacks the {name:?} field"))
+    };
+
+    let dtype = parse_type(field("type")?)?;
+    let sizes: Vec<usize> = field("sizes")?
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .context("invalid NRRD sizes")?;
+    // NRRD lists the fastest axis first
+    let shape = match sizes[..] {
+        [x, y, z] => [1, z, y, x],
+        [x, y, z, t] => [t, z, y, x],
+        _ => return Err(anyhow!("expected a 3D or 4D NRRD volume")),
+    };
+    let big_endian = fields.get("endian").is_some_and(|e| e == "big");
+    let encoding = fields.get("encoding").map_or("raw", String::as_str);
+
+    let data_file = fields.get("data file").or_else(|| fields.get("datafile"));
+    let payload = match data_file {
+        Some(name) if name.starts_with("LIST") || name.contains(' ') => {
+            return Err(anyhow!("NRRD data file lists are not supported"))
+        }
+        Some(name) => {
+            let dir = file_path.parent().unwrap_or(Path::new("."));
+            std::fs::read(dir.join(name))?
+        }
+        None => bytes[offset.min(bytes.len())..].to_vec(),
+    };
+    let payload = match encoding {
+        "raw" => payload,
+        "gzip" | "gz" => {
+            let mut decoded = Vec::new();
+            flate2::read::MultiGzDecoder::new(&payload[..]).read_to_end(&mut decoded)?;
+            decoded
+        }
+        "ascii" | "text" | "txt" => {
+            let values = std::str::from_utf8(&payload)?
+                .split_whitespace()
+                .map(str::parse::<f64>)
+                .collect::<Result<Vec<_>, _>>()
+                .context("invalid ASCII NRRD data")?;
+            return Values::F64(values).into_frames(shape);
+        }
+        _ => return Err(anyhow!("unsupported NRRD encoding {encoding:?}")),
+    };
+
+    let count: usize = shape.iter().product();
+    let byte_skip: usize = fields.get("byte skip").map_or(Ok(0), |s| s.parse())?;
+    let data = payload
+        .get(byte_skip..byte_skip + count * dtype.size())
+        .ok_or_else(|| anyhow!("NRRD data is shorter than its sizes imply"))?;
+
+    dtype.decode_all(data, big_endian).into_frames(shape)
+}
+
+fn parse_type(name: &str) -> Result<Dtype> {
+    Ok(match name {
+        "signed char" | "int8" | "int8_t" => Dtype::I8,
+        "uchar" | "unsigned char" | "uint8" | "uint8_t" => Dtype::U8,
+        "short" | "short int" | "signed short" | "signed short int" | "int16" | "int16_t" => {
+            Dtype::I16
+        }
+        "ushort" | "unsigned short" | "unsigned short int" | "uint16" | "uint16_t" => Dtype::U16,
+        "int" | "signed int" | "int32" | "int32_t" => Dtype::I32,
+        "uint" | "unsigned int" | "uint32" | "uint32_t" => Dtype::U32,
+        "longlong"
+        | "long long"
+        | "long long int"
+        | "signed long long"
+        | "signed long long int"
+        | "int64"
+        | "int64_t" => Dtype::I64,
+        "ulonglong" | "unsigned long long" | "unsigned long long int" | "uint64" | "uint64_t" => {
+            Dtype::U64
+        }
+        "float" => Dtype::F32,
+        "double" => Dtype::F64,
+        _ => return Err(anyhow!("unsupported NRRD type {name:?}")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::*;
+
+    fn read(name: &str, bytes: &[u8]) -> Box<dyn FrameSource> {
+        let path = std::env::temp_dir().join(format!("nrrd-{}-{name}.nrrd", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let frames = load_aggregate_data_nrrd(&path);
+        std::fs::remove_file(path).unwrap();
+        frames.unwrap()
+    }
+
+    #[test]
+    fn big_endian_floats_keep_their_values() {
+        let mut bytes =
+            b"NRRD0004\ntype: float\ndimension: 3\nsizes: 2 1 1\nendian: big\nencoding: raw\n\n"
+                .to_vec();
+        bytes.extend([0.25f32, 2.5].iter().flat_map(|x| x.to_be_bytes()));
+        let mut frames = read("float", &bytes);
+        let Frame::F32(frame) = frames.frame(0).unwrap() else {
+            panic!("float volumes are read as f32");
+        };
+        assert_eq!(frame.dim(), (1, 1, 2));
+        assert_eq!(frame.iter().copied().collect::<Vec<_>>(), [0.25, 2.5]);
+    }
+
+    #[test]
+    fn ascii_data_is_read_as_numbers() {
+        let bytes = b"NRRD0004\ntype: uchar\nsizes: 3 1 1 2\nencoding: ascii\n\n0 2 0.5\n1 1 1\n";
+        let mut frames = read("ascii", bytes);
+        assert_eq!(frames.frame_count(), 2);
+        let Frame::F64(frame) = frames.frame(0).unwrap() else {
+            panic!("ASCII data is read as f64");
+        };
+        assert_eq!(frame.iter().copied().collect::<Vec<_>>(), [0.0, 2.0, 0.5]);
+    }
+
+    #[test]
+    fn types_parse() {
+        assert_eq!(parse_type("unsigned short").unwrap(), Dtype::U16);
+        assert_eq!(parse_type("int64_t").unwrap(), Dtype::I64);
+        assert_eq!(parse_type("double").unwrap(), Dtype::F64);
+        assert!(parse_type("block").is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs::File;
+use std::path::Path;
+
+use ::parquet::file::reader::{FileReader, SerializedFileReader};
+use ::parquet::record::Field;
+use ::parquet::schema::types::Type;
+use anyhow::{anyhow, Result};
+
+use super::{FrameSource, SparseFrames};
+
+const COORD_COLUMNS: [&str; 4] = ["t", "x", "y", "z"];
+const VALUE_COLUMN: &str = "value";
+
+/// Loads a Parquet table with one row per occupied voxel, given by the
+/// integer columns `t`, `x`, `y` and `z`. An optional `value` column holds
+/// the voxel values. Without a `shape` the grid just covers all coordinates.
+pub fn load_aggregate_data_parquet(
+    file_path: impl AsRef<Path>,
+    shape: Option<[usize; 4]>,
+) -> Result<Box<dyn FrameSource>> {
+    let reader = SerializedFileReader::new(File::open(file_path)?)?;
+    let schema = reader.metadata().file_metadata().schema();
+    let fields: Vec<_> = schema
+        .get_fields()
+        .iter()
+        .filter(|f| COORD_COLUMNS.contains(&f.name()) || f.name() == VALUE_COLUMN)
+        .cloned()
+        .collect();
+    for column in COORD_COLUMNS {
+        if !fields.iter().any(|f| f.name() == column) {
+            return Err(anyhow!("the table has no {column} column"));
+        }
+    }
+    let projection = Type::group_type_builder(schema.name())
+        .with_fields(fields)
+        .build()?;
+
+    let mut entries = Vec::new();
+    // Floating point values are kept as they are, integers are narrowed to i32
+    let mut floats = false;
+    for row in reader.get_row_iter(Some(projection))? {
+        let row = row?;
+        let mut coord = [0; 4];
+        let mut value = None;
+        for (name, field) in row.get_column_iter() {
+            match COORD_COLUMNS.iter().position(|c| c == name) {
+                Some(axis) => {
+                    coord[axis] = field_as_i64(field)
+                        .ok_or_else(|| anyhow!("{name} holds {field}, expected an integer"))?
+                }
+                None => {
+                    floats |= matches!(field, Field::Float(_) | Field::Double(_));
+                    value = field_as_f64(field);
+                }
+            }
+        }
+        entries.push((coord, value));
+    }
+
+    Ok(if floats {
+        Box::new(SparseFrames::<f64>::new(entries, shape)?)
+    } else {
+        let entries = entries
+            .into_iter()
+            .map(|(coord, value)| (coord, value.map(|v| v as i32)));
+        Box::new(SparseFrames::<i32>::new(entries, shape)?)
+    })
+}
+
+fn field_as_f64(field: &Field) -> Option<f64> {
+    match *field {
+        Field::Float(v) => Some(v.into()),
+        Field::Double(v) => Some(v),
+        _ => field_as_i64(field).map(|v| v as f64),
+    }
+}
+
+fn field_as_i64(field: &Field) -> Option<i64> {
+    Some(match *field {
+        Field::Bool(v) => v as i64,
+        Field::Byte(v) => v as i64,
+        Field::Short(v) => v as i64,
+        Field::Int(v) => v as i64,
+        Field::Long(v) => v,
+        Field::UByte(v) => v as i64,
+        Field::UShort(v) => v as i64,
+        Field::UInt(v) => v as i64,
+        Field::ULong(v) => v as i64,
+        Field::Float(v) => v as i64,
+        Field::Double(v) => v as i64,
+        _ => return None,
+    })
+}
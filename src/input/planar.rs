@@ -0,0 +1,53 @@
+use anyhow::{anyhow, Result};
+use ndarray::{Axis, CowArray, Ix3};
+
+use super::{Frame, FrameSource, Voxel};
+
+/// A 2D+t input: a single 3D (T, X, Y) array read as T planar frames of
+/// X×Y×1 voxels.
+pub struct PlanarFrames {
+    volume: Frame<'static>,
+}
+
+impl PlanarFrames {
+    pub fn new(mut frames: Box<dyn FrameSource>) -> Result<Self> {
+        if !frames.has_frame(0)? || frames.has_frame(1)? {
+            return Err(anyhow!(
+                "--planar reads a single 3D (T, X, Y) array, but the input holds {} frames",
+                frames.frame_count()
+            ));
+        }
+        let volume = match frames.frame(0)? {
+            Frame::Bool(f) => Frame::Bool(f.into_owned().into()),
+            Frame::U8(f) => Frame::U8(f.into_owned().into()),
+            Frame::U16(f) => Frame::U16(f.into_owned().into()),
+            Frame::I32(f) => Frame::I32(f.into_owned().into()),
+            Frame::F32(f) => Frame::F32(f.into_owned().into()),
+            Frame::F64(f) => Frame::F64(f.into_owned().into()),
+        };
+
+        Ok(Self { volume })
+    }
+}
+
+impl FrameSource for PlanarFrames {
+    fn frame_count(&self) -> usize {
+        self.volume.shape()[0]
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        Ok(match &self.volume {
+            Frame::Bool(f) => plane(f, index),
+            Frame::U8(f) => plane(f, index),
+            Frame::U16(f) => plane(f, index),
+            Frame::I32(f) => plane(f, index),
+            Frame::F32(f) => plane(f, index),
+            Frame::F64(f) => plane(f, index),
+        })
+    }
+}
+
+/// Frame `index` of a (T, X, Y) volume, as X×Y×1 voxels.
+fn plane<'a, T: Voxel>(volume: &'a CowArray<'_, T, Ix3>, index: usize) -> Frame<'a> {
+    CowArray::from(volume.index_axis(Axis(0), index).insert_axis(Axis(2))).into()
+}
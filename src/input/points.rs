@@ -0,0 +1,188 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ndarray::Array4;
+
+/// Value given to voxels that contain at least one particle
+pub const OCCUPIED: i32 = u8::MAX as i32;
+
+/// How particle coordinates are binned onto a lattice. Unless the caller knows
+/// the domain bounds, the lattice starts at the smallest coordinate along every axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Lattice {
+    /// Edge length of a voxel; derived from `grid` if not given
+    pub voxel_size: Option<f64>,
+    /// Number of voxels along x, y and z; derived from `voxel_size` if not given
+    pub grid: Option<[usize; 3]>,
+}
+
+/// Loads whitespace or comma separated `x y z [t]` rows. Every distinct `t`
+/// becomes a frame (in ascending order); without a `t` column all particles
+/// belong to a single frame. A header line and `#` comments are skipped.
+pub fn load_aggregate_data_points(
+    file_path: impl AsRef<Path>,
+    lattice: Lattice,
+) -> Result<Array4<i32>> {
+    let text = std::fs::read_to_string(file_path)?;
+    let mut rows = Vec::new();
+    let mut columns = None;
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Result<Vec<f64>, _> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|f| !f.is_empty())
+            .map(str::parse)
+            .collect();
+        let fields = match fields {
+            Ok(fields) => fields,
+            Err(_) if columns.is_none() => continue, // header
+            Err(e) => return Err(anyhow!("line {}: {e}", line_number + 1)),
+        };
+        if !matches!(fields.len(), 3 | 4) || columns.is_some_and(|c| c != fields.len()) {
+            return Err(anyhow!(
+                "line {}: expected 3 (x,y,z) or 4 (x,y,z,t) columns consistently",
+                line_number + 1
+            ));
+        }
+        columns = Some(fields.len());
+        rows.push(fields);
+    }
+
+    let mut times: Vec<f64> = rows
+        .iter()
+        .map(|r| r.get(3).copied().unwrap_or(0.0))
+        .collect();
+    times.sort_by(f64::total_cmp);
+    times.dedup();
+    let points: Vec<_> = rows
+        .iter()
+        .map(|r| {
+            let t = r.get(3).copied().unwrap_or(0.0);
+            let frame = times.partition_point(|&x| x < t);
+            (frame, [r[0], r[1], r[2]])
+        })
+        .collect();
+
+    voxelize(&points, times.len(), lattice, None)
+}
+
+/// Bins `(frame, position)` samples onto a common lattice for all frames,
+/// spanning the half-open box `bounds` (`[min, max]`) or else the extent of the samples.
+/// Samples outside the lattice are skipped.
+pub fn voxelize(
+    points: &[(usize, [f64; 3])],
+    frames: usize,
+    lattice: Lattice,
+    bounds: Option<[[f64; 3]; 2]>,
+) -> Result<Array4<i32>> {
+    if points.is_empty() {
+        return Err(anyhow!("no particles to voxelize"));
+    }
+    let [min, max] = bounds.unwrap_or_else(|| {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for (_, p) in points {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        [min, max]
+    });
+
+    let (voxel_size, grid) = match (lattice.voxel_size, lattice.grid) {
+        (Some(size), Some(grid)) => (size, grid),
+        (size, None) => {
+            let size = size.unwrap_or(1.0);
+            let grid = [0, 1, 2].map(|a| {
+                let cells = (max[a] - min[a]) / size;
+                if bounds.is_some() {
+                    (cells.ceil() as usize).max(1)
+                } else {
+                    cells.floor() as usize + 1
+                }
+            });
+            (size, grid)
+        }
+        (None, Some(grid)) => {
+            let extent = (0..3)
+                .map(|a| (max[a] - min[a]) / grid[a] as f64)
+                .fold(0.0, f64::max);
+            // Grow the voxels slightly so that the largest coordinate falls inside the grid
+            let size = if extent > 0.0 {
+                extent * (1.0 + 1e-9)
+            } else {
+                1.0
+            };
+            (size, grid)
+        }
+    };
+    if voxel_size <= 0.0 {
+        return Err(anyhow!("voxel size must be positive"));
+    }
+
+    let mut volume = Array4::zeros([frames, grid[0], grid[1], grid[2]]);
+    for &(frame, p) in points {
+        let cell = [0, 1, 2].map(|a| ((p[a] - min[a]) / voxel_size).floor());
+        // Samples outside the lattice (or at NaN) are left out, rather than
+        // piled up on its faces
+        if (0..3).all(|a| cell[a] >= 0.0 && cell[a] < grid[a] as f64) {
+            volume[[frame, cell[0] as usize, cell[1] as usize, cell[2] as usize]] = OCCUPIED;
+        }
+    }
+
+    Ok(volume)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIT: Lattice = Lattice {
+        voxel_size: Some(1.0),
+        grid: None,
+    };
+
+    #[test]
+    fn voxelize_spans_the_samples() {
+        let points = [(0, [0.5, 0.5, 0.5]), (1, [2.5, 1.5, 0.5])];
+        let volume = voxelize(&points, 2, UNIT, None).unwrap();
+
+        assert_eq!(volume.shape(), [2, 3, 2, 1]);
+        assert_eq!(volume[[0, 0, 0, 0]], OCCUPIED);
+        assert_eq!(volume[[1, 2, 1, 0]], OCCUPIED);
+        assert_eq!(volume.iter().filter(|&&v| v == OCCUPIED).count(), 2);
+    }
+
+    #[test]
+    fn voxelize_skips_samples_outside_the_bounds() {
+        let points = [
+            (0, [1.5, 1.5, 1.5]),
+            (0, [-0.5, 1.5, 1.5]),
+            (0, [1.5, 4.0, 1.5]),
+            (0, [f64::NAN, 1.5, 1.5]),
+        ];
+        let bounds = [[0.0; 3], [4.0; 3]];
+        let volume = voxelize(&points, 1, UNIT, Some(bounds)).unwrap();
+
+        assert_eq!(volume.shape(), [1, 4, 4, 4]);
+        assert_eq!(volume[[0, 1, 1, 1]], OCCUPIED);
+        assert_eq!(volume.iter().filter(|&&v| v == OCCUPIED).count(), 1);
+    }
+
+    #[test]
+    fn voxelize_fits_a_given_grid() {
+        let points = [(0, [0.0; 3]), (0, [10.0, 5.0, 0.0])];
+        let lattice = Lattice {
+            voxel_size: None,
+            grid: Some([4, 4, 4]),
+        };
+        let volume = voxelize(&points, 1, lattice, None).unwrap();
+
+        assert_eq!(volume[[0, 0, 0, 0]], OCCUPIED);
+        assert_eq!(volume[[0, 3, 1, 0]], OCCUPIED);
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use flate2::read::MultiGzDecoder;
+
+use super::{is_stdin, ByteOrder, Dtype, Frame, FrameSource};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// A flat binary dump in C order, read one frame at a time. There is no
+/// header, so the shape, element type and byte order have to be supplied by
+/// the caller. Gzip and zstd compressed dumps are inflated on the fly.
+/// Frames are cheapest to request in order; going back restarts the stream.
+pub struct RawFrames {
+    file_path: PathBuf,
+    shape: [usize; 4],
+    dtype: Dtype,
+    byte_order: ByteOrder,
+    stream: Box<dyn Read>,
+    next_frame: usize,
+    buf: Vec<u8>,
+}
+
+impl RawFrames {
+    pub fn open(
+        file_path: impl AsRef<Path>,
+        shape: [usize; 4],
+        dtype: Dtype,
+        byte_order: ByteOrder,
+    ) -> Result<Self> {
+        let file_path = file_path.as_ref();
+        let frame_len = shape[1..].iter().product::<usize>() * dtype.size();
+        let (stream, compressed) = Self::decompress(file_path)?;
+        if !compressed && !is_stdin(file_path) {
+            let len = std::fs::metadata(file_path)?.len() as usize;
+            let expected = shape[0] * frame_len;
+            if len != expected {
+                return Err(anyhow!(
+                    "raw file holds {len} bytes, but shape {shape:?} of {dtype:?} needs {expected}"
+                ));
+            }
+        }
+
+        Ok(Self {
+            file_path: file_path.to_path_buf(),
+            shape,
+            dtype,
+            byte_order,
+            stream,
+            next_frame: 0,
+            buf: vec![0; frame_len],
+        })
+    }
+
+    /// Opens the input, inflating it if it starts with a gzip or zstd magic
+    /// number. Also returns whether it is compressed.
+    fn decompress(file_path: &Path) -> Result<(Box<dyn Read>, bool)> {
+        let input: Box<dyn Read> = if is_stdin(file_path) {
+            Box::new(std::io::stdin())
+        } else {
+            Box::new(File::open(file_path)?)
+        };
+        let mut input = BufReader::new(input);
+        let magic = input.fill_buf()?;
+
+        Ok(if magic.starts_with(&GZIP_MAGIC) {
+            (Box::new(MultiGzDecoder::new(input)), true)
+        } else if magic.starts_with(&ZSTD_MAGIC) {
+            (Box::new(zstd::Decoder::with_buffer(input)?), true)
+        } else {
+            (Box::new(input), false)
+        })
+    }
+}
+
+impl FrameSource for RawFrames {
+    fn frame_count(&self) -> usize {
+        self.shape[0]
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        if index < self.next_frame {
+            if is_stdin(&self.file_path) {
+                return Err(anyhow!("cannot go back to frame {index} of standard input"));
+            }
+            (self.stream, self.next_frame) = (Self::decompress(&self.file_path)?.0, 0);
+        }
+        while self.next_frame <= index {
+            self.stream
+                .read_exact(&mut self.buf)
+                .map_err(|e| match e.kind() {
+                    ErrorKind::UnexpectedEof => anyhow!(
+                        "raw input ends in frame {} of shape {:?}",
+                        self.next_frame,
+                        self.shape
+                    ),
+                    _ => e.into(),
+                })?;
+            self.next_frame += 1;
+        }
+        if index + 1 == self.shape[0] && self.stream.read(&mut [0])? != 0 {
+            return Err(anyhow!(
+                "raw input holds more data than shape {:?} of {:?}",
+                self.shape,
+                self.dtype
+            ));
+        }
+
+        let [_, nx, ny, nz] = self.shape;
+        let big_endian = self.byte_order == ByteOrder::Big;
+        self.dtype
+            .decode_all(&self.buf, big_endian)
+            .into_frame([nx, ny, nz])
+    }
+}
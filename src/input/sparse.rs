@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Result};
+use ndarray::{Array3, CowArray};
+
+use super::points::OCCUPIED;
+use super::{Frame, FrameSource, Voxel};
+
+/// Occupied voxels stored as per-frame coordinate lists. A dense frame is
+/// only assembled when it is requested.
+pub struct SparseFrames<T = i32> {
+    shape: [usize; 4],
+    frames: Vec<Vec<([usize; 3], T)>>,
+}
+
+impl<T: Voxel + Default + From<i32>> SparseFrames<T> {
+    /// Groups `(t, x, y, z)` coordinates, each with an optional value
+    /// (`OCCUPIED` if absent), into frames. Without a `shape` the grid just
+    /// covers the largest coordinate along every axis.
+    pub fn new(
+        entries: impl IntoIterator<Item = ([i64; 4], Option<T>)>,
+        shape: Option<[usize; 4]>,
+    ) -> Result<Self> {
+        let mut voxels = Vec::new();
+        let mut extent = [0; 4];
+        for (coord, value) in entries {
+            let idx = coord.map(|c| usize::try_from(c).ok());
+            let [Some(t), Some(x), Some(y), Some(z)] = idx else {
+                return Err(anyhow!("negative coordinate {coord:?}"));
+            };
+            let idx = [t, x, y, z];
+            for (e, i) in extent.iter_mut().zip(idx) {
+                *e = (*e).max(i + 1);
+            }
+            voxels.push((idx, value.unwrap_or(T::from(OCCUPIED))));
+        }
+        let shape = match shape {
+            Some(shape) => {
+                if extent.iter().zip(shape).any(|(&e, s)| e > s) {
+                    return Err(anyhow!(
+                        "coordinates span {extent:?}, which does not fit in shape {shape:?}"
+                    ));
+                }
+                shape
+            }
+            None => extent,
+        };
+
+        let mut frames = vec![Vec::new(); shape[0]];
+        for ([t, x, y, z], value) in voxels {
+            frames[t].push(([x, y, z], value));
+        }
+
+        Ok(Self { shape, frames })
+    }
+}
+
+impl<T: Voxel + Default> FrameSource for SparseFrames<T> {
+    fn frame_count(&self) -> usize {
+        self.shape[0]
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        let [_, nx, ny, nz] = self.shape;
+        let mut frame = Array3::from_elem((nx, ny, nz), T::default());
+        for &(idx, value) in &self.frames[index] {
+            frame[idx] = value;
+        }
+
+        Ok(CowArray::from(frame).into())
+    }
+}
@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::{saturate, sorted_files_with_extension, FrameSource, Values};
+use ::tiff::decoder::{Decoder, DecodingResult, Limits};
+use ::tiff::ColorType;
+use anyhow::{anyhow, Result};
+
+/// Loads TIFF stacks. A single multi-page file is one 3D frame with the pages
+/// along the first spatial axis, a directory holds one such stack per frame.
+pub fn load_aggregate_data_tiff(path: impl AsRef<Path>) -> Result<Box<dyn FrameSource>> {
+    let path = path.as_ref();
+    let files = if path.is_dir() {
+        sorted_files_with_extension(path, &["tif", "tiff"])?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut data = Vec::new();
+    let mut frame_shape = None;
+    for file in &files {
+        let (shape, frame) = read_stack(file)?;
+        match frame_shape {
+            None => frame_shape = Some(shape),
+            Some(s) if s != shape => {
+                return Err(anyhow!(
+                    "{} has shape {:?}, expected {:?}",
+                    file.display(),
+                    shape,
+                    s
+                ))
+            }
+            _ => {}
+        }
+        data.push(frame);
+    }
+    let [z, y, x] = frame_shape.ok_or_else(|| anyhow!("no TIFF files in {}", path.display()))?;
+
+    Values::concat(data).into_frames((files.len(), z, y, x))
+}
+
+/// Decodes all pages of a grayscale TIFF into a flat (pages, height, width) buffer.
+fn read_stack(path: &Path) -> Result<([usize; 3], Values)> {
+    let mut decoder =
+        Decoder::new(BufReader::new(File::open(path)?))?.with_limits(Limits::unlimited());
+    let (width, height) = decoder.dimensions()?;
+    let mut data = Vec::new();
+    let mut pages = 0;
+    loop {
+        if decoder.dimensions()? != (width, height) {
+            return Err(anyhow!("pages of {} differ in size", path.display()));
+        }
+        if !matches!(decoder.colortype()?, ColorType::Gray(_)) {
+            return Err(anyhow!("{} is not a grayscale TIFF", path.display()));
+        }
+        let ints = |v: Vec<i32>| Values::I32(v);
+        data.push(match decoder.read_image()? {
+            DecodingResult::U8(v) => ints(v.into_iter().map(i32::from).collect()),
+            DecodingResult::U16(v) => ints(v.into_iter().map(i32::from).collect()),
+            DecodingResult::U32(v) => ints(v.into_iter().map(saturate).collect()),
+            DecodingResult::U64(v) => ints(v.into_iter().map(saturate).collect()),
+            DecodingResult::F32(v) => Values::F32(v),
+            DecodingResult::F64(v) => Values::F64(v),
+            DecodingResult::I8(v) => ints(v.into_iter().map(i32::from).collect()),
+            DecodingResult::I16(v) => ints(v.into_iter().map(i32::from).collect()),
+            DecodingResult::I32(v) => ints(v),
+            DecodingResult::I64(v) => ints(v.into_iter().map(saturate).collect()),
+        });
+        pages += 1;
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()?;
+    }
+
+    Ok((
+        [pages, height as usize, width as usize],
+        Values::concat(data),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use ::tiff::encoder::{colortype, TiffEncoder};
+
+    use super::super::Frame;
+    use super::*;
+
+    /// Reads a stack of two 2×1 pages written by `write`.
+    fn read(name: &str, write: impl Fn(&mut TiffEncoder<File>)) -> Box<dyn FrameSource> {
+        let path = std::env::temp_dir().join(format!("tiff-{}-{name}.tif", std::process::id()));
+        write(&mut TiffEncoder::new(File::create(&path).unwrap()).unwrap());
+        let frames = load_aggregate_data_tiff(&path);
+        std::fs::remove_file(path).unwrap();
+        frames.unwrap()
+    }
+
+    #[test]
+    fn float_pages_keep_their_values() {
+        let mut frames = read("float", |tiff| {
+            for page in [[0.25f32, 2.5], [0.0, 1.0]] {
+                tiff.write_image::<colortype::Gray32Float>(2, 1, &page)
+                    .unwrap();
+            }
+        });
+        let Frame::F32(frame) = frames.frame(0).unwrap() else {
+            panic!("float pages are read as f32");
+        };
+        assert_eq!(frame.dim(), (2, 1, 2));
+        assert_eq!(
+            frame.iter().copied().collect::<Vec<_>>(),
+            [0.25, 2.5, 0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn wide_integers_saturate() {
+        let mut frames = read("wide", |tiff| {
+            tiff.write_image::<colortype::Gray32>(2, 1, &[7, u32::MAX])
+                .unwrap();
+        });
+        let Frame::I32(frame) = frames.frame(0).unwrap() else {
+            panic!("integer pages are read as i32");
+        };
+        assert_eq!(frame.iter().copied().collect::<Vec<_>>(), [7, i32::MAX]);
+    }
+}
@@ -0,0 +1,154 @@
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ndarray::{s, Array3, ArrayView3};
+use vtkio::model::{Attribute, DataSet, Extent, IOBuffer, ImageDataPiece};
+use vtkio::Vtk;
+
+use super::{saturate, sorted_files_with_extension, FrameSource, Values};
+
+/// Loads legacy VTK structured points or VTK XML image data. A single file is
+/// one frame, a directory holds one file per frame. The scalar array is picked
+/// by name, or the first single-component point (then cell) array is used.
+pub fn load_aggregate_data_vtk(
+    path: impl AsRef<Path>,
+    field: Option<&str>,
+) -> Result<Box<dyn FrameSource>> {
+    let path = path.as_ref();
+    let files = if path.is_dir() {
+        sorted_files_with_extension(path, &["vtk", "vti", "pvti"])?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut frames = Vec::new();
+    let mut frame_shape = None;
+    for file in &files {
+        let (shape, values) = read_image_data(file, field)?;
+        if frame_shape.is_some_and(|s| s != shape) {
+            return Err(anyhow!(
+                "{} differs in size from the other images",
+                file.display()
+            ));
+        }
+        frame_shape = Some(shape);
+        frames.push(values);
+    }
+    let [z, y, x] = frame_shape.ok_or_else(|| anyhow!("no VTK files in {}", path.display()))?;
+
+    Values::concat(frames).into_frames((files.len(), z, y, x))
+}
+
+/// Reads a VTK image as the values of a (z, y, x) array, stitching together
+/// all of its pieces.
+fn read_image_data(path: &Path, field: Option<&str>) -> Result<([usize; 3], Values)> {
+    let vtk = Vtk::import(path)?;
+    let (extent, pieces) = match vtk.data {
+        DataSet::ImageData { extent, pieces, .. } => (extent, pieces),
+        _ => return Err(anyhow!("{} is not structured image data", path.display())),
+    };
+    let whole = point_ranges(extent);
+
+    // The offset and shape of every piece in the frame, and the frame shape
+    let mut pieces_placed = Vec::new();
+    let mut piece_values = Vec::new();
+    let mut frame_shape = None;
+    for piece in pieces {
+        let ImageDataPiece { extent, data } = piece.into_loaded_piece_data(Some(path))?;
+        let piece_ranges = point_ranges(extent);
+        let (is_cell, values) = pick_scalars(data.point, data.cell, field)
+            .ok_or_else(|| anyhow!("no matching scalar array in {}", path.display()))?;
+        // Cell data has one value less than point data along every axis.
+        let len =
+            |r: &RangeInclusive<i32>| (r.end() - r.start() + 1 - is_cell as i32).max(0) as usize;
+        let piece_shape = [
+            len(&piece_ranges[2]),
+            len(&piece_ranges[1]),
+            len(&piece_ranges[0]),
+        ];
+        if values.len() != piece_shape.iter().product::<usize>() {
+            return Err(anyhow!(
+                "scalar array in {} does not match its extent",
+                path.display()
+            ));
+        }
+        frame_shape.get_or_insert([len(&whole[2]), len(&whole[1]), len(&whole[0])]);
+        let offset = |axis: usize| (piece_ranges[axis].start() - whole[axis].start()) as usize;
+        pieces_placed.push(([offset(2), offset(1), offset(0)], piece_shape));
+        piece_values.push(values);
+    }
+    let shape = frame_shape.ok_or_else(|| anyhow!("{} contains no pieces", path.display()))?;
+
+    let values = match Values::concat(piece_values) {
+        Values::I32(v) => Values::I32(stitch(shape, &pieces_placed, &v)?),
+        Values::F32(v) => Values::F32(stitch(shape, &pieces_placed, &v)?),
+        Values::F64(v) => Values::F64(stitch(shape, &pieces_placed, &v)?),
+    };
+    Ok((shape, values))
+}
+
+/// Places the `values` of consecutive pieces, each at its offset and of its
+/// shape, in a frame of `shape`, and returns the values of the frame in C
+/// order.
+fn stitch<T: Copy + Default>(
+    shape: [usize; 3],
+    pieces: &[([usize; 3], [usize; 3])],
+    values: &[T],
+) -> Result<Vec<T>> {
+    let mut frame = Array3::from_elem(shape, T::default());
+    let mut start = 0;
+    for &([z0, y0, x0], piece_shape) in pieces {
+        let len = piece_shape.iter().product::<usize>();
+        let piece = ArrayView3::from_shape(piece_shape, &values[start..start + len])?;
+        frame
+            .slice_mut(s![
+                z0..z0 + piece_shape[0],
+                y0..y0 + piece_shape[1],
+                x0..x0 + piece_shape[2]
+            ])
+            .assign(&piece);
+        start += len;
+    }
+    Ok(frame.into_raw_vec())
+}
+
+/// Inclusive point index ranges of an extent. Unlike `Extent::into_ranges`,
+/// legacy dimensions map to `0..=n - 1`.
+fn point_ranges(extent: Extent) -> [RangeInclusive<i32>; 3] {
+    match extent {
+        Extent::Dims(dims) => dims.map(|n| 0..=n as i32 - 1),
+        Extent::Ranges(ranges) => ranges,
+    }
+}
+
+/// Returns whether the picked array is cell data, along with its values.
+fn pick_scalars(
+    point: Vec<Attribute>,
+    cell: Vec<Attribute>,
+    field: Option<&str>,
+) -> Option<(bool, Values)> {
+    let tagged = point
+        .into_iter()
+        .map(|a| (false, a))
+        .chain(cell.into_iter().map(|a| (true, a)));
+    for (is_cell, attribute) in tagged {
+        let Attribute::DataArray(array) = attribute else {
+            continue;
+        };
+        let wanted = match field {
+            Some(name) => array.name == name,
+            None => array.num_comp() == 1,
+        };
+        if wanted && array.num_comp() == 1 {
+            let values = match array.data {
+                IOBuffer::F32(v) => Values::F32(v),
+                IOBuffer::F64(v) => Values::F64(v),
+                data => Values::I32(data.cast_into::<i64>()?.into_iter().map(saturate).collect()),
+            };
+            return Some((is_cell, values));
+        }
+    }
+
+    None
+}
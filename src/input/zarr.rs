@@ -0,0 +1,91 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use ndarray::{Array3, CowArray};
+use zarrs::array::data_type::{
+    BoolDataType, Float32DataType, Float64DataType, Int16DataType, Int32DataType, Int64DataType,
+    Int8DataType, UInt16DataType, UInt32DataType, UInt64DataType, UInt8DataType,
+};
+use zarrs::array::{Array, ArraySubset, ElementOwned};
+use zarrs::filesystem::FilesystemStore;
+
+use super::{saturate, Frame, FrameSource, Values};
+
+/// A 4D Zarr array (v2 or v3) on disk. Frames are decoded one at a time, so
+/// only the chunks overlapping the requested frame are ever read.
+pub struct ZarrFrames {
+    array: Array<FilesystemStore>,
+    shape: [u64; 4],
+}
+
+impl ZarrFrames {
+    /// Opens the array at `array_path` (default: the store root) inside the store at `store_path`.
+    pub fn open(store_path: impl AsRef<Path>, array_path: Option<&str>) -> Result<Self> {
+        let store = Arc::new(FilesystemStore::new(store_path)?);
+        let array_path = match array_path {
+            Some(p) if p.starts_with('/') => p.to_string(),
+            Some(p) => format!("/{p}"),
+            None => "/".to_string(),
+        };
+        let array = Array::open(store, &array_path)?;
+        let shape = match array.shape() {
+            &[t, x, y, z] => [t, x, y, z],
+            _ => return Err(anyhow!("expected 4D array")),
+        };
+
+        Ok(Self { array, shape })
+    }
+
+    /// Integer elements narrowed to `i32`, saturating.
+    fn retrieve<T>(&self, subset: &ArraySubset) -> Result<Values>
+    where
+        T: ElementOwned + TryInto<i32> + PartialOrd + Default + Copy,
+    {
+        let data: Vec<T> = self.array.retrieve_array_subset(subset)?;
+        Ok(Values::I32(data.into_iter().map(saturate).collect()))
+    }
+}
+
+impl FrameSource for ZarrFrames {
+    fn frame_count(&self) -> usize {
+        self.shape[0] as usize
+    }
+
+    fn frame(&mut self, index: usize) -> Result<Frame<'_>> {
+        let [_, x, y, z] = self.shape;
+        let t = index as u64;
+        let subset = ArraySubset::new_with_ranges(&[t..t + 1, 0..x, 0..y, 0..z]);
+
+        let data_type = self.array.data_type();
+        let shape = [x as usize, y as usize, z as usize];
+        let data = if data_type.is::<Int32DataType>() {
+            self.retrieve::<i32>(&subset)?
+        } else if data_type.is::<UInt8DataType>() {
+            self.retrieve::<u8>(&subset)?
+        } else if data_type.is::<Int8DataType>() {
+            self.retrieve::<i8>(&subset)?
+        } else if data_type.is::<UInt16DataType>() {
+            self.retrieve::<u16>(&subset)?
+        } else if data_type.is::<Int16DataType>() {
+            self.retrieve::<i16>(&subset)?
+        } else if data_type.is::<UInt32DataType>() {
+            self.retrieve::<u32>(&subset)?
+        } else if data_type.is::<Int64DataType>() {
+            self.retrieve::<i64>(&subset)?
+        } else if data_type.is::<UInt64DataType>() {
+            self.retrieve::<u64>(&subset)?
+        } else if data_type.is::<Float32DataType>() {
+            Values::F32(self.array.retrieve_array_subset(&subset)?)
+        } else if data_type.is::<Float64DataType>() {
+            Values::F64(self.array.retrieve_array_subset(&subset)?)
+        } else if data_type.is::<BoolDataType>() {
+            let data: Vec<bool> = self.array.retrieve_array_subset(&subset)?;
+            return Ok(CowArray::from(Array3::from_shape_vec(shape, data)?).into());
+        } else {
+            return Err(anyhow!("unsupported Zarr data type"));
+        };
+
+        data.into_frame(shape)
+    }
+}
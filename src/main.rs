@@ -1,127 +1,1330 @@
+mod analysis;
+mod input;
+mod output;
+
 use std::path::{Path, PathBuf};
 
+use analysis::{
+    Backend, Comparison, Connectivity, Cut, FitOptions, Method, Padding, Phase, Regression,
+    Settings, Threshold,
+};
 use anyhow::{anyhow, Result};
 use clap::Parser;
-use fractal_analysis::*;
-use ndarray::{Array4, ArrayView3};
-use npyz::Deserialize;
-use rayon::prelude::*;
-
-static ARR_DEFAULT_NAME: &str = "arr_0";
+use input::{ByteOrder, Dtype, Frame, FrameSource, InputFormat, Lattice, Pooling};
+use output::{
+    Column, CurveTable, FieldExport, OutputFormat, Plot, Provenance, Record, ResultWriter, RunInfo,
+    SeriesOptions, Smoothing, Summary, Value, WriterOptions,
+};
 
 /// A CLI tool that takes 3D+t aggregation simulations
-/// as 4D *.npz matrices and calculates the fractal dimension
+/// as 4D matrices (see --format) and calculates the fractal dimension
 /// of the aggregate.
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, serde::Serialize)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// Path to the simulation output
-    #[arg()]
-    npz_file_path: PathBuf,
+    /// Paths or glob patterns of the simulation outputs (files, directories with one file per frame, http(s):// or s3:// URLs, or - for stdin)
+    #[arg(required = true)]
+    input_paths: Vec<PathBuf>,
+
+    /// Format of the simulation output [default: inferred from the file extension]
+    #[arg(short = 'f', long, value_enum)]
+    format: Option<InputFormat>,
+
+    /// Dataset inside the input: an NPZ array name, an HDF5 or Zarr path, or a VTK/NetCDF/MAT variable name; repeat to analyse several in one pass [default: arr_0 or the only/first one]
+    #[arg(long, visible_aliases = ["array-name", "variable"])]
+    dataset: Vec<String>,
+
+    /// Channel to analyse in 5D (T, C, X, Y, Z) NPZ and NPY inputs
+    #[arg(long)]
+    channel: Option<usize>,
+
+    /// Read a single 3D array as 2D+t data, (T, X, Y), and compute the 2D dimension of every frame; a raw input takes --shape 1,T,X,Y
+    #[arg(long)]
+    planar: bool,
+
+    /// Only analyse this region of every frame, as X0:X1,Y0:Y1,Z0:Z1 voxels with the ends left out
+    #[arg(long, value_parser = input::parse_region)]
+    crop: Option<[[usize; 2]; 3]>,
+
+    /// Coarse-grain every frame by this factor along every axis, after --crop: the voxels are binarized (by --threshold or --keep, if given) and each block of N³ of them merged into one with --pooling
+    #[arg(long, value_parser = parse_factor)]
+    downsample: Option<usize>,
+
+    /// How --downsample merges the binarized voxels of a block
+    #[arg(long, value_enum, default_value_t = Pooling::Mean, requires = "downsample")]
+    pooling: Pooling,
+
+    /// Only analyse the frames from START up to, but not including, END in steps of STEP, as START:END:STEP; END may be left out for the rest of the input, and STEP for every frame, e.g. 1000: or 0::10
+    #[arg(long, value_parser = parse_frames)]
+    frames: Option<FrameRange>,
+
+    /// List the arrays of an NPZ input and exit
+    #[arg(long)]
+    list_arrays: bool,
+
+    /// Shape of a raw input, or the dense size of a Parquet voxel table or Arrow stream frames, as T,X,Y,Z (T is ignored for Arrow streams)
+    #[arg(long, value_parser = input::parse_dims::<4>)]
+    shape: Option<[usize; 4]>,
+
+    /// Element type of a raw input
+    #[arg(long, value_enum, default_value_t = Dtype::I32)]
+    dtype: Dtype,
+
+    /// Byte order of a raw input; NPY and NPZ inputs declare their own
+    #[arg(long, value_enum, default_value_t = ByteOrder::Little)]
+    byte_order: ByteOrder,
+
+    /// Voxel edge length used to bin point-cloud and LAMMPS inputs [default: 1, or derived from --grid]
+    #[arg(long)]
+    voxel_size: Option<f64>,
+
+    /// Lattice size of point-cloud and LAMMPS inputs as NX,NY,NZ [default: derived from --voxel-size]
+    #[arg(long, value_parser = input::parse_dims::<3>)]
+    grid: Option<[usize; 3]>,
+
+    /// File name pattern grouping an image directory into Z-stacks, with {t} and {z} placeholders such as t{t}_z{z}.png [default: one flat frame per image]
+    #[arg(long)]
+    frame_pattern: Option<String>,
+
+    /// Fraction of the full gray range at or above which image pixels belong to the aggregate
+    #[arg(long, default_value_t = 0.5)]
+    image_threshold: f64,
+
+    /// Only keep LAMMPS atoms of these types (comma separated) [default: all]
+    #[arg(long, value_delimiter = ',')]
+    atom_types: Vec<u32>,
+
+    /// Binarize with this threshold instead of the built-in rule (value >= 2, or true): voxels whose value passes --threshold-op against it belong to the aggregate; otsu picks it for every frame by Otsu's method
+    #[arg(long, allow_hyphen_values = true, value_parser = Cut::parse)]
+    threshold: Option<Cut>,
+
+    /// How voxel values are compared with --threshold and --threshold-sweep
+    #[arg(long, value_enum, default_value_t = Comparison::Ge)]
+    threshold_op: Comparison,
+
+    /// Estimate the dimension of every frame binarized at every threshold from START to STOP in steps of STEP, as START:STOP:STEP, and write them to --sweep-table
+    #[arg(
+        long,
+        value_parser = parse_sweep,
+        allow_hyphen_values = true,
+        requires = "sweep_table",
+        conflicts_with = "downsample"
+    )]
+    threshold_sweep: Option<[f64; 3]>,
+
+    /// Delimited table of the --threshold-sweep dimensions, as Threshold, FractalDimension and OccupiedVoxels rows
+    #[arg(long, requires = "threshold_sweep")]
+    sweep_table: Option<PathBuf>,
+
+    /// Binarize by value instead: voxels whose value is one of these (comma separated), or in an inclusive range such as 3..=7, belong to the aggregate
+    #[arg(
+        long,
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        value_parser = analysis::parse_range,
+        conflicts_with = "threshold"
+    )]
+    keep: Vec<[f64; 2]>,
+
+    /// Analyse the complementary phase, the empty voxels or pores, instead of the aggregate
+    #[arg(long)]
+    invert: bool,
+
+    /// Fractal dimension estimator: the fast zbox merge, classical grid box counting at box sizes 1, 2, 4, ... voxels (slower, the reference method), the correlation dimension D2 (less biased for sparse aggregates), the mass-radius dimension of the DLA literature, or the sandbox dimension around random occupied voxels (a cross-check on small aggregates)
+    #[arg(long, value_enum, default_value_t = Method::Zbox)]
+    method: Method,
+
+    /// Voxel the spheres of --method mass-radius are grown around, as X,Y,Z [default: the centre of mass of each frame]
+    #[arg(long, value_parser = input::parse_dims::<3>)]
+    centre: Option<[usize; 3]>,
+
+    /// Most occupied voxels the windows of --method sandbox are centred on, drawn at random but the same on every run
+    #[arg(long, default_value_t = 100)]
+    sandboxes: usize,
+
+    /// Embed every frame in the smallest empty cube with an edge of a power of two voxels before --method zbox, so that the Morton keys scale every axis alike
+    #[arg(long)]
+    pad: bool,
+
+    /// Embed every frame in an empty box of X,Y,Z voxels instead of --pad's cube, no smaller than any frame
+    #[arg(long, value_parser = input::parse_dims::<3>, conflicts_with = "pad")]
+    pad_to: Option<[usize; 3]>,
+
+    /// Smallest scale, in voxels, that enters the fit of the dimension: the box edge, or the radius for --method correlation, mass-radius and sandbox
+    #[arg(long)]
+    min_scale: Option<f64>,
+
+    /// Largest scale, in voxels, that enters the fit of the dimension
+    #[arg(long)]
+    max_scale: Option<f64>,
+
+    /// How the line through the log-log data is fitted
+    #[arg(long, value_enum, default_value_t = Regression::Ols)]
+    fit: Regression,
+
+    /// Fit the dimension on the most linear run of at least --auto-scales-points successive scales, and write the scales used as FitMinScale and FitMaxScale
+    #[arg(long)]
+    auto_scales: bool,
+
+    /// Fewest scales --auto-scales may fit on, at least 3
+    #[arg(long, default_value_t = 3)]
+    auto_scales_points: usize,
+
+    /// Also write the generalized (Rényi) dimensions D_q of these q values (comma separated), from grid box counting on the box sizes --min-scale, --max-scale and --auto-scales pick, as D<q> columns
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    q: Vec<f64>,
+
+    /// Also write the radial pair-correlation function g(r) of every frame, as Distance and PairCorrelation rows of a delimited table
+    #[arg(long)]
+    pair_correlation: Option<PathBuf>,
+
+    /// How g(r) counts the pairs of occupied voxels: by FFT (for dense frames) or pair by pair (for sparse ones)
+    #[arg(long, value_enum, default_value_t = Backend::Fft)]
+    pair_correlation_backend: Backend,
+
+    /// Also write the radially averaged structure factor S(q) of every frame, as Q (in radians per voxel) and StructureFactor rows of a delimited table
+    #[arg(long)]
+    structure_factor: Option<PathBuf>,
+
+    /// Also write the chord-length distributions of the aggregate along X, Y and Z, as Axis (0 to 2), Length and Count rows of a delimited table
+    #[arg(long)]
+    chord_lengths: Option<PathBuf>,
+
+    /// Also write the branch-length distribution of the curve skeleton of every frame, as Length and Count rows of a delimited table
+    #[arg(long)]
+    branch_lengths: Option<PathBuf>,
+
+    /// Also write the histogram of the coordination numbers (occupied neighbours under --connectivity) of the occupied voxels, as Neighbours and Count rows of a delimited table
+    #[arg(long)]
+    coordination: Option<PathBuf>,
+
+    /// Also write the 2D box-counting dimension of every Z slice of every frame, as Slice, FractalDimension and OccupiedPixels rows of a delimited table
+    #[arg(long)]
+    slices: Option<PathBuf>,
+
+    /// Split every frame into this grid of tiles, as NxNxN, and write the dimension of each to --tile-table
+    #[arg(long, value_parser = parse_tiles, requires = "tile_table")]
+    tiles: Option<[usize; 3]>,
+
+    /// Delimited table of the --tiles dimensions, as TileX, TileY, TileZ and FractalDimension rows
+    #[arg(long, requires = "tiles")]
+    tile_table: Option<PathBuf>,
+
+    /// Also write the multifractal singularity spectrum f(alpha) of every frame, as Q, Alpha and F rows of a delimited table
+    #[arg(long)]
+    spectrum: Option<PathBuf>,
+
+    /// q values the singularity spectrum is sampled at (comma separated)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        default_value = "-5,-4,-3,-2,-1,0,1,2,3,4,5"
+    )]
+    spectrum_q: Vec<f64>,
 
-    /// Path to the output file (CSV)
+    /// Only write the singularity spectrum of these frames (comma separated) [default: all]
+    #[arg(long, value_delimiter = ',')]
+    spectrum_frames: Vec<usize>,
+
+    /// Also write the gliding-box lacunarity curve of every frame, as BoxSize and Lacunarity rows of a delimited table
+    #[arg(long)]
+    lacunarity_curve: Option<PathBuf>,
+
+    /// Also write the counts the dimension of every frame is fitted to, as Level, Scale (in voxels), Log2Count and Fitted rows of a delimited table
+    #[arg(long)]
+    counts: Option<PathBuf>,
+
+    /// Also write the correlation integral C(r) of every frame that --method correlation fits, as Radius and CorrelationIntegral rows of a delimited table
+    #[arg(long)]
+    correlation_integral: Option<PathBuf>,
+
+    /// Path to the output file, or - for standard output
     #[arg(short = 'o', long, default_value = "fractal_dimension.csv")]
     output_file: PathBuf,
 
+    /// Format of the output file [default: inferred from the file extension, else csv]
+    #[arg(long, value_enum)]
+    output_format: Option<OutputFormat>,
+
+    /// Write one output file per input, prefixed with its name, instead of one table with a Run column
+    #[arg(long)]
+    per_input: bool,
+
+    /// Result columns to write, comma separated: time, rate (dD/dt, see --rate-stencil), surface (the dimension of the boundary voxels), projected (the dimensions of the projections along X, Y and Z), lacunarity, information (the information dimension D1), mass (the occupied voxel count), rg (the radius of gyration), anisotropy (of the gyration tensor), clusters (their number, mean and largest size), porosity and hull (the solid fraction of the bounding box and convex hull), minkowski (volume, surface area, integral mean curvature and Euler characteristic), euler (the Euler characteristic alone), sv (the surface area over the volume), percolation (whether a cluster spans the frame along each axis), succolarity (how far a fluid penetrates the pores along each axis), tortuosity (of the shortest paths across each axis), coordination (the mean number of occupied neighbours), skeleton (the voxels, endpoints, junctions and branches of the curve skeleton), r2 and rms (fit quality), stderr and ci (uncertainty of the dimension), scales (the range the dimension is fitted on) or all; frame and dim are always written
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "frame,time,dim"
+    )]
+    columns: Vec<Column>,
+
+    /// Also write BootstrapMean and BootstrapStd columns: the mean and standard deviation of the dimension over this many replicates of every frame, each with its occupied voxels drawn with replacement; at least 2
+    #[arg(long, value_parser = parse_replicates)]
+    bootstrap: Option<usize>,
+
+    /// Seed of the first bootstrap replicate, so that runs can be repeated
+    #[arg(long, default_value_t = 0)]
+    bootstrap_seed: u64,
+
+    /// Number of frames on either side of a frame that the rate column fits the slope of the dimension over
+    #[arg(long, default_value_t = 2)]
+    rate_stencil: usize,
+
+    /// Also write a SmoothedDimension column: the moving average over an odd number of frames with window=N, or a Savitzky-Golay (local quadratic) fit with savgol=N
+    #[arg(long, value_parser = Smoothing::parse)]
+    smooth: Option<Smoothing>,
+
+    /// Time step between frames, for a Time column [default: the `time` array of an NPZ input, if any]
+    #[arg(long)]
+    dt: Option<f64>,
+
+    /// Add rows, with a Run column, to an existing CSV or JSON Lines output instead of replacing it (SQLite databases are always added to)
+    #[arg(long)]
+    append: bool,
+
+    /// Also plot the fractal dimension against the frame number, as an .svg or .png image
+    #[arg(long)]
+    plot: Option<PathBuf>,
+
+    /// Also write a run summary (final dimension, mean over the last --summary-frames frames, growth exponent of the mass, runtime, parameters) as a .yaml or .toml file
+    #[arg(long)]
+    summary: Option<PathBuf>,
+
+    /// Number of trailing frames the mean dimension of the summary is taken over
+    #[arg(long, default_value_t = 10)]
+    summary_frames: usize,
+
+    /// First and last frame, comma separated, that the growth exponent beta of M ~ t^beta in the summary is fitted over [default: all frames]
+    #[arg(long, value_parser = input::parse_dims::<2>)]
+    growth_frames: Option<[usize; 2]>,
+
+    /// Also export spatially resolved results, such as the binarized `mask` of every frame, as (T, X, Y, Z) arrays of an NPZ file
+    #[arg(long)]
+    export_fields: Option<PathBuf>,
+
+    /// Also export a `local_dimension` field with the box-counting dimension of every cubic window of this edge (in voxels) for --export-fields
+    #[arg(long, requires = "export_fields")]
+    local_window: Option<usize>,
+
+    /// Distance between the windows of --local-window, which overlap when it is shorter than the window [default: half the window]
+    #[arg(long, requires = "local_window")]
+    local_step: Option<usize>,
+
+    /// Analyse every connected cluster of a frame on its own, cropped to its bounding box, in rows with a Cluster number (1 for the largest)
+    #[arg(long)]
+    per_cluster: bool,
+
+    /// Only analyse the largest cluster of every frame, leaving out stray monomers and detached fragments
+    #[arg(long, conflicts_with = "per_cluster")]
+    largest_cluster_only: bool,
+
+    /// Treat the frames as periodic boxes: roll every frame so that its largest cluster, joined across the faces, is in one piece at its centre before it is analysed
+    #[arg(long)]
+    periodic: bool,
+
+    /// Which neighbours of a voxel belong to the same cluster: 6 (faces), 18 (and edges) or 26 (and corners)
+    #[arg(long, value_enum, default_value_t = Connectivity::Corners)]
+    connectivity: Connectivity,
+
+    /// Phase the paths of the tortuosity column run through: the empty voxels, or the occupied voxels of the aggregate backbone
+    #[arg(long, value_enum, default_value_t = Phase::Pores)]
+    tortuosity_phase: Phase,
+
+    /// Also write the cluster-size distribution of every frame, as Size and Count rows of a delimited table
+    #[arg(long)]
+    cluster_sizes: Option<PathBuf>,
+
+    /// Smallest cluster, in voxels, that --per-cluster analyses
+    #[arg(long, default_value_t = 1)]
+    min_cluster_size: usize,
+
+    /// Add Input, Shape, Threshold and Algorithm columns so the results describe where they come from
+    #[arg(long)]
+    provenance: bool,
+
     #[arg(short = 's', long, default_value_t = '\t')]
     csv_separator: char,
 }
 
-#[derive(Debug, serde::Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct CsvRecord {
-    frame_number: usize,
-    fractal_dimension: f64,
+fn input_format(cli: &Cli, path: &Path) -> Result<InputFormat> {
+    match cli.format {
+        Some(format) => Ok(format),
+        None => InputFormat::from_path(path),
+    }
+}
+
+/// Physical time of the frames of one input
+enum FrameTimes {
+    /// Fixed time step, from `--dt`
+    Step(f64),
+    /// Time of every frame, from the `time` array of an NPZ input
+    Listed(Vec<f64>),
+}
+
+impl FrameTimes {
+    /// Finds the frame times of the input `path`, if it has any.
+    fn of_input(cli: &Cli, path: &Path) -> Result<Option<Self>> {
+        if let Some(dt) = cli.dt {
+            return Ok(Some(Self::Step(dt)));
+        }
+        if input_format(cli, path)? != InputFormat::Npz {
+            return Ok(None);
+        }
+
+        Ok(input::read_times(path)?.map(Self::Listed))
+    }
+
+    /// Whether the tables of `inputs` have a Time column. It is decided once
+    /// for all of them, so that their rows all have the same columns; frames
+    /// of inputs without times get NaN. Remote inputs are not downloaded twice,
+    /// so a remote NPZ input is taken to have a `time` array.
+    fn any_of_inputs(cli: &Cli, inputs: &[PathBuf]) -> Result<bool> {
+        if cli.dt.is_some() {
+            return Ok(true);
+        }
+        for path in inputs {
+            if input_format(cli, path)? != InputFormat::Npz {
+                continue;
+            }
+            if input::is_remote(path) || input::read_times(path)?.is_some() {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn time(&self, frame: usize) -> Result<f64> {
+        match self {
+            Self::Step(dt) => Ok(frame as f64 * dt),
+            Self::Listed(times) => times.get(frame).copied().ok_or_else(|| {
+                anyhow!(
+                    "the time array holds {} entries, but there is a frame {frame}",
+                    times.len()
+                )
+            }),
+        }
+    }
+}
+
+/// Opens the frames of `dataset` in one input; `path` must be local.
+/// Downsampled frames are binarized by `threshold` before they are pooled.
+fn open_frames(
+    cli: &Cli,
+    path: &Path,
+    dataset: Option<&str>,
+    threshold: Option<&Threshold>,
+) -> Result<Box<dyn FrameSource>> {
+    let format = input_format(cli, path)?;
+    let from_stdin = input::is_stdin(path);
+    if from_stdin
+        && !matches!(
+            format,
+            InputFormat::Npz | InputFormat::Npy | InputFormat::Raw | InputFormat::Arrow
+        )
+    {
+        return Err(anyhow!(
+            "only npz, npy, raw and arrow inputs can be read from standard input"
+        ));
+    }
+
+    let mut frames: Box<dyn FrameSource> = match format {
+        InputFormat::Npz => input::open_npz(path, dataset, cli.channel)?,
+        InputFormat::Npy if from_stdin => input::load_aggregate_data_npy(path, cli.channel)?,
+        InputFormat::Npy if path.is_dir() => Box::new(input::NpyFrames::open(path)?),
+        InputFormat::Npy => Box::new(input::MappedNpy::open(path, cli.channel)?),
+        InputFormat::Hdf5 => input::load_aggregate_data_hdf5(path, dataset, false)?,
+        InputFormat::Raw => {
+            let shape = cli
+                .shape
+                .ok_or_else(|| anyhow!("--shape is required for raw input"))?;
+            Box::new(input::RawFrames::open(
+                path,
+                shape,
+                cli.dtype,
+                cli.byte_order,
+            )?)
+        }
+        InputFormat::Tiff => input::load_aggregate_data_tiff(path)?,
+        InputFormat::Vtk => input::load_aggregate_data_vtk(path, dataset)?,
+        InputFormat::Zarr => Box::new(input::ZarrFrames::open(path, dataset)?),
+        InputFormat::Netcdf => Box::new(input::NetcdfFrames::open(path, dataset)?),
+        InputFormat::Points => Box::new(input::load_aggregate_data_points(
+            path,
+            Lattice {
+                voxel_size: cli.voxel_size,
+                grid: cli.grid,
+            },
+        )?),
+        InputFormat::Lammps => Box::new(input::load_aggregate_data_lammps(
+            path,
+            Lattice {
+                voxel_size: cli.voxel_size,
+                grid: cli.grid,
+            },
+            &cli.atom_types,
+        )?),
+        InputFormat::Mrc => input::load_aggregate_data_mrc(path)?,
+        InputFormat::Nrrd => input::load_aggregate_data_nrrd(path)?,
+        InputFormat::Mat => input::load_aggregate_data_mat(path, dataset)?,
+        InputFormat::Images => Box::new(input::load_aggregate_data_images(
+            path,
+            cli.frame_pattern.as_deref(),
+            cli.image_threshold,
+        )?),
+        InputFormat::Parquet => input::load_aggregate_data_parquet(path, cli.shape)?,
+        InputFormat::Dicom => Box::new(input::load_aggregate_data_dicom(path)?),
+        InputFormat::Arrow => Box::new(input::ArrowFrames::open(
+            path,
+            cli.shape.map(|[_, x, y, z]| [x, y, z]),
+        )?),
+    };
+    if cli.planar {
+        frames = Box::new(input::PlanarFrames::new(frames)?);
+    }
+    if let Some(region) = cli.crop {
+        frames = Box::new(input::CroppedFrames::new(frames, region));
+    }
+    if let Some(factor) = cli.downsample {
+        let threshold = threshold.cloned();
+        let occupancy = move |frame: &Frame| match &threshold {
+            Some(threshold) => analysis::thresholded(frame, threshold),
+            None => analysis::occupancy(frame),
+        };
+        frames = Box::new(input::DownsampledFrames::new(
+            frames,
+            factor,
+            cli.pooling,
+            occupancy,
+        ));
+    }
+
+    Ok(frames)
 }
 
-fn load_aggregate_data<T: Deserialize>(file_path: impl AsRef<Path>) -> Result<Array4<T>> {
-    use ndarray::ShapeBuilder;
-    let file = std::io::Cursor::new(std::fs::read(file_path)?); // Read the whole file in one shot
-    let mut npz = npyz::npz::NpzArchive::new(file)?;
-    let arr = npz
-        .by_name(ARR_DEFAULT_NAME)?
-        .ok_or_else(|| anyhow!("Could not load array by name {}", ARR_DEFAULT_NAME))?;
-    let shape = arr.shape().to_vec();
-    let order = arr.order();
-    let data: Vec<T> = arr.into_vec()?;
+fn main() -> Result<()> {
+    let started = std::time::Instant::now();
+    let cli = Cli::parse();
+    let input_paths = input::expand_globs(&cli.input_paths)?;
+
+    if cli.list_arrays {
+        for path in &input_paths {
+            let format = match cli.format {
+                Some(format) => format,
+                None => InputFormat::from_path(path)?,
+            };
+            if format != InputFormat::Npz {
+                return Err(anyhow!("--list-arrays is only supported for NPZ inputs"));
+            }
+            for (name, shape, dtype) in input::list_arrays(path)? {
+                println!("{name}\t{shape:?}\t{dtype}");
+            }
+        }
+        return Ok(());
+    }
 
-    let shape = match shape[..] {
-        [i1, i2, i3, i4] => [i1 as usize, i2 as usize, i3 as usize, i4 as usize],
-        _ => return Err(anyhow!("expected 4D array")),
+    // Several inputs go into one table with a Run column, or into one table each
+    let batch = input_paths.len() > 1;
+    let picked = |column| cli.columns.contains(&column) || cli.columns.contains(&Column::All);
+    let writer_options = WriterOptions {
+        format: cli
+            .output_format
+            .unwrap_or_else(|| OutputFormat::from_path(&cli.output_file)),
+        csv_separator: cli.csv_separator,
+        append: cli.append,
+        series: SeriesOptions {
+            rate_stencil: picked(Column::Rate).then_some(cli.rate_stencil),
+            smoothing: cli.smooth,
+        },
+    };
+    if cli.per_input && output::is_stdout(&cli.output_file) {
+        return Err(anyhow!("--per-input needs an output file rather than -"));
+    }
+    let threshold = match cli.threshold {
+        Some(value) => Some(Threshold::Compare(cli.threshold_op, value)),
+        None if !cli.keep.is_empty() => Some(Threshold::Keep(cli.keep.clone())),
+        None => None,
+    };
+    let threshold_rule = {
+        let rule = threshold
+            .as_ref()
+            .map_or_else(|| analysis::THRESHOLD_RULE.to_string(), Threshold::rule);
+        if cli.invert {
+            format!("not ({rule})")
+        } else {
+            rule
+        }
+    };
+    let settings = Settings {
+        method: cli.method,
+        centre: cli.centre,
+        sandboxes: cli.sandboxes,
+        padding: match (cli.pad, cli.pad_to) {
+            (_, Some(size)) => Some(Padding::Size(size)),
+            (true, None) => Some(Padding::PowerOfTwo),
+            (false, None) => None,
+        },
+        fit: FitOptions {
+            min_scale: cli.min_scale,
+            max_scale: cli.max_scale,
+            regression: cli.fit,
+            auto_points: cli.auto_scales.then_some(cli.auto_scales_points),
+        },
+    };
+    let mut plot = cli.plot.as_deref().map(Plot::new).transpose()?;
+    let mut summary = match &cli.summary {
+        Some(path) => Some(Summary::new(path, cli.summary_frames, cli.growth_frames)?),
+        None => None,
+    };
+    let mut fields = cli
+        .export_fields
+        .as_deref()
+        .map(FieldExport::create)
+        .transpose()?;
+    let mut spectrum = match &cli.spectrum {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Q", "Alpha", "F"],
+        )?),
+        None => None,
+    };
+    let mut lacunarity_curve = match &cli.lacunarity_curve {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["BoxSize", "Lacunarity"],
+        )?),
+        None => None,
+    };
+    let mut counts = match &cli.counts {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Level", "Scale", "Log2Count", "Fitted"],
+        )?),
+        None => None,
+    };
+    let mut correlation_integral = match &cli.correlation_integral {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Radius", "CorrelationIntegral"],
+        )?),
+        None => None,
+    };
+    let mut pair_correlation = match &cli.pair_correlation {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Distance", "PairCorrelation"],
+        )?),
+        None => None,
     };
-    let true_shape = shape.set_f(order == npyz::Order::Fortran);
+    let mut structure_factor = match &cli.structure_factor {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Q", "StructureFactor"],
+        )?),
+        None => None,
+    };
+    let mut coordination = match &cli.coordination {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Neighbours", "Count"],
+        )?),
+        None => None,
+    };
+    let mut slices = match &cli.slices {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Slice", "FractalDimension", "OccupiedPixels"],
+        )?),
+        None => None,
+    };
+    let mut sweep_table = match &cli.sweep_table {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Threshold", "FractalDimension", "OccupiedVoxels"],
+        )?),
+        None => None,
+    };
+    let mut tile_table = match &cli.tile_table {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["TileX", "TileY", "TileZ", "FractalDimension"],
+        )?),
+        None => None,
+    };
+    let mut chord_lengths = match &cli.chord_lengths {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Axis", "Length", "Count"],
+        )?),
+        None => None,
+    };
+    let mut branch_lengths = match &cli.branch_lengths {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Length", "Count"],
+        )?),
+        None => None,
+    };
+    let mut size_table = match &cli.cluster_sizes {
+        Some(path) => Some(CurveTable::create(
+            path,
+            cli.csv_separator,
+            &["Size", "Count"],
+        )?),
+        None => None,
+    };
+    let mut wtr: Option<Box<dyn ResultWriter>> = None;
+    // Curve tables span every input, so this holds for --per-input tables too
+    let timed = picked(Column::Time) && FrameTimes::any_of_inputs(&cli, &input_paths)?;
+    for path in &input_paths {
+        // Remote inputs are downloaded to a temporary file, kept until they are processed
+        let download = if input::is_remote(path) {
+            Some(input::Download::fetch(path)?)
+        } else {
+            None
+        };
+        let local_path = download.as_ref().map_or(path.as_path(), |d| d.path());
+        // Several datasets are read side by side, frame by frame
+        let datasets: Vec<Option<&str>> = match &cli.dataset[..] {
+            [] => vec![None],
+            names => names.iter().map(|name| Some(name.as_str())).collect(),
+        };
+        if datasets.len() > 1 && input::is_stdin(local_path) {
+            return Err(anyhow!("standard input can only be read for one --dataset"));
+        }
+        let mut sources = datasets
+            .iter()
+            .map(|&dataset| {
+                Ok((
+                    dataset,
+                    open_frames(&cli, local_path, dataset, threshold.as_ref())?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let times = FrameTimes::of_input(&cli, local_path)?;
+        eprintln!("Loading done. Starting processing.");
+
+        if wtr.is_none() || cli.per_input {
+            let (output_file, inputs) = if cli.per_input {
+                let output_file = per_input_output_file(&cli.output_file, path);
+                (output_file, std::slice::from_ref(path))
+            } else {
+                (cli.output_file.clone(), &input_paths[..])
+            };
+            if let Some(previous) = wtr.take() {
+                previous.finish()?;
+            }
+            let info = RunInfo::new(inputs, &cli)?;
+            wtr = Some(output::create(&output_file, &writer_options, info)?);
+        }
+        let wtr = wtr.as_mut().unwrap();
+        // Appended rows are labelled too, to tell the runs of a growing table apart
+        let run = ((batch || cli.append) && !cli.per_input).then(|| path.display().to_string());
+
+        let range = cli.frames.unwrap_or(FrameRange {
+            start: 0,
+            end: None,
+            step: 1,
+        });
+        let mut frame_number = range.start;
+        let mut processed = 0;
+        // Skipped frames are never read, but keep their numbers in the output
+        while range.end.is_none_or(|end| frame_number < end) {
+            let mut any = false;
+            for (dataset, frames) in &mut sources {
+                if !frames.has_frame(frame_number)? {
+                    continue;
+                }
+                any = true;
+                let frame = frames.frame(frame_number)?;
+                if let Some(Padding::Size(size)) = settings.padding {
+                    let shape = frame.shape();
+                    if let Some(axis) = (0..3).find(|&i| shape[i] > size[i]) {
+                        return Err(anyhow!(
+                            "frame {frame_number} is {} voxels across along {}, more than --pad-to",
+                            shape[axis],
+                            ["X", "Y", "Z"][axis]
+                        ));
+                    }
+                }
+                // The sweep binarizes the frame as it was read
+                let sweep = cli.threshold_sweep.filter(|_| sweep_table.is_some()).map(
+                    |[start, stop, step]| {
+                        sweep_thresholds(start, stop, step)
+                            .into_iter()
+                            .map(|value| {
+                                let threshold =
+                                    Threshold::Compare(cli.threshold_op, Cut::Value(value));
+                                let binarized = analysis::binarize(&frame, &threshold);
+                                let binarized = if cli.invert {
+                                    analysis::invert(&binarized)
+                                } else {
+                                    binarized
+                                };
+                                let estimate = analysis::estimate(&binarized, &settings);
+                                vec![
+                                    value.into(),
+                                    estimate.fractal_dimension.into(),
+                                    estimate.occupied_voxels.into(),
+                                ]
+                            })
+                            .collect::<Vec<Vec<Value>>>()
+                    },
+                );
+                // Downsampled frames come binarized already
+                let frame = match threshold.as_ref().filter(|_| cli.downsample.is_none()) {
+                    Some(threshold) => analysis::binarize(&frame, threshold),
+                    None => frame,
+                };
+                let frame = if cli.invert {
+                    analysis::invert(&frame)
+                } else {
+                    frame
+                };
+                let frame = if cli.periodic {
+                    analysis::recentre_periodic(&frame, cli.connectivity)
+                } else {
+                    frame
+                };
+                let frame = if cli.largest_cluster_only {
+                    analysis::largest_cluster(&frame, cli.connectivity)
+                } else {
+                    frame
+                };
+                if let Some(fields) = &mut fields {
+                    // Fields of several inputs and datasets are told apart by prefixes
+                    let mut name = String::new();
+                    if batch {
+                        name += &format!(
+                            "{}/",
+                            path.file_stem().unwrap_or_default().to_string_lossy()
+                        );
+                    }
+                    if let Some(dataset) = dataset.filter(|_| datasets.len() > 1) {
+                        name += &format!("{dataset}/");
+                    }
+                    fields.add(&format!("{name}mask"), analysis::mask(&frame));
+                    if let Some(window) = cli.local_window {
+                        let step = cli.local_step.unwrap_or(window / 2).max(1);
+                        let map = analysis::local_dimensions(&frame, window, step);
+                        fields.add(&format!("{name}local_dimension"), map);
+                    }
+                }
+                let cluster_sizes = (cli.cluster_sizes.is_some() || picked(Column::Clusters))
+                    .then(|| analysis::cluster_sizes(&frame, cli.connectivity));
+                // Like the cluster counts, a property of the whole frame
+                let percolates = picked(Column::Percolation)
+                    .then(|| analysis::percolation(&frame, cli.connectivity));
+                // Clusters are analysed one by one, largest first
+                let targets = if cli.per_cluster {
+                    cluster_targets(&frame, cli.connectivity, cli.min_cluster_size)
+                } else {
+                    vec![(None, frame)]
+                };
+                for (cluster, frame) in &targets {
+                    let estimate = analysis::estimate(frame, &settings);
+                    let gyration = picked(Column::Anisotropy).then(|| analysis::gyration(frame));
+                    let bootstrap = cli.bootstrap.map(|samples| {
+                        analysis::bootstrap(frame, &settings, samples, cli.bootstrap_seed)
+                    });
+                    let minkowski =
+                        picked(Column::Minkowski).then(|| analysis::minkowski_functionals(frame));
+                    let skeleton = (picked(Column::Skeleton) || branch_lengths.is_some())
+                        .then(|| analysis::skeleton(frame));
+                    let branches = skeleton.as_ref().filter(|_| picked(Column::Skeleton));
+                    let record = Record {
+                        input: path.display().to_string(),
+                        run: run.clone(),
+                        dataset: dataset.filter(|_| datasets.len() > 1).map(String::from),
+                        frame_number,
+                        cluster: *cluster,
+                        time: match &times {
+                            Some(times) if timed => Some(times.time(frame_number)?),
+                            None if timed => Some(f64::NAN),
+                            _ => None,
+                        },
+                        fractal_dimension: estimate.fractal_dimension,
+                        // Filled in once the series is complete
+                        dimension_rate: None,
+                        smoothed_dimension: None,
+                        surface_dimension: picked(Column::Surface)
+                            .then(|| analysis::surface_dimension(frame, &settings)),
+                        projected_dimensions: picked(Column::Projected)
+                            .then(|| analysis::projected_dimensions(frame, &settings)),
+                        lacunarity: picked(Column::Lacunarity).then_some(estimate.lacunarity),
+                        information_dimension: picked(Column::Information)
+                            .then(|| analysis::information_dimension(frame, &settings)),
+                        occupied_voxels: picked(Column::Mass).then_some(estimate.occupied_voxels),
+                        radius_of_gyration: picked(Column::Rg)
+                            .then(|| analysis::radius_of_gyration(frame)),
+                        gyration_eigenvalues: gyration.as_ref().map(|g| g.eigenvalues),
+                        asphericity: gyration.as_ref().map(|g| g.asphericity),
+                        prolateness: gyration.as_ref().map(|g| g.prolateness),
+                        solid_fraction: picked(Column::Porosity)
+                            .then(|| analysis::solid_fraction(frame)),
+                        hull_solid_fraction: picked(Column::Hull)
+                            .then(|| analysis::hull_solid_fraction(frame)),
+                        volume: minkowski.as_ref().map(|m| m.volume),
+                        surface_area: minkowski.as_ref().map(|m| m.surface_area),
+                        mean_curvature: minkowski.as_ref().map(|m| m.mean_curvature),
+                        euler_characteristic: match &minkowski {
+                            Some(m) => Some(m.euler_characteristic),
+                            None => {
+                                picked(Column::Euler).then(|| analysis::euler_characteristic(frame))
+                            }
+                        },
+                        specific_surface_area: picked(Column::Sv)
+                            .then(|| analysis::specific_surface_area(frame)),
+                        percolates,
+                        succolarity: picked(Column::Succolarity)
+                            .then(|| analysis::succolarity(frame)),
+                        tortuosity: picked(Column::Tortuosity)
+                            .then(|| analysis::tortuosity(frame, cli.tortuosity_phase)),
+                        mean_coordination: picked(Column::Coordination)
+                            .then(|| analysis::mean_coordination(frame, cli.connectivity)),
+                        skeleton_voxels: branches.map(|s| s.voxels),
+                        endpoint_count: branches.map(|s| s.endpoints),
+                        junction_count: branches.map(|s| s.junctions),
+                        branch_count: branches.map(|s| s.branch_lengths.len()),
+                        mean_branch_length: branches.map(|s| {
+                            // Like the mean cluster size, 0 without branches
+                            let lengths = &s.branch_lengths;
+                            if lengths.is_empty() {
+                                0.0
+                            } else {
+                                lengths.iter().sum::<usize>() as f64 / lengths.len() as f64
+                            }
+                        }),
+                        cluster_count: picked(Column::Clusters)
+                            .then(|| cluster_sizes.as_ref().map_or(0, Vec::len)),
+                        mean_cluster_size: picked(Column::Clusters).then(|| {
+                            // An empty frame has no clusters, and a mean size of 0
+                            let sizes = cluster_sizes.as_deref().unwrap_or_default();
+                            if sizes.is_empty() {
+                                0.0
+                            } else {
+                                sizes.iter().sum::<usize>() as f64 / sizes.len() as f64
+                            }
+                        }),
+                        largest_cluster: picked(Column::Clusters).then(|| {
+                            let sizes = cluster_sizes.as_deref().unwrap_or_default();
+                            sizes.first().copied().unwrap_or(0)
+                        }),
+                        r_squared: picked(Column::R2).then_some(estimate.r_squared),
+                        rms_residual: picked(Column::Rms).then_some(estimate.rms_residual),
+                        std_error: picked(Column::Stderr).then_some(estimate.std_error),
+                        confidence_interval: picked(Column::Ci)
+                            .then_some(estimate.confidence_interval),
+                        bootstrap_mean: bootstrap.map(|b| b.0),
+                        bootstrap_std: bootstrap.map(|b| b.1),
+                        fit_scales: (picked(Column::Scales) || cli.auto_scales)
+                            .then_some(estimate.scale_range),
+                        generalized_dimensions: cli
+                            .q
+                            .iter()
+                            .copied()
+                            .zip(analysis::generalized_dimensions(frame, &cli.q, &settings))
+                            .collect(),
+                        provenance: cli.provenance.then(|| Provenance {
+                            shape: frame.shape(),
+                            threshold: threshold_rule.clone(),
+                            algorithm: cli.method.description().to_string(),
+                        }),
+                    };
+                    wtr.write(&record)?;
+                    if let Some(curve) = &mut lacunarity_curve {
+                        curve.add(&record, &analysis::lacunarity_curve(frame))?;
+                    }
+                    if let Some(table) = &mut counts {
+                        let rows: Vec<Vec<Value>> = estimate
+                            .counts
+                            .iter()
+                            .enumerate()
+                            .map(|(level, &[scale, log_count])| {
+                                let fitted = estimate.fitted.contains(&level);
+                                vec![level.into(), scale.into(), log_count.into(), fitted.into()]
+                            })
+                            .collect();
+                        table.add(&record, &rows)?;
+                    }
+                    if let Some(table) = &mut correlation_integral {
+                        table.add(&record, &analysis::correlation_integral(frame))?;
+                    }
+                    if let Some(table) = &mut pair_correlation {
+                        let points =
+                            analysis::pair_correlation(frame, cli.pair_correlation_backend);
+                        table.add(&record, &points)?;
+                    }
+                    if let Some(table) = &mut structure_factor {
+                        table.add(&record, &analysis::structure_factor(frame))?;
+                    }
+                    if let Some(table) = &mut chord_lengths {
+                        table.add(&record, &analysis::chord_lengths(frame))?;
+                    }
+                    if let (Some(table), Some(skeleton)) = (&mut branch_lengths, &skeleton) {
+                        table.add(&record, &size_distribution(&skeleton.branch_lengths))?;
+                    }
+                    if let Some(table) = &mut slices {
+                        let rows: Vec<Vec<Value>> = analysis::slice_estimates(frame, &settings)
+                            .into_iter()
+                            .enumerate()
+                            .map(|(z, estimate)| {
+                                let dimension = estimate.fractal_dimension;
+                                vec![z.into(), dimension.into(), estimate.occupied_voxels.into()]
+                            })
+                            .collect();
+                        table.add(&record, &rows)?;
+                    }
+                    if let (Some(table), Some(tiles)) = (&mut tile_table, cli.tiles) {
+                        let rows: Vec<Vec<Value>> =
+                            analysis::tile_dimensions(frame, &settings, tiles)
+                                .into_iter()
+                                .map(|([x, y, z], dimension)| {
+                                    vec![x.into(), y.into(), z.into(), dimension.into()]
+                                })
+                                .collect();
+                        table.add(&record, &rows)?;
+                    }
+                    if let Some(table) = &mut coordination {
+                        let histogram = analysis::coordination_histogram(frame, cli.connectivity);
+                        table.add(&record, &histogram)?;
+                    }
+                    if let Some(spectrum) = &mut spectrum {
+                        if cli.spectrum_frames.is_empty()
+                            || cli.spectrum_frames.contains(&frame_number)
+                        {
+                            let points =
+                                analysis::singularity_spectrum(frame, &cli.spectrum_q, &settings);
+                            spectrum.add(&record, &points)?;
+                        }
+                    }
+                    // Per-frame tables, plots and summaries follow the largest cluster
+                    if cluster.flatten().is_some_and(|c| c > 1) {
+                        continue;
+                    }
+                    if let (Some(table), Some(sizes)) = (&mut size_table, &cluster_sizes) {
+                        let frame_record = Record {
+                            cluster: None,
+                            ..record.clone()
+                        };
+                        table.add(&frame_record, &size_distribution(sizes))?;
+                    }
+                    if let (Some(table), Some(rows)) = (&mut sweep_table, &sweep) {
+                        let frame_record = Record {
+                            cluster: None,
+                            ..record.clone()
+                        };
+                        table.add(&frame_record, rows)?;
+                    }
+                    if let Some(plot) = &mut plot {
+                        plot.add(&record);
+                    }
+                    if let Some(summary) = &mut summary {
+                        summary.add(&record, estimate.occupied_voxels);
+                    }
+                }
+            }
+            if !any {
+                break;
+            }
+            if processed % 10 == 0 {
+                wtr.flush()?;
+            }
+            eprintln!("Processed frame: {frame_number}");
+            processed += 1;
+            frame_number += range.step;
+        }
+
+        wtr.flush()?;
+    }
+    if let Some(wtr) = wtr {
+        wtr.finish()?;
+    }
+    if let Some(curve) = lacunarity_curve {
+        curve.finish()?;
+    }
+    if let Some(spectrum) = spectrum {
+        spectrum.finish()?;
+    }
+    if let Some(table) = counts {
+        table.finish()?;
+    }
+    if let Some(table) = correlation_integral {
+        table.finish()?;
+    }
+    if let Some(table) = pair_correlation {
+        table.finish()?;
+    }
+    if let Some(table) = structure_factor {
+        table.finish()?;
+    }
+    if let Some(table) = chord_lengths {
+        table.finish()?;
+    }
+    if let Some(table) = branch_lengths {
+        table.finish()?;
+    }
+    if let Some(table) = coordination {
+        table.finish()?;
+    }
+    if let Some(table) = slices {
+        table.finish()?;
+    }
+    if let Some(table) = tile_table {
+        table.finish()?;
+    }
+    if let Some(table) = sweep_table {
+        table.finish()?;
+    }
+    if let Some(table) = size_table {
+        table.finish()?;
+    }
+    if let Some(plot) = plot {
+        plot.save()?;
+    }
+    if let Some(fields) = fields {
+        fields.save()?;
+    }
+    if let Some(summary) = summary {
+        summary.save(RunInfo::new(&input_paths, &cli)?, started.elapsed())?;
+    }
+
+    Ok(())
+}
 
-    Ok(ndarray::Array4::from_shape_vec(true_shape, data)?)
+/// The clusters of a frame to analyse one by one, largest first, with their
+/// numbers. A frame without clusters gets an empty frame of its shape
+/// instead, so that it still has a row.
+fn cluster_targets<'a>(
+    frame: &Frame,
+    connectivity: Connectivity,
+    min_size: usize,
+) -> Vec<(Option<Option<usize>>, Frame<'a>)> {
+    let clusters = analysis::clusters(frame, connectivity, min_size);
+    if clusters.is_empty() {
+        let empty = ndarray::Array3::from_elem(frame.shape(), false);
+        return vec![(Some(None), ndarray::CowArray::from(empty).into())];
+    }
+
+    clusters
+        .into_iter()
+        .enumerate()
+        .map(|(i, cluster)| (Some(Some(i + 1)), cluster))
+        .collect()
 }
 
-fn threshold(x: &i32) -> u8 {
-    if *x < 2 {
-        return u8::MIN;
+/// Number of clusters or branches of every size, as rows of size and count, smallest
+/// first; `sizes` are sorted largest first.
+fn size_distribution(sizes: &[usize]) -> Vec<Vec<usize>> {
+    let mut rows: Vec<Vec<usize>> = Vec::new();
+    for &size in sizes.iter().rev() {
+        match rows.last_mut() {
+            Some(row) if row[0] == size => row[1] += 1,
+            _ => rows.push(vec![size, 1]),
+        }
     }
-    return u8::MAX;
+    rows
 }
 
-fn calculate_fractal_dimension_3d(frame: ArrayView3<i32>) -> f64 {
-    let frame = frame.map(threshold);
-    let s = frame.shape();
-    let x_max = s[0];
-    let y_max = s[1];
-    let z_max = s[2];
-    let buf = frame.into_raw_vec().into_par_iter().enumerate();
+/// Parses a tile grid such as `4x4x4`.
+fn parse_tiles(s: &str) -> Result<[usize; 3], String> {
+    let tiles = s
+        .split('x')
+        .map(|n| match n.trim().parse::<usize>() {
+            Ok(0) => Err("a grid needs at least one tile along every axis".to_string()),
+            Ok(n) => Ok(n),
+            Err(e) => Err(format!("{n:?}: {e}")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    tiles
+        .try_into()
+        .map_err(|t: Vec<_>| format!("expected tiles as NxNxN, got {} numbers", t.len()))
+}
+
+/// Parses a downsampling factor, 2 or more.
+fn parse_factor(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(0 | 1) => Err(format!(
+            "{s:?}: a factor of 2 or more coarse-grains the frames"
+        )),
+        Ok(factor) => Ok(factor),
+        Err(e) => Err(format!("{s:?}: {e}")),
+    }
+}
 
-    let get_key_from_sample = |(flattened_coord, val): (usize, u8)| -> u32 {
-        let mut idx = flattened_coord;
-        let z = idx / (x_max * y_max);
-        idx -= z * x_max * y_max;
-        let y = idx / x_max;
-        let x = idx % x_max;
+fn parse_replicates(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(0 | 1) => Err(format!(
+            "{s:?}: a standard deviation takes 2 replicates or more"
+        )),
+        Ok(samples) => Ok(samples),
+        Err(e) => Err(format!("{s:?}: {e}")),
+    }
+}
 
-        let normalise_as_u8 = |q, max, min| ((q - min) * 256 / max) as u8;
-        let norm_x = |x| normalise_as_u8(x, x_max, 0);
-        let norm_y = |y| normalise_as_u8(y, y_max, 0);
-        let norm_z = |z| normalise_as_u8(z, z_max, 0);
+/// Frames picked with `--frames`
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+struct FrameRange {
+    start: usize,
+    /// First frame left out, if not the end of the input
+    end: Option<usize>,
+    step: usize,
+}
 
-        let arr = [norm_x(x), norm_y(y), norm_z(z), val];
-        lindel::morton_encode(arr)
+/// Parses a frame range such as `100:1000:10`, `100:` or `::5`.
+fn parse_frames(s: &str) -> Result<FrameRange, String> {
+    let parts: Vec<&str> = s.split(':').map(str::trim).collect();
+    if !(2..=3).contains(&parts.len()) {
+        return Err(format!("{s:?}: expected START:END:STEP, e.g. 100:1000:10"));
+    }
+    let number = |n: &str| n.parse::<usize>().map_err(|e| format!("{n:?}: {e}"));
+    let start = match parts[0] {
+        "" => 0,
+        n => number(n)?,
+    };
+    let end = match parts[1] {
+        "" => None,
+        n => Some(number(n)?),
     };
+    let step = match parts.get(2).copied().unwrap_or("") {
+        "" => 1,
+        n => number(n)?,
+    };
+    if step == 0 {
+        return Err(format!("{s:?}: the step must be at least 1"));
+    }
+    if end.is_some_and(|end| end <= start) {
+        return Err(format!("{s:?}: the range holds no frames"));
+    }
+    Ok(FrameRange { start, end, step })
+}
 
-    let clzs = get_clzs_par(buf, get_key_from_sample).collect::<Vec<_>>();
-    let (tmp, lacun) = get_results_from_clzs(clzs.into_iter());
-    let res = finalise_results::<32>(tmp, lacun, x_max * y_max * z_max, 8);
-    res.0
+/// Parses a threshold sweep such as `0.1:0.9:0.1`.
+fn parse_sweep(s: &str) -> Result<[f64; 3], String> {
+    let numbers = s
+        .split(':')
+        .map(|n| n.trim().parse::<f64>().map_err(|e| format!("{n:?}: {e}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [start, stop, step]: [f64; 3] = numbers
+        .try_into()
+        .map_err(|n: Vec<_>| format!("expected START:STOP:STEP, got {} numbers", n.len()))?;
+    if step <= 0.0 || stop < start {
+        return Err(format!(
+            "{s:?}: a sweep needs a positive step and a stop no smaller than its start"
+        ));
+    }
+    Ok([start, stop, step])
 }
 
-fn get_separator(sep_char: char) -> Result<u8> {
-    let mut buf = [0; 1];
-    sep_char.encode_utf8(&mut buf);
+/// Thresholds from `start` up to `stop` in steps of `step`, with `stop`
+/// itself when the steps land on it.
+fn sweep_thresholds(start: f64, stop: f64, step: f64) -> Vec<f64> {
+    // Counted in steps, so that rounding does not drop or add the last one
+    let steps = ((stop - start) / step + 1e-9).floor() as usize;
+    (0..=steps).map(|i| start + i as f64 * step).collect()
+}
 
-    Ok(buf[0])
+/// Names the output of one input of a batch after it, e.g.
+/// `run_1_fractal_dimension.csv` next to `fractal_dimension.csv`.
+fn per_input_output_file(output_file: &Path, input_path: &Path) -> PathBuf {
+    let stem = input_path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = output_file
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    output_file.with_file_name(format!("{stem}_{name}"))
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let p = PathBuf::from(cli.npz_file_path);
-    let l = load_aggregate_data::<i32>(p)?;
-    println!("Loading done. Starting processing.");
-
-    let output_file = std::fs::File::create(cli.output_file)?;
-    let mut wtr = csv::WriterBuilder::new()
-        .delimiter(get_separator(cli.csv_separator)?)
-        .from_writer(output_file);
-
-    for (frame_number, frame) in l.outer_iter().enumerate() {
-        let fractal_dimension = calculate_fractal_dimension_3d(frame);
-        wtr.serialize(CsvRecord {
-            frame_number,
-            fractal_dimension,
-        })?;
-        if frame_number % 10 == 0 {
-            wtr.flush()?;
-        }
-        println!("Processed frame: {frame_number}");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_grids_parse() {
+        assert_eq!(parse_tiles("4x2x1"), Ok([4, 2, 1]));
+        assert!(parse_tiles("4x0x4").is_err());
+        assert!(parse_tiles("4x4").is_err());
+        assert!(parse_tiles("4,4,4").is_err());
     }
 
-    wtr.flush()?;
+    #[test]
+    fn threshold_sweeps_parse() {
+        assert_eq!(parse_sweep("0.1:0.9:0.2"), Ok([0.1, 0.9, 0.2]));
+        assert_eq!(parse_sweep("-1 : 1 : 1"), Ok([-1.0, 1.0, 1.0]));
+        assert!(parse_sweep("0:1").is_err());
+        assert!(parse_sweep("0:1:0").is_err());
+        assert!(parse_sweep("1:0:0.1").is_err());
+    }
 
-    Ok(())
+    #[test]
+    fn sweeps_end_on_their_stop() {
+        assert_eq!(sweep_thresholds(0.0, 1.0, 0.5), [0.0, 0.5, 1.0]);
+        // (0.9 - 0.1) / 0.1 falls just short of 8 steps
+        assert_eq!(sweep_thresholds(0.1, 0.9, 0.1).len(), 9);
+        assert_eq!(sweep_thresholds(0.0, 1.0, 0.3).len(), 4);
+        assert_eq!(sweep_thresholds(2.0, 2.0, 1.0), [2.0]);
+    }
+
+    #[test]
+    fn frame_ranges_parse() {
+        let range = parse_frames("100:1000:10").unwrap();
+        assert_eq!((range.start, range.end, range.step), (100, Some(1000), 10));
+        let range = parse_frames("1000:").unwrap();
+        assert_eq!((range.start, range.end, range.step), (1000, None, 1));
+        let range = parse_frames("::5").unwrap();
+        assert_eq!((range.start, range.end, range.step), (0, None, 5));
+        assert!(parse_frames("10").is_err());
+        assert!(parse_frames("0:10:0").is_err());
+        assert!(parse_frames("10:10").is_err());
+        assert!(parse_frames("0:10:2:1").is_err());
+    }
+
+    #[test]
+    fn downsampling_factors_parse() {
+        assert_eq!(parse_factor(" 4"), Ok(4));
+        assert!(parse_factor("1").is_err());
+        assert!(parse_factor("0").is_err());
+        assert!(parse_factor("two").is_err());
+    }
+
+    #[test]
+    fn bootstrap_replicates_parse() {
+        assert_eq!(parse_replicates("2"), Ok(2));
+        assert!(parse_replicates("1").is_err());
+        assert!(parse_replicates("0").is_err());
+    }
+
+    #[test]
+    fn empty_time_steps_keep_their_row() {
+        // Two blobs in the first time step, none in the second
+        let mut frames = ndarray::Array4::from_elem((2, 8, 8, 8), false);
+        frames[[0, 1, 1, 1]] = true;
+        frames[[0, 5, 5, 5]] = true;
+        frames[[0, 5, 5, 6]] = true;
+
+        let first = cluster_targets(&frames.frame(0).unwrap(), Connectivity::Corners, 1);
+        let numbers: Vec<_> = first.iter().map(|(cluster, _)| *cluster).collect();
+        assert_eq!(numbers, [Some(Some(1)), Some(Some(2))]);
+
+        let second = cluster_targets(&frames.frame(1).unwrap(), Connectivity::Corners, 1);
+        assert_eq!(second.len(), 1);
+        let (cluster, frame) = &second[0];
+        assert_eq!(*cluster, Some(None));
+        assert_eq!(frame.shape(), [8, 8, 8]);
+        assert_eq!(analysis::occupancy(frame).iter().filter(|&&v| v).count(), 0);
+    }
 }
@@ -0,0 +1,126 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use super::{is_stdout, open, Record, ResultWriter};
+
+/// Delimited text with a header row. When appending to an existing table, its
+/// header is kept and has to match the columns of the run.
+pub struct CsvWriter {
+    wtr: ::csv::Writer<Box<dyn Write>>,
+    has_header: bool,
+    /// Header of the table appended to
+    existing_header: Option<Vec<String>>,
+}
+
+impl CsvWriter {
+    pub fn create(path: &Path, separator: u8, append: bool) -> Result<Self> {
+        let existing_header = if append && !is_stdout(path) && path.exists() {
+            let mut rdr = ::csv::ReaderBuilder::new()
+                .delimiter(separator)
+                .from_path(path)?;
+            let header: Vec<String> = rdr.headers()?.iter().map(String::from).collect();
+            Some(header).filter(|h| !h.is_empty())
+        } else {
+            None
+        };
+        let wtr = ::csv::WriterBuilder::new()
+            .delimiter(separator)
+            .from_writer(open(path, append)?);
+
+        Ok(Self {
+            wtr,
+            has_header: false,
+            existing_header,
+        })
+    }
+}
+
+impl ResultWriter for CsvWriter {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        let columns = record.columns();
+        if !self.has_header {
+            let names: Vec<_> = columns.iter().map(|(name, _)| name.as_str()).collect();
+            match &self.existing_header {
+                Some(existing) if *existing != names => {
+                    // Only runs with --append write the Run column
+                    let hint = if names.first() == Some(&"Run")
+                        && existing.first().map(String::as_str) != Some("Run")
+                    {
+                        "; the table was written without --append, so it has no Run column"
+                    } else {
+                        ""
+                    };
+                    return Err(anyhow!(
+                        "cannot append columns {} to a table with columns {}{hint}",
+                        names.join(", "),
+                        existing.join(", ")
+                    ));
+                }
+                Some(_) => {}
+                None => self.wtr.write_record(&names)?,
+            }
+            self.has_header = true;
+        }
+        let values: Vec<_> = columns.into_iter().map(|(_, value)| value).collect();
+        self.wtr.serialize(values)?;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.wtr.flush()?)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{records, temp_path};
+    use super::*;
+
+    #[test]
+    fn tables_read_back() {
+        let path = temp_path("read.csv");
+        let mut writer = CsvWriter::create(&path, b',', false).unwrap();
+        for record in records() {
+            writer.write(&record).unwrap();
+        }
+        writer.flush().unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "Run,FrameNumber,Cluster,Time,FractalDimension,OccupiedVoxels,PercolatesX,PercolatesY,PercolatesZ",
+                "a.npz,0,,0.5,1.5,8,true,false,false",
+                "a.npz,1,1,1.0,2.25,8,true,false,false",
+            ]
+        );
+    }
+
+    #[test]
+    fn tables_without_runs_cannot_be_appended_to() {
+        let path = temp_path("append.csv");
+        let mut writer = CsvWriter::create(&path, b',', false).unwrap();
+        let record = Record {
+            run: None,
+            ..records()[0].clone()
+        };
+        writer.write(&record).unwrap();
+        writer.flush().unwrap();
+
+        let mut writer = CsvWriter::create(&path, b',', true).unwrap();
+        let error = writer.write(&records()[0]).unwrap_err().to_string();
+        std::fs::remove_file(path).unwrap();
+        assert!(
+            error.ends_with("written without --append, so it has no Run column"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::{separator, Record, Value};
+
+/// Columns of a record that tell the frame of a curve point apart
+const KEY_COLUMNS: [&str; 5] = ["Run", "Dataset", "FrameNumber", "Cluster", "Time"];
+
+/// A long-format delimited table of per-frame curves, such as the
+/// singularity spectrum: one row per point of a curve, led by the columns
+/// of the frame it belongs to.
+pub struct CurveTable {
+    wtr: ::csv::Writer<File>,
+    /// Names of the columns of a point
+    names: &'static [&'static str],
+    has_header: bool,
+}
+
+impl CurveTable {
+    pub fn create(
+        path: &Path,
+        csv_separator: char,
+        names: &'static [&'static str],
+    ) -> Result<Self> {
+        let wtr = ::csv::WriterBuilder::new()
+            .delimiter(separator(csv_separator)?)
+            .from_path(path)?;
+
+        Ok(Self {
+            wtr,
+            names,
+            has_header: false,
+        })
+    }
+
+    /// Adds the curve of the frame of `record`, one point per row.
+    pub fn add<T: Clone + Into<Value>>(
+        &mut self,
+        record: &Record,
+        points: &[Vec<T>],
+    ) -> Result<()> {
+        let key: Vec<_> = record
+            .columns()
+            .into_iter()
+            .filter(|(name, _)| KEY_COLUMNS.contains(&name.as_str()))
+            .collect();
+        if !self.has_header {
+            let header = key.iter().map(|(name, _)| name.as_str());
+            self.wtr
+                .write_record(header.chain(self.names.iter().copied()))?;
+            self.has_header = true;
+        }
+        for point in points {
+            let mut row: Vec<_> = key.iter().map(|(_, value)| value.clone()).collect();
+            row.extend(point.iter().map(|x| x.clone().into()));
+            self.wtr.serialize(row)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        Ok(self.wtr.flush()?)
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ::arrow_ipc::writer::FileWriter;
+use anyhow::Result;
+
+use super::{record_batch, Record, ResultWriter, RunInfo};
+
+/// Key of the run metadata (as JSON) in the schema metadata
+const METADATA_KEY: &str = "aggregate-fractal-dim";
+
+/// An uncompressed Feather (Arrow IPC) file, which polars and pyarrow can map
+/// without parsing. Like Parquet, it is written in one go once the run
+/// finishes.
+pub struct FeatherWriter {
+    path: PathBuf,
+    info: RunInfo,
+    records: Vec<Record>,
+}
+
+impl FeatherWriter {
+    pub fn create(path: &Path, info: RunInfo) -> Result<Self> {
+        // Fail early rather than after the whole run
+        File::create(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            info,
+            records: Vec::new(),
+        })
+    }
+}
+
+impl ResultWriter for FeatherWriter {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let batch = record_batch(&self.records)?;
+        let metadata =
+            HashMap::from([(METADATA_KEY.to_string(), serde_json::to_string(&self.info)?)]);
+        let schema = Arc::new(batch.schema().as_ref().clone().with_metadata(metadata));
+        let batch = batch.with_schema(schema.clone())?;
+        let mut wtr = FileWriter::try_new(File::create(&self.path)?, &schema)?;
+        wtr.write(&batch)?;
+        wtr.finish()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::arrow_ipc::reader::FileReader;
+    use arrow_array::{Array, Float64Array, Int64Array};
+
+    use super::super::tests::{info, temp_path, write_records};
+    use super::*;
+
+    #[test]
+    fn tables_read_back() {
+        let path = temp_path("read.feather");
+        write_records(Box::new(FeatherWriter::create(&path, info()).unwrap()));
+        let mut reader = FileReader::try_new(File::open(&path).unwrap(), None).unwrap();
+        let schema = reader.schema();
+        let batch = reader.next().unwrap().unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert!(schema.metadata()[METADATA_KEY].contains("\"method\":\"zbox\""));
+        assert_eq!(batch.num_rows(), 2);
+        let cluster = batch.column_by_name("Cluster").unwrap();
+        let cluster = cluster.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert!(cluster.is_null(0));
+        assert_eq!(cluster.value(1), 1);
+        let dimension = batch.column_by_name("FractalDimension").unwrap();
+        let dimension = dimension.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(dimension.values(), &[1.5, 2.25]);
+    }
+}
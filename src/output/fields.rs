@@ -0,0 +1,109 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use ndarray::ArrayView3;
+use npyz::npz::NpzWriter;
+use npyz::{AutoSerialize, WriterBuilder};
+
+use crate::input::Frame;
+
+/// Spatially resolved results (masks, labels, local dimensions) exported to
+/// an NPZ archive, with one (T, X, Y, Z) array per field so that they line up
+/// with the input in the same viewers. Frames are held in memory until the
+/// run finishes, when the frame count of every field is known.
+pub struct FieldExport {
+    path: PathBuf,
+    /// Array name and frames of each field, in order of appearance
+    fields: Vec<(String, Vec<Frame<'static>>)>,
+}
+
+impl FieldExport {
+    pub fn create(path: &Path) -> Result<Self> {
+        // Fail early rather than after the whole run
+        File::create(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            fields: Vec::new(),
+        })
+    }
+
+    /// Adds the next frame of the field stored as array `name`.
+    pub fn add(&mut self, name: &str, frame: Frame<'static>) {
+        match self.fields.iter_mut().find(|(n, _)| n == name) {
+            Some((_, frames)) => frames.push(frame),
+            None => self.fields.push((name.to_string(), vec![frame])),
+        }
+    }
+
+    pub fn save(self) -> Result<()> {
+        let mut npz = NpzWriter::create(&self.path)?;
+        for (name, frames) in &self.fields {
+            // The field is stored in the element type of its first frame
+            match &frames[0] {
+                Frame::Bool(_) => write_field(&mut npz, name, frames, |f| match f {
+                    Frame::Bool(f) => Some(f.view()),
+                    _ => None,
+                })?,
+                Frame::U8(_) => write_field(&mut npz, name, frames, |f| match f {
+                    Frame::U8(f) => Some(f.view()),
+                    _ => None,
+                })?,
+                Frame::U16(_) => write_field(&mut npz, name, frames, |f| match f {
+                    Frame::U16(f) => Some(f.view()),
+                    _ => None,
+                })?,
+                Frame::I32(_) => write_field(&mut npz, name, frames, |f| match f {
+                    Frame::I32(f) => Some(f.view()),
+                    _ => None,
+                })?,
+                Frame::F32(_) => write_field(&mut npz, name, frames, |f| match f {
+                    Frame::F32(f) => Some(f.view()),
+                    _ => None,
+                })?,
+                Frame::F64(_) => write_field(&mut npz, name, frames, |f| match f {
+                    Frame::F64(f) => Some(f.view()),
+                    _ => None,
+                })?,
+            }
+        }
+        npz.zip_writer().finish()?;
+
+        Ok(())
+    }
+}
+
+/// Writes the frames of one field as a (T, X, Y, Z) array in C order.
+fn write_field<'f, T: AutoSerialize + Copy + 'static>(
+    npz: &mut NpzWriter<std::io::BufWriter<File>>,
+    name: &str,
+    frames: &'f [Frame<'static>],
+    view: impl Fn(&'f Frame<'static>) -> Option<ArrayView3<'f, T>>,
+) -> Result<()> {
+    let views = frames
+        .iter()
+        .map(|f| view(f).ok_or_else(|| anyhow!("the frames of field {name} differ in type")))
+        .collect::<Result<Vec<_>>>()?;
+    let shape = views[0].shape().to_vec();
+    if views.iter().any(|v| v.shape() != shape) {
+        return Err(anyhow!("the frames of field {name} differ in shape"));
+    }
+    let mut shape: Vec<u64> = shape.into_iter().map(|n| n as u64).collect();
+    shape.insert(0, views.len() as u64);
+
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut wtr = npz
+        .array::<T>(name, options)?
+        .default_dtype()
+        .shape(&shape)
+        .begin_nd()?;
+    for view in views {
+        // iter() visits the elements in logical (C) order whatever the layout
+        wtr.extend(view.iter().copied())?;
+    }
+    wtr.finish()?;
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use std::path::{Path, PathBuf};
+
+use ::hdf5::types::VarLenUnicode;
+use anyhow::{anyhow, Result};
+
+use super::{Record, ResultWriter, RunInfo, Value};
+
+/// An HDF5 file with one 1D dataset per result column at its root, and the
+/// run metadata as string attributes: `tool`, `version` and `inputs` on the
+/// root, and one attribute per command line option on the `parameters`
+/// group. The file is written when the run finishes.
+pub struct Hdf5Writer {
+    path: PathBuf,
+    info: RunInfo,
+    records: Vec<Record>,
+}
+
+impl Hdf5Writer {
+    pub fn create(path: &Path, info: RunInfo) -> Result<Self> {
+        // Fail early rather than after the whole run
+        ::hdf5::File::create(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            info,
+            records: Vec::new(),
+        })
+    }
+}
+
+fn unicode(s: &str) -> Result<VarLenUnicode> {
+    s.parse()
+        .map_err(|e| anyhow!("cannot store {s:?} in HDF5: {e:?}"))
+}
+
+fn write_attr(location: &::hdf5::Location, name: &str, value: &str) -> Result<()> {
+    location
+        .new_attr::<VarLenUnicode>()
+        .create(name)?
+        .write_scalar(&unicode(value)?)?;
+    Ok(())
+}
+
+impl ResultWriter for Hdf5Writer {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let file = ::hdf5::File::create(&self.path)?;
+        let rows: Vec<_> = self.records.iter().map(Record::columns).collect();
+        let names = rows.first().map_or(Vec::new(), |row| {
+            row.iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+        });
+        for (i, name) in names.into_iter().enumerate() {
+            let cells = rows.iter().map(|row| &row[i].1);
+            let builder = file.new_dataset_builder();
+            // Typed by the first cell with a value
+            let typed = rows
+                .iter()
+                .map(|row| &row[i].1)
+                .find(|v| **v != Value::Null)
+                .unwrap_or(&Value::Null);
+            match typed {
+                Value::Text(_) => {
+                    let data = cells
+                        .map(|v| match v {
+                            Value::Text(s) => unicode(s),
+                            _ => unicode(""),
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    builder.with_data(&data[..]).create(name)?;
+                }
+                // Without a value, as cluster 0 like in SQLite
+                Value::Int(_) | Value::Null => {
+                    let data: Vec<i64> = cells
+                        .map(|v| match v {
+                            Value::Int(i) => *i,
+                            _ => 0,
+                        })
+                        .collect();
+                    builder.with_data(&data[..]).create(name)?;
+                }
+                Value::Float(_) => {
+                    let data: Vec<f64> = cells
+                        .map(|v| match v {
+                            Value::Float(f) => *f,
+                            _ => f64::NAN,
+                        })
+                        .collect();
+                    builder.with_data(&data[..]).create(name)?;
+                }
+                Value::Bool(_) => {
+                    let data: Vec<bool> = cells
+                        .map(|v| match v {
+                            Value::Bool(b) => *b,
+                            _ => false,
+                        })
+                        .collect();
+                    builder.with_data(&data[..]).create(name)?;
+                }
+            }
+        }
+
+        write_attr(&file, "tool", self.info.tool)?;
+        write_attr(&file, "version", self.info.version)?;
+        write_attr(&file, "inputs", &self.info.inputs.join("\n"))?;
+        let parameters = file.create_group("parameters")?;
+        if let serde_json::Value::Object(options) = &self.info.parameters {
+            for (name, value) in options {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    value => value.to_string(),
+                };
+                write_attr(&parameters, name, &value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde_json::{Map, Value as JsonValue};
+
+use super::{is_stdout, open, Record, ResultWriter, RunInfo};
+
+/// A JSON document with the run metadata and an array of per-frame records.
+/// The document is only complete once the run finishes, so it is written
+/// then.
+pub struct JsonWriter {
+    path: PathBuf,
+    info: RunInfo,
+    frames: Vec<Map<String, JsonValue>>,
+}
+
+impl JsonWriter {
+    pub fn create(path: &Path, info: RunInfo) -> Result<Self> {
+        // Fail early rather than after the whole run
+        if !is_stdout(path) {
+            File::create(path)?;
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            info,
+            frames: Vec::new(),
+        })
+    }
+}
+
+impl ResultWriter for JsonWriter {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        self.frames.push(record.to_json());
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let mut out = BufWriter::new(open(&self.path, false)?);
+        let document = serde_json::json!({
+            "metadata": self.info,
+            "frames": self.frames,
+        });
+        serde_json::to_writer_pretty(&mut out, &document)?;
+        writeln!(out)?;
+
+        Ok(out.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{info, temp_path, write_records};
+    use super::*;
+
+    #[test]
+    fn documents_read_back() {
+        let path = temp_path("read.json");
+        write_records(Box::new(JsonWriter::create(&path, info()).unwrap()));
+        let document: JsonValue =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(document["metadata"]["tool"], env!("CARGO_PKG_NAME"));
+        assert_eq!(document["metadata"]["parameters"]["method"], "zbox");
+        let frames = document["frames"].as_array().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0]["Run"], "a.npz");
+        assert_eq!(frames[0]["Cluster"], JsonValue::Null);
+        assert_eq!(frames[0]["PercolatesX"], true);
+        assert_eq!(frames[1]["FrameNumber"], 1);
+        assert_eq!(frames[1]["Cluster"], 1);
+        assert_eq!(frames[1]["FractalDimension"], 2.25);
+    }
+}
@@ -0,0 +1,37 @@
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::{open, Record, ResultWriter};
+
+/// One JSON object per line and frame, flushed as soon as it is written so
+/// that the output can be followed while the run goes on.
+pub struct JsonLinesWriter {
+    out: BufWriter<Box<dyn Write>>,
+}
+
+impl JsonLinesWriter {
+    pub fn create(path: &Path, append: bool) -> Result<Self> {
+        Ok(Self {
+            out: BufWriter::new(open(path, append)?),
+        })
+    }
+}
+
+impl ResultWriter for JsonLinesWriter {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        serde_json::to_writer(&mut self.out, &record.to_json())?;
+        writeln!(self.out)?;
+
+        self.flush()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.flush()
+    }
+}
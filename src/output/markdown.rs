@@ -0,0 +1,132 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{is_stdout, open, Record, ResultWriter, Value};
+
+/// A Markdown (GitHub-flavoured) table, for pasting small runs into notes and
+/// issues. Columns are padded to line up, so the table is written once the
+/// run finishes.
+pub struct MarkdownWriter {
+    path: PathBuf,
+    header: Vec<String>,
+    /// Whether each column holds numbers, which are right-aligned
+    numeric: Vec<bool>,
+    rows: Vec<Vec<String>>,
+}
+
+impl MarkdownWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        // Fail early rather than after the whole run
+        if !is_stdout(path) {
+            File::create(path)?;
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            header: Vec::new(),
+            numeric: Vec::new(),
+            rows: Vec::new(),
+        })
+    }
+}
+
+/// Formats a cell like the CSV writer does, escaping pipes in text.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Text(s) => s.replace('|', "\\|"),
+        Value::Int(i) => i.to_string(),
+        Value::Float(f) => format!("{f:?}"),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => String::new(),
+    }
+}
+
+impl ResultWriter for MarkdownWriter {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        let columns = record.columns();
+        if self.header.is_empty() {
+            self.header = columns.iter().map(|(name, _)| name.clone()).collect();
+            self.numeric = columns
+                .iter()
+                .map(|(_, value)| !matches!(value, Value::Text(_)))
+                .collect();
+        }
+        self.rows
+            .push(columns.iter().map(|(_, value)| cell(value)).collect());
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let mut out = BufWriter::new(open(&self.path, false)?);
+        if self.header.is_empty() {
+            return Ok(out.flush()?);
+        }
+        let widths: Vec<usize> = self
+            .header
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let widest = self.rows.iter().map(|row| row[i].chars().count()).max();
+                widest.unwrap_or(0).max(name.len()).max(3)
+            })
+            .collect();
+
+        let header: Vec<_> = self.header.iter().map(|name| name.to_string()).collect();
+        let rule: Vec<_> = widths
+            .iter()
+            .zip(&self.numeric)
+            .map(|(&width, &numeric)| match numeric {
+                true => format!("{}:", "-".repeat(width - 1)),
+                false => "-".repeat(width),
+            })
+            .collect();
+        for row in std::iter::once(&header).chain([&rule]).chain(&self.rows) {
+            write!(out, "|")?;
+            for ((value, &width), &numeric) in row.iter().zip(&widths).zip(&self.numeric) {
+                match numeric {
+                    true => write!(out, " {value:>width$} |")?,
+                    false => write!(out, " {value:<width$} |")?,
+                }
+            }
+            writeln!(out)?;
+        }
+
+        Ok(out.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{temp_path, write_records};
+    use super::*;
+
+    #[test]
+    fn tables_read_back() {
+        let path = temp_path("read.md");
+        write_records(Box::new(MarkdownWriter::create(&path).unwrap()));
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let rows: Vec<Vec<&str>> = text
+            .lines()
+            .map(|line| {
+                let cells = line.trim_matches('|').split('|');
+                cells.map(str::trim).collect()
+            })
+            .collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0][..4], ["Run", "FrameNumber", "Cluster", "Time"]);
+        // Text is left-aligned, numbers right-aligned
+        assert!(!rows[1][0].ends_with(':') && rows[1][1].ends_with(':'));
+        assert_eq!(rows[2][..5], ["a.npz", "0", "", "0.5", "1.5"]);
+        assert_eq!(rows[3][..5], ["a.npz", "1", "1", "1.0", "2.25"]);
+    }
+}
@@ -0,0 +1,749 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray};
+use clap::ValueEnum;
+use serde::Serialize;
+
+mod csv;
+mod curves;
+mod feather;
+mod fields;
+#[cfg(feature = "hdf5")]
+mod hdf5;
+mod json;
+mod jsonl;
+mod markdown;
+mod parquet;
+mod plot;
+mod series;
+mod sqlite;
+mod summary;
+mod xlsx;
+
+pub use self::csv::CsvWriter;
+pub use self::curves::CurveTable;
+pub use self::feather::FeatherWriter;
+pub use self::fields::FieldExport;
+#[cfg(feature = "hdf5")]
+pub use self::hdf5::Hdf5Writer;
+pub use self::parquet::ParquetWriter;
+pub use self::plot::Plot;
+use self::series::SeriesWriter;
+pub use self::series::{SeriesOptions, Smoothing};
+pub use json::JsonWriter;
+pub use jsonl::JsonLinesWriter;
+pub use markdown::MarkdownWriter;
+pub use sqlite::SqliteWriter;
+pub use summary::Summary;
+pub use xlsx::XlsxWriter;
+
+/// Supported result file formats
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Csv,
+    Json,
+    Jsonl,
+    Parquet,
+    Hdf5,
+    Sqlite,
+    Xlsx,
+    Markdown,
+    Feather,
+}
+
+impl OutputFormat {
+    /// Guesses the output format from the file extension, falling back to CSV.
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("json") => Self::Json,
+            Some("jsonl" | "ndjson") => Self::Jsonl,
+            Some("parquet" | "pq") => Self::Parquet,
+            Some("h5" | "hdf5" | "he5") => Self::Hdf5,
+            Some("sqlite" | "sqlite3" | "db") => Self::Sqlite,
+            Some("xlsx") => Self::Xlsx,
+            Some("md" | "markdown") => Self::Markdown,
+            Some("feather" | "arrow" | "ipc") => Self::Feather,
+            _ => Self::Csv,
+        }
+    }
+}
+
+/// Result columns that can be picked with `--columns`. The frame number and
+/// the fractal dimension are always written.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    Frame,
+    // Physical time, when known
+    Time,
+    Dim,
+    // Time derivative of the dimension
+    Rate,
+    // Dimension of the surface (boundary voxels) of the aggregate
+    Surface,
+    // Dimensions of the projections along X, Y and Z
+    Projected,
+    Lacunarity,
+    // Information dimension D1
+    Information,
+    // Number of occupied voxels
+    Mass,
+    // Radius of gyration
+    Rg,
+    // Eigenvalues, asphericity and prolateness of the gyration tensor
+    Anisotropy,
+    // Number, mean size and largest size of the clusters
+    Clusters,
+    // Solid fraction of the bounding box, and of the convex hull
+    Porosity,
+    Hull,
+    // Volume, surface area, integral mean curvature and Euler characteristic
+    Minkowski,
+    // Euler characteristic alone
+    Euler,
+    // Surface area over volume
+    Sv,
+    // Whether the aggregate spans the frame along each axis
+    Percolation,
+    // Penetration of the pores by a fluid along X, Y and Z
+    Succolarity,
+    // Geometric tortuosity of the shortest paths across X, Y and Z
+    Tortuosity,
+    // Mean number of occupied neighbours of an occupied voxel
+    Coordination,
+    // Voxels, endpoints, junctions and branches of the curve skeleton
+    Skeleton,
+    // R² and RMS residual of the log-log fit
+    R2,
+    Rms,
+    // Standard error and 95% confidence interval of the dimension
+    Stderr,
+    Ci,
+    // Smallest and largest scale the dimension is fitted on
+    Scales,
+    All,
+}
+
+/// A single cell of the results table
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    /// A cell without a value, such as the cluster of a frame without any
+    Null,
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Value::Float(f)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(i: usize) -> Self {
+        Value::Int(i as i64)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+/// Results of one frame
+#[derive(Clone, Debug, Default)]
+pub struct Record {
+    /// Input the frame comes from
+    pub input: String,
+    /// Input the frame belongs to, when several inputs share one table
+    pub run: Option<String>,
+    /// Dataset the frame belongs to, when several are analysed at once
+    pub dataset: Option<String>,
+    pub frame_number: usize,
+    /// Rank of the cluster by size, 1 for the largest, when clusters are
+    /// analysed on their own; `Some(None)` for a frame without clusters
+    pub cluster: Option<Option<usize>>,
+    /// Physical time of the frame, when known
+    pub time: Option<f64>,
+    pub fractal_dimension: f64,
+    // The other results are only set if their column was picked
+    /// Time derivative of the fractal dimension, fitted over the neighbouring
+    /// frames
+    pub dimension_rate: Option<f64>,
+    /// Fractal dimension smoothed over the neighbouring frames
+    pub smoothed_dimension: Option<f64>,
+    /// Fractal dimension of the surface of the aggregate
+    pub surface_dimension: Option<f64>,
+    /// Box-counting dimensions of the projections along X, Y and Z
+    pub projected_dimensions: Option<[f64; 3]>,
+    pub lacunarity: Option<f64>,
+    /// Information dimension D1 of the box occupation probabilities
+    pub information_dimension: Option<f64>,
+    /// Number of voxels that belong to the aggregate
+    pub occupied_voxels: Option<usize>,
+    /// Radius of gyration of the aggregate, in voxels
+    pub radius_of_gyration: Option<f64>,
+    /// Eigenvalues of the gyration tensor, largest first
+    pub gyration_eigenvalues: Option<[f64; 3]>,
+    /// Anisotropy of the gyration tensor
+    pub asphericity: Option<f64>,
+    pub prolateness: Option<f64>,
+    /// Fraction of the bounding box and of the convex hull of the aggregate
+    /// that it fills
+    pub solid_fraction: Option<f64>,
+    pub hull_solid_fraction: Option<f64>,
+    /// Minkowski functionals of the aggregate: its volume and surface area in
+    /// voxels, integral mean curvature in units of pi voxels and Euler
+    /// characteristic
+    pub volume: Option<usize>,
+    pub surface_area: Option<f64>,
+    pub mean_curvature: Option<f64>,
+    pub euler_characteristic: Option<i64>,
+    /// Surface area of the aggregate over its volume, per voxel
+    pub specific_surface_area: Option<f64>,
+    /// Whether some cluster spans the frame along X, Y and Z
+    pub percolates: Option<[bool; 3]>,
+    /// Succolarity of the pores for flow along X, Y and Z
+    pub succolarity: Option<[f64; 3]>,
+    /// Geometric tortuosity of the shortest paths across X, Y and Z
+    pub tortuosity: Option<[f64; 3]>,
+    /// Mean number of occupied neighbours of an occupied voxel
+    pub mean_coordination: Option<f64>,
+    /// Voxels, endpoints and junctions of the curve skeleton, and the number
+    /// and mean length in voxels of its branches (0 without branches)
+    pub skeleton_voxels: Option<usize>,
+    pub endpoint_count: Option<usize>,
+    pub junction_count: Option<usize>,
+    pub branch_count: Option<usize>,
+    pub mean_branch_length: Option<f64>,
+    /// Number of clusters of the frame, and their mean and largest size in
+    /// voxels; the mean is 0 without clusters
+    pub cluster_count: Option<usize>,
+    pub mean_cluster_size: Option<f64>,
+    pub largest_cluster: Option<usize>,
+    /// Goodness of the log-log fit behind the dimension
+    pub r_squared: Option<f64>,
+    pub rms_residual: Option<f64>,
+    /// Standard error and 95% confidence interval of the dimension
+    pub std_error: Option<f64>,
+    pub confidence_interval: Option<[f64; 2]>,
+    /// Mean and standard deviation of the dimension over bootstrap replicates
+    pub bootstrap_mean: Option<f64>,
+    pub bootstrap_std: Option<f64>,
+    /// Smallest and largest scale the dimension is fitted on, in voxels
+    pub fit_scales: Option<[f64; 2]>,
+    /// Generalized dimensions D_q as (q, D_q) pairs, one column each
+    pub generalized_dimensions: Vec<(f64, f64)>,
+    /// Where the result comes from, when the table should describe itself
+    pub provenance: Option<Provenance>,
+}
+
+/// How a result was obtained
+#[derive(Clone, Debug)]
+pub struct Provenance {
+    /// Frame size (X, Y, Z)
+    pub shape: [usize; 3],
+    /// Rule that decides which voxels belong to the aggregate
+    pub threshold: String,
+    /// Estimator and its parameters
+    pub algorithm: String,
+}
+
+impl Record {
+    /// Time of the frame where known, else its frame number.
+    pub fn time_or_frame(&self) -> f64 {
+        self.time
+            .filter(|t| !t.is_nan())
+            .unwrap_or(self.frame_number as f64)
+    }
+
+    /// Named cells of the record, in column order. Columns that do not apply
+    /// to the run are left out.
+    pub fn columns(&self) -> Vec<(String, Value)> {
+        let mut columns = Vec::new();
+        if let Some(run) = &self.run {
+            columns.push(("Run".to_string(), Value::Text(run.clone())));
+        }
+        if let Some(dataset) = &self.dataset {
+            columns.push(("Dataset".to_string(), Value::Text(dataset.clone())));
+        }
+        columns.push((
+            "FrameNumber".to_string(),
+            Value::Int(self.frame_number as i64),
+        ));
+        match self.cluster {
+            Some(Some(cluster)) => {
+                columns.push(("Cluster".to_string(), Value::Int(cluster as i64)));
+            }
+            Some(None) => columns.push(("Cluster".to_string(), Value::Null)),
+            None => {}
+        }
+        if let Some(time) = self.time {
+            columns.push(("Time".to_string(), Value::Float(time)));
+        }
+        columns.push((
+            "FractalDimension".to_string(),
+            Value::Float(self.fractal_dimension),
+        ));
+        if let Some(smoothed) = self.smoothed_dimension {
+            columns.push(("SmoothedDimension".to_string(), Value::Float(smoothed)));
+        }
+        if let Some(rate) = self.dimension_rate {
+            columns.push(("DimensionRate".to_string(), Value::Float(rate)));
+        }
+        if let Some(surface_dimension) = self.surface_dimension {
+            columns.push((
+                "SurfaceDimension".to_string(),
+                Value::Float(surface_dimension),
+            ));
+        }
+        if let Some(dimensions) = self.projected_dimensions {
+            for (axis, dimension) in ["X", "Y", "Z"].into_iter().zip(dimensions) {
+                columns.push((format!("ProjectedDimension{axis}"), Value::Float(dimension)));
+            }
+        }
+        if let Some(lacunarity) = self.lacunarity {
+            columns.push(("Lacunarity".to_string(), Value::Float(lacunarity)));
+        }
+        if let Some(dimension) = self.information_dimension {
+            columns.push(("InformationDimension".to_string(), Value::Float(dimension)));
+        }
+        if let Some(occupied_voxels) = self.occupied_voxels {
+            columns.push((
+                "OccupiedVoxels".to_string(),
+                Value::Int(occupied_voxels as i64),
+            ));
+        }
+        if let Some(radius) = self.radius_of_gyration {
+            columns.push(("RadiusOfGyration".to_string(), Value::Float(radius)));
+        }
+        if let Some(eigenvalues) = self.gyration_eigenvalues {
+            for (i, eigenvalue) in eigenvalues.into_iter().enumerate() {
+                columns.push((
+                    format!("GyrationEigenvalue{}", i + 1),
+                    Value::Float(eigenvalue),
+                ));
+            }
+        }
+        if let Some(asphericity) = self.asphericity {
+            columns.push(("Asphericity".to_string(), Value::Float(asphericity)));
+        }
+        if let Some(prolateness) = self.prolateness {
+            columns.push(("Prolateness".to_string(), Value::Float(prolateness)));
+        }
+        if let Some(fraction) = self.solid_fraction {
+            columns.push(("SolidFraction".to_string(), Value::Float(fraction)));
+        }
+        if let Some(fraction) = self.hull_solid_fraction {
+            columns.push(("HullSolidFraction".to_string(), Value::Float(fraction)));
+        }
+        if let Some(volume) = self.volume {
+            columns.push(("Volume".to_string(), Value::Int(volume as i64)));
+        }
+        if let Some(area) = self.surface_area {
+            columns.push(("SurfaceArea".to_string(), Value::Float(area)));
+        }
+        if let Some(curvature) = self.mean_curvature {
+            columns.push(("IntegralMeanCurvature".to_string(), Value::Float(curvature)));
+        }
+        if let Some(euler) = self.euler_characteristic {
+            columns.push(("EulerCharacteristic".to_string(), Value::Int(euler)));
+        }
+        if let Some(ratio) = self.specific_surface_area {
+            columns.push(("SpecificSurfaceArea".to_string(), Value::Float(ratio)));
+        }
+        if let Some(percolates) = self.percolates {
+            for (axis, percolates) in ["X", "Y", "Z"].into_iter().zip(percolates) {
+                columns.push((format!("Percolates{axis}"), Value::Bool(percolates)));
+            }
+        }
+        if let Some(succolarity) = self.succolarity {
+            for (axis, succolarity) in ["X", "Y", "Z"].into_iter().zip(succolarity) {
+                columns.push((format!("Succolarity{axis}"), Value::Float(succolarity)));
+            }
+        }
+        if let Some(tortuosity) = self.tortuosity {
+            for (axis, tortuosity) in ["X", "Y", "Z"].into_iter().zip(tortuosity) {
+                columns.push((format!("Tortuosity{axis}"), Value::Float(tortuosity)));
+            }
+        }
+        if let Some(mean) = self.mean_coordination {
+            columns.push(("MeanCoordination".to_string(), Value::Float(mean)));
+        }
+        if let Some(voxels) = self.skeleton_voxels {
+            columns.push(("SkeletonVoxels".to_string(), Value::Int(voxels as i64)));
+        }
+        if let Some(count) = self.endpoint_count {
+            columns.push(("EndpointCount".to_string(), Value::Int(count as i64)));
+        }
+        if let Some(count) = self.junction_count {
+            columns.push(("JunctionCount".to_string(), Value::Int(count as i64)));
+        }
+        if let Some(count) = self.branch_count {
+            columns.push(("BranchCount".to_string(), Value::Int(count as i64)));
+        }
+        if let Some(mean) = self.mean_branch_length {
+            columns.push(("MeanBranchLength".to_string(), Value::Float(mean)));
+        }
+        if let Some(count) = self.cluster_count {
+            columns.push(("ClusterCount".to_string(), Value::Int(count as i64)));
+        }
+        if let Some(mean) = self.mean_cluster_size {
+            columns.push(("MeanClusterSize".to_string(), Value::Float(mean)));
+        }
+        if let Some(largest) = self.largest_cluster {
+            columns.push(("LargestCluster".to_string(), Value::Int(largest as i64)));
+        }
+        if let Some(r_squared) = self.r_squared {
+            columns.push(("RSquared".to_string(), Value::Float(r_squared)));
+        }
+        if let Some(rms_residual) = self.rms_residual {
+            columns.push(("RmsResidual".to_string(), Value::Float(rms_residual)));
+        }
+        if let Some(std_error) = self.std_error {
+            columns.push(("StdError".to_string(), Value::Float(std_error)));
+        }
+        if let Some([low, high]) = self.confidence_interval {
+            columns.push(("CiLow".to_string(), Value::Float(low)));
+            columns.push(("CiHigh".to_string(), Value::Float(high)));
+        }
+        if let Some(mean) = self.bootstrap_mean {
+            columns.push(("BootstrapMean".to_string(), Value::Float(mean)));
+        }
+        if let Some(std) = self.bootstrap_std {
+            columns.push(("BootstrapStd".to_string(), Value::Float(std)));
+        }
+        if let Some([min, max]) = self.fit_scales {
+            columns.push(("FitMinScale".to_string(), Value::Float(min)));
+            columns.push(("FitMaxScale".to_string(), Value::Float(max)));
+        }
+        for &(q, dimension) in &self.generalized_dimensions {
+            columns.push((format!("D{q}"), Value::Float(dimension)));
+        }
+        if let Some(provenance) = &self.provenance {
+            let [x, y, z] = provenance.shape;
+            columns.push(("Input".to_string(), Value::Text(self.input.clone())));
+            columns.push(("Shape".to_string(), Value::Text(format!("{x}x{y}x{z}"))));
+            columns.push((
+                "Threshold".to_string(),
+                Value::Text(provenance.threshold.clone()),
+            ));
+            columns.push((
+                "Algorithm".to_string(),
+                Value::Text(provenance.algorithm.clone()),
+            ));
+        }
+        columns
+    }
+
+    /// The record as a JSON object keyed by column name.
+    pub fn to_json(&self) -> serde_json::Map<String, serde_json::Value> {
+        self.columns()
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    Value::Text(s) => s.into(),
+                    Value::Int(i) => i.into(),
+                    Value::Float(f) => f.into(),
+                    Value::Bool(b) => b.into(),
+                    Value::Null => serde_json::Value::Null,
+                };
+                (name, value)
+            })
+            .collect()
+    }
+}
+
+/// What produced a results file, for formats that can store it
+#[derive(Clone, Debug, Serialize)]
+pub struct RunInfo {
+    pub tool: &'static str,
+    pub version: &'static str,
+    pub inputs: Vec<String>,
+    /// Command line options of the run
+    pub parameters: serde_json::Value,
+}
+
+impl RunInfo {
+    pub fn new(inputs: &[impl AsRef<Path>], parameters: &impl Serialize) -> Result<Self> {
+        Ok(Self {
+            tool: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            inputs: inputs
+                .iter()
+                .map(|p| p.as_ref().display().to_string())
+                .collect(),
+            parameters: serde_json::to_value(parameters)?,
+        })
+    }
+}
+
+/// A sink for per-frame results
+pub trait ResultWriter {
+    fn write(&mut self, record: &Record) -> Result<()>;
+
+    /// Makes the records written so far visible to readers of the output,
+    /// where the format allows it.
+    fn flush(&mut self) -> Result<()>;
+
+    /// Completes the output; nothing can be written afterwards.
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// Options of the result writers
+pub struct WriterOptions {
+    pub format: OutputFormat,
+    pub csv_separator: char,
+    /// Add to an existing output file rather than replacing it
+    pub append: bool,
+    /// Columns that take the whole series of frames
+    pub series: SeriesOptions,
+}
+
+/// Whether `path` is `-`, which stands for standard output.
+pub fn is_stdout(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Opens the text output `path`, or standard output for `-`, either
+/// replacing or adding to its contents.
+fn open(path: &Path, append: bool) -> Result<Box<dyn Write>> {
+    Ok(if is_stdout(path) {
+        Box::new(std::io::stdout())
+    } else if append {
+        Box::new(File::options().create(true).append(true).open(path)?)
+    } else {
+        Box::new(File::create(path)?)
+    })
+}
+
+/// Creates the results file `path`; `-` writes text formats to standard
+/// output.
+pub fn create(
+    path: &Path,
+    options: &WriterOptions,
+    info: RunInfo,
+) -> Result<Box<dyn ResultWriter>> {
+    let is_binary = matches!(
+        options.format,
+        OutputFormat::Parquet
+            | OutputFormat::Hdf5
+            | OutputFormat::Sqlite
+            | OutputFormat::Xlsx
+            | OutputFormat::Feather
+    );
+    if is_binary && is_stdout(path) {
+        return Err(anyhow!(
+            "{:?} results cannot be written to standard output",
+            options.format
+        ));
+    }
+    let appendable = matches!(
+        options.format,
+        OutputFormat::Csv | OutputFormat::Jsonl | OutputFormat::Sqlite
+    );
+    if options.append && !appendable {
+        return Err(anyhow!(
+            "{:?} results cannot be appended to; use CSV, JSON Lines or SQLite",
+            options.format
+        ));
+    }
+
+    let writer: Box<dyn ResultWriter> = match options.format {
+        OutputFormat::Csv => Box::new(CsvWriter::create(
+            path,
+            separator(options.csv_separator)?,
+            options.append,
+        )?),
+        OutputFormat::Json => Box::new(JsonWriter::create(path, info)?),
+        OutputFormat::Jsonl => Box::new(JsonLinesWriter::create(path, options.append)?),
+        OutputFormat::Parquet => Box::new(ParquetWriter::create(path, info)?),
+        OutputFormat::Hdf5 => Box::new(Hdf5Writer::create(path, info)?),
+        OutputFormat::Sqlite => Box::new(SqliteWriter::create(path)?),
+        OutputFormat::Xlsx => Box::new(XlsxWriter::create(path, info)?),
+        OutputFormat::Markdown => Box::new(MarkdownWriter::create(path)?),
+        OutputFormat::Feather => Box::new(FeatherWriter::create(path, info)?),
+    };
+    if options.series.is_empty() {
+        return Ok(writer);
+    }
+
+    Ok(Box::new(SeriesWriter::new(writer, options.series.clone())))
+}
+
+/// Gathers records into an Arrow batch with one typed column per result.
+fn record_batch(records: &[Record]) -> Result<RecordBatch> {
+    if records.is_empty() {
+        return Ok(RecordBatch::new_empty(Arc::new(
+            arrow_schema::Schema::empty(),
+        )));
+    }
+    let rows: Vec<_> = records.iter().map(Record::columns).collect();
+    // Every column any row has, in order of appearance, typed by its first
+    // cell with a value; cells a row lacks are null
+    let mut header: Vec<(String, Value)> = Vec::new();
+    for (name, value) in rows.iter().flatten() {
+        match header.iter_mut().find(|(n, _)| n == name) {
+            Some((_, typed @ Value::Null)) => *typed = value.clone(),
+            Some(_) => {}
+            None => header.push((name.clone(), value.clone())),
+        }
+    }
+    let columns = header.into_iter().map(|(name, value)| {
+        let cells = rows
+            .iter()
+            .map(|row| row.iter().find(|(n, _)| *n == name).map(|(_, v)| v));
+        let column: ArrayRef = match value {
+            Value::Text(_) => Arc::new(
+                cells
+                    .map(|v| match v {
+                        Some(Value::Text(s)) => Some(s.as_str()),
+                        _ => None,
+                    })
+                    .collect::<StringArray>(),
+            ),
+            // A column without any value, like Cluster when no frame has one
+            Value::Int(_) | Value::Null => Arc::new(
+                cells
+                    .map(|v| match v {
+                        Some(Value::Int(i)) => Some(*i),
+                        _ => None,
+                    })
+                    .collect::<Int64Array>(),
+            ),
+            Value::Float(_) => Arc::new(
+                cells
+                    .map(|v| match v {
+                        Some(Value::Float(f)) => Some(*f),
+                        _ => None,
+                    })
+                    .collect::<Float64Array>(),
+            ),
+            Value::Bool(_) => Arc::new(
+                cells
+                    .map(|v| match v {
+                        Some(Value::Bool(b)) => Some(*b),
+                        _ => None,
+                    })
+                    .collect::<BooleanArray>(),
+            ),
+        };
+        (name, column)
+    });
+
+    Ok(RecordBatch::try_from_iter(columns)?)
+}
+
+fn separator(sep_char: char) -> Result<u8> {
+    if !sep_char.is_ascii() {
+        return Err(anyhow!("the CSV separator must be an ASCII character"));
+    }
+
+    Ok(sep_char as u8)
+}
+
+/// Stand-in for the HDF5 writer in builds without the `hdf5` feature
+#[cfg(not(feature = "hdf5"))]
+pub struct Hdf5Writer(std::convert::Infallible);
+
+#[cfg(not(feature = "hdf5"))]
+impl Hdf5Writer {
+    pub fn create(_path: &Path, _info: RunInfo) -> Result<Self> {
+        Err(anyhow!(
+            "HDF5 support is not compiled in; rebuild with `--features hdf5`"
+        ))
+    }
+}
+
+#[cfg(not(feature = "hdf5"))]
+impl ResultWriter for Hdf5Writer {
+    fn write(&mut self, _record: &Record) -> Result<()> {
+        match self.0 {}
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self.0 {}
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        match self.0 {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two frames with cells of every type, the first without a cluster, for
+    /// the writers to read back.
+    pub(super) fn records() -> Vec<Record> {
+        let first = Record {
+            input: "a.npz".to_string(),
+            run: Some("a.npz".to_string()),
+            frame_number: 0,
+            cluster: Some(None),
+            time: Some(0.5),
+            fractal_dimension: 1.5,
+            occupied_voxels: Some(8),
+            percolates: Some([true, false, false]),
+            ..Default::default()
+        };
+        let second = Record {
+            frame_number: 1,
+            cluster: Some(Some(1)),
+            time: Some(1.0),
+            fractal_dimension: 2.25,
+            ..first.clone()
+        };
+        vec![first, second]
+    }
+
+    pub(super) fn info() -> RunInfo {
+        RunInfo::new(&["a.npz"], &serde_json::json!({ "method": "zbox" })).unwrap()
+    }
+
+    /// A file name in the temporary directory that no other test uses.
+    pub(super) fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("output-{}-{name}", std::process::id()))
+    }
+
+    /// Writes `records()` with `writer` and finishes it.
+    pub(super) fn write_records(mut writer: Box<dyn ResultWriter>) {
+        for record in records() {
+            writer.write(&record).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn record_batch_fills_missing_cells_with_nulls() {
+        let timed = Record {
+            frame_number: 0,
+            time: Some(0.5),
+            ..Default::default()
+        };
+        let untimed = Record {
+            frame_number: 1,
+            ..Default::default()
+        };
+        let batch = record_batch(&[untimed, timed]).unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        let time = batch.column_by_name("Time").unwrap();
+        assert_eq!(time.null_count(), 1);
+        assert!(time.is_null(0));
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::basic::{Compression, ZstdLevel};
+use ::parquet::file::metadata::KeyValue;
+use ::parquet::file::properties::WriterProperties;
+use anyhow::Result;
+
+use super::{record_batch, Record, ResultWriter, RunInfo};
+
+/// Key of the run metadata (as JSON) in the Parquet footer
+const METADATA_KEY: &str = "aggregate-fractal-dim";
+
+/// A Parquet table with one typed column per result. Parquet files are
+/// written in one go, so the records are kept until the run finishes.
+pub struct ParquetWriter {
+    path: PathBuf,
+    info: RunInfo,
+    records: Vec<Record>,
+}
+
+impl ParquetWriter {
+    pub fn create(path: &Path, info: RunInfo) -> Result<Self> {
+        // Fail early rather than after the whole run
+        File::create(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            info,
+            records: Vec::new(),
+        })
+    }
+}
+
+impl ResultWriter for ParquetWriter {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let batch = record_batch(&self.records)?;
+        let metadata = KeyValue::new(METADATA_KEY.to_string(), serde_json::to_string(&self.info)?);
+        let props = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .set_key_value_metadata(Some(vec![metadata]))
+            .build();
+        let mut wtr = ArrowWriter::try_new(File::create(&self.path)?, batch.schema(), Some(props))?;
+        wtr.write(&batch)?;
+        wtr.close()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use arrow_array::{Array, BooleanArray, Float64Array, Int64Array, StringArray};
+
+    use super::super::tests::{info, temp_path, write_records};
+    use super::*;
+
+    #[test]
+    fn tables_read_back() {
+        let path = temp_path("read.parquet");
+        write_records(Box::new(ParquetWriter::create(&path, info()).unwrap()));
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let metadata = builder
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .cloned();
+        let batch = builder.build().unwrap().next().unwrap().unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata[0].key, METADATA_KEY);
+        assert!(metadata[0]
+            .value
+            .as_ref()
+            .unwrap()
+            .contains("\"method\":\"zbox\""));
+        assert_eq!(batch.num_rows(), 2);
+        let column = |name| batch.column_by_name(name).unwrap().as_any();
+        let run = column("Run").downcast_ref::<StringArray>().unwrap();
+        assert_eq!(run.value(1), "a.npz");
+        let cluster = column("Cluster").downcast_ref::<Int64Array>().unwrap();
+        assert!(cluster.is_null(0));
+        assert_eq!(cluster.value(1), 1);
+        let dimension = column("FractalDimension")
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(dimension.values(), &[1.5, 2.25]);
+        let percolates = column("PercolatesX")
+            .downcast_ref::<BooleanArray>()
+            .unwrap();
+        assert!(percolates.value(0));
+    }
+}
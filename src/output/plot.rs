@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use super::Record;
+
+const SIZE: (u32, u32) = (800, 500);
+
+/// (frame number, fractal dimension) points of one line
+type Points = Vec<(usize, f64)>;
+
+#[derive(Clone, Copy)]
+enum ImageFormat {
+    Svg,
+    Png,
+}
+
+/// A plot of the fractal dimension against the frame number, with one line
+/// per input and dataset. Points are gathered as results come in and drawn
+/// when the run finishes.
+pub struct Plot {
+    path: PathBuf,
+    format: ImageFormat,
+    /// Input, dataset and points of each line, in order of appearance
+    series: Vec<(String, Option<String>, Points)>,
+}
+
+impl Plot {
+    /// Checks that `path` names an SVG or PNG image.
+    pub fn new(path: &Path) -> Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let format = match ext.as_deref() {
+            Some("svg") => ImageFormat::Svg,
+            Some("png") => ImageFormat::Png,
+            _ => {
+                return Err(anyhow!(
+                    "cannot plot to {}; use an .svg or .png file",
+                    path.display()
+                ))
+            }
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            series: Vec::new(),
+        })
+    }
+
+    pub fn add(&mut self, record: &Record) {
+        let point = (record.frame_number, record.fractal_dimension);
+        let line = self
+            .series
+            .iter_mut()
+            .find(|(input, dataset, _)| *input == record.input && *dataset == record.dataset);
+        match line {
+            Some((_, _, points)) => points.push(point),
+            None => self
+                .series
+                .push((record.input.clone(), record.dataset.clone(), vec![point])),
+        }
+    }
+
+    /// Draws the plot. Lines are labelled by input only if there are several.
+    pub fn save(self) -> Result<()> {
+        let several_inputs = self
+            .series
+            .iter()
+            .any(|(input, _, _)| *input != self.series[0].0);
+        let series: Vec<_> = self
+            .series
+            .into_iter()
+            .map(|(input, dataset, points)| {
+                let label = match dataset {
+                    Some(dataset) if several_inputs => format!("{input}: {dataset}"),
+                    Some(dataset) => dataset,
+                    None if several_inputs => input,
+                    None => String::new(),
+                };
+                (label, points)
+            })
+            .collect();
+
+        match self.format {
+            ImageFormat::Svg => draw(
+                SVGBackend::new(&self.path, SIZE).into_drawing_area(),
+                &series,
+            ),
+            ImageFormat::Png => draw(
+                BitMapBackend::new(&self.path, SIZE).into_drawing_area(),
+                &series,
+            ),
+        }
+    }
+}
+
+fn draw<DB: DrawingBackend>(root: DrawingArea<DB, Shift>, series: &[(String, Points)]) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    let points = series.iter().flat_map(|(_, points)| points);
+    let last_frame = points.clone().map(|p| p.0).max().unwrap_or(0);
+    let (low, high) = points
+        .map(|p| p.1)
+        .filter(|d| d.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), d| {
+            (lo.min(d), hi.max(d))
+        });
+    let (low, high) = if low <= high { (low, high) } else { (0.0, 3.0) };
+    let margin = ((high - low) * 0.05).max(0.01);
+
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root)
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0..last_frame.max(1), low - margin..high + margin)?;
+    chart
+        .configure_mesh()
+        .x_desc("Frame number")
+        .y_desc("Fractal dimension")
+        .draw()?;
+
+    for (i, (label, points)) in series.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        let line = chart.draw_series(LineSeries::new(
+            points.iter().copied(),
+            color.stroke_width(2),
+        ))?;
+        if !label.is_empty() {
+            line.label(label).legend(move |(x, y)| {
+                PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2))
+            });
+        }
+        chart.draw_series(points.iter().map(|&p| Circle::new(p, 3, color.filled())))?;
+    }
+    if series.iter().any(|(label, _)| !label.is_empty()) {
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+    root.present()?;
+
+    Ok(())
+}
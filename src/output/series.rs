@@ -0,0 +1,389 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{Record, ResultWriter};
+
+/// Columns computed over the frames of a series rather than frame by frame
+#[derive(Clone, Debug, Default)]
+pub struct SeriesOptions {
+    /// Frames on either side of a frame that its dD/dt is fitted over
+    pub rate_stencil: Option<usize>,
+    pub smoothing: Option<Smoothing>,
+}
+
+impl SeriesOptions {
+    pub fn is_empty(&self) -> bool {
+        self.rate_stencil.is_none() && self.smoothing.is_none()
+    }
+
+    /// Later frames of its series a frame has to wait for: half of the
+    /// widest centred window.
+    fn lookahead(&self) -> usize {
+        let half_window = self.smoothing.map_or(0, |s| s.stencil().0);
+        self.rate_stencil.unwrap_or(0).max(half_window)
+    }
+}
+
+/// How the dimension series is smoothed, over a window of an odd number of
+/// frames centred on each frame
+#[derive(Clone, Copy, Debug, Serialize)]
+pub enum Smoothing {
+    #[serde(rename = "window")]
+    MovingAverage(usize),
+    /// Local quadratic fit
+    #[serde(rename = "savgol")]
+    SavitzkyGolay(usize),
+}
+
+impl Smoothing {
+    /// Parses `window=N` or `savgol=N`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (kind, frames) = s
+            .split_once('=')
+            .ok_or_else(|| format!("{s:?}: expected window=N or savgol=N"))?;
+        let frames: usize = frames
+            .trim()
+            .parse()
+            .map_err(|e| format!("{frames:?}: {e}"))?;
+        if frames.is_multiple_of(2) {
+            return Err(format!(
+                "the smoothing window has to be an odd number of frames, not {frames}"
+            ));
+        }
+        match kind.trim() {
+            "window" => Ok(Self::MovingAverage(frames)),
+            "savgol" => Ok(Self::SavitzkyGolay(frames)),
+            other => Err(format!("unknown smoothing {other:?}; use window or savgol")),
+        }
+    }
+
+    /// Frames on either side of a frame, and the degree of the local fit
+    fn stencil(self) -> (usize, usize) {
+        match self {
+            Self::MovingAverage(frames) => (frames / 2, 0),
+            Self::SavitzkyGolay(frames) => (frames / 2, 2),
+        }
+    }
+}
+
+/// Fills in the columns that depend on the neighbouring frames of every
+/// input, dataset and cluster, passing each record on, in the order they came
+/// in, as soon as the later frames its windows reach have been written. Only
+/// the points of those windows are kept.
+pub struct SeriesWriter {
+    inner: Box<dyn ResultWriter>,
+    options: SeriesOptions,
+    series: Vec<Series>,
+    /// Records not passed on yet, with their series and their position in it
+    pending: VecDeque<(Record, usize, usize)>,
+}
+
+/// The recent frames of one input, dataset and cluster, in frame order
+struct Series {
+    input: String,
+    dataset: Option<String>,
+    cluster: Option<Option<usize>>,
+    /// `(time, dimension)` of the frames from position `first` on
+    points: VecDeque<(f64, f64)>,
+    first: usize,
+    /// Frames written so far
+    len: usize,
+    /// Whether no more frames will come
+    done: bool,
+}
+
+impl SeriesWriter {
+    pub fn new(inner: Box<dyn ResultWriter>, options: SeriesOptions) -> Self {
+        Self {
+            inner,
+            options,
+            series: Vec::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Passes on the records at the front whose windows are complete.
+    fn pass_on_complete(&mut self) -> Result<()> {
+        let lookahead = self.options.lookahead();
+        while let Some((_, s, k)) = self.pending.front() {
+            let (s, k) = (*s, *k);
+            let series = &mut self.series[s];
+            if !series.done && series.len - k <= lookahead {
+                break;
+            }
+            let (mut record, _, _) = self.pending.pop_front().unwrap();
+            // Against the time of the frames where known, else the frame number
+            let points = series.points.make_contiguous();
+            let window = |half: usize| {
+                let start = k.saturating_sub(half) - series.first;
+                let end = k.saturating_add(half + 1).min(series.len) - series.first;
+                &points[start..end]
+            };
+            if let Some(stencil) = self.options.rate_stencil {
+                record.dimension_rate = Some(slope(window(stencil)));
+            }
+            if let Some(smoothing) = self.options.smoothing {
+                let (half, degree) = smoothing.stencil();
+                let x = points[k - series.first].0;
+                record.smoothed_dimension = Some(local_fit(window(half), x, degree));
+            }
+            // The next frame of the series looks back no further
+            let keep_from = (k + 1).saturating_sub(lookahead).max(series.first);
+            series.points.drain(..keep_from - series.first);
+            series.first = keep_from;
+
+            self.inner.write(&record)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ResultWriter for SeriesWriter {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        // Inputs are analysed one after the other, so the series of the
+        // earlier ones are complete
+        for series in &mut self.series {
+            series.done |= series.input != record.input;
+        }
+        let found = self.series.iter().position(|s| {
+            s.input == record.input && s.dataset == record.dataset && s.cluster == record.cluster
+        });
+        let s = found.unwrap_or_else(|| {
+            self.series.push(Series {
+                input: record.input.clone(),
+                dataset: record.dataset.clone(),
+                cluster: record.cluster,
+                points: VecDeque::new(),
+                first: 0,
+                len: 0,
+                done: false,
+            });
+            self.series.len() - 1
+        });
+        let series = &mut self.series[s];
+        series
+            .points
+            .push_back((record.time_or_frame(), record.fractal_dimension));
+        self.pending.push_back((record.clone(), s, series.len));
+        series.len += 1;
+
+        self.pass_on_complete()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        for series in &mut self.series {
+            series.done = true;
+        }
+        self.pass_on_complete()?;
+
+        self.inner.finish()
+    }
+}
+
+/// Value at `x` of the least-squares polynomial of `degree` through the
+/// points, of a lower degree where there are too few of them; NaN for none.
+fn local_fit(points: &[(f64, f64)], x: f64, degree: usize) -> f64 {
+    let n = degree.min(points.len().saturating_sub(1)) + 1;
+    // Normal equations of the polynomial in powers of the distance to x
+    let mut a = vec![vec![0.0; n + 1]; n];
+    for &(px, py) in points {
+        let dx = px - x;
+        for (row, line) in a.iter_mut().enumerate() {
+            for (col, cell) in line[..n].iter_mut().enumerate() {
+                *cell += dx.powi((row + col) as i32);
+            }
+            line[n] += py * dx.powi(row as i32);
+        }
+    }
+    // Gaussian elimination with partial pivoting
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+            .unwrap();
+        a.swap(col, pivot);
+        if a[col][col] == 0.0 {
+            return f64::NAN;
+        }
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_line = &upper[col];
+        for line in lower {
+            let factor = line[col] / pivot_line[col];
+            for (cell, &p) in line[col..].iter_mut().zip(&pivot_line[col..]) {
+                *cell -= factor * p;
+            }
+        }
+    }
+    let mut coefficients = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|k| a[row][k] * coefficients[k]).sum();
+        coefficients[row] = (a[row][n] - known) / a[row][row];
+    }
+
+    // The constant term is the value at x
+    coefficients.first().copied().unwrap_or(f64::NAN)
+}
+
+/// Least-squares slope of the points; NaN for fewer than two distinct
+/// abscissae.
+fn slope(points: &[(f64, f64)]) -> f64 {
+    fit_line(points).0
+}
+
+/// Least-squares slope of the points and its standard error, NaN for fewer
+/// than three points.
+pub(super) fn fit_line(points: &[(f64, f64)]) -> (f64, f64) {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for &(x, y) in points {
+        sxy += (x - mean_x) * (y - mean_y);
+        sxx += (x - mean_x).powi(2);
+    }
+    if sxx == 0.0 {
+        return (f64::NAN, f64::NAN);
+    }
+    let slope = sxy / sxx;
+    let residuals: f64 = points
+        .iter()
+        .map(|&(x, y)| (y - mean_y - slope * (x - mean_x)).powi(2))
+        .sum();
+
+    (slope, (residuals / (n - 2.0) / sxx).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    /// Collects the records passed on to it
+    struct Collect(Rc<RefCell<Vec<Record>>>);
+
+    impl ResultWriter for Collect {
+        fn write(&mut self, record: &Record) -> Result<()> {
+            self.0.borrow_mut().push(record.clone());
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn finish(self: Box<Self>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn frame(input: &str, frame_number: usize, fractal_dimension: f64) -> Record {
+        Record {
+            input: input.to_string(),
+            frame_number,
+            fractal_dimension,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rates_are_written_once_their_stencil_is_complete() {
+        let written = Rc::new(RefCell::new(Vec::new()));
+        let options = SeriesOptions {
+            rate_stencil: Some(1),
+            smoothing: None,
+        };
+        let mut wtr = Box::new(SeriesWriter::new(
+            Box::new(Collect(written.clone())),
+            options,
+        ));
+        wtr.write(&frame("a", 0, 1.0)).unwrap();
+        assert_eq!(written.borrow().len(), 0);
+        wtr.write(&frame("a", 1, 2.0)).unwrap();
+        assert_eq!(written.borrow().len(), 1);
+        wtr.write(&frame("a", 2, 4.0)).unwrap();
+        assert_eq!(written.borrow().len(), 2);
+        // A new input completes the series of the last one
+        wtr.write(&frame("b", 0, 1.0)).unwrap();
+        assert_eq!(written.borrow().len(), 3);
+        wtr.finish().unwrap();
+
+        let rates: Vec<_> = written
+            .borrow()
+            .iter()
+            .map(|r| r.dimension_rate.unwrap())
+            .collect();
+        assert_eq!(rates[..3], [1.0, 1.5, 2.0]);
+        // A single frame has no slope
+        assert!(rates[3].is_nan());
+    }
+
+    #[test]
+    fn smoothing_waits_for_half_a_window() {
+        let written = Rc::new(RefCell::new(Vec::new()));
+        let options = SeriesOptions {
+            rate_stencil: None,
+            smoothing: Some(Smoothing::MovingAverage(5)),
+        };
+        let mut wtr = Box::new(SeriesWriter::new(
+            Box::new(Collect(written.clone())),
+            options,
+        ));
+        for (k, d) in [1.0, 2.0, 3.0, 4.0, 5.0, 6.0].into_iter().enumerate() {
+            wtr.write(&frame("a", k, d)).unwrap();
+            assert_eq!(written.borrow().len(), k.saturating_sub(1));
+            // Only the frames the next window reaches back to are kept
+            assert!(wtr.series[0].points.len() <= 5);
+        }
+        wtr.finish().unwrap();
+
+        let smoothed: Vec<_> = written
+            .borrow()
+            .iter()
+            .map(|r| r.smoothed_dimension.unwrap())
+            .collect();
+        assert_eq!(smoothed, [2.0, 2.5, 3.0, 4.0, 4.5, 5.0]);
+    }
+
+    #[test]
+    fn moving_average_is_cut_short_at_the_ends() {
+        let points = [(0.0, 1.0), (1.0, 2.0), (2.0, 6.0)];
+        assert_eq!(local_fit(&points, 1.0, 0), 3.0);
+        assert_eq!(local_fit(&points[..2], 0.0, 0), 1.5);
+    }
+
+    #[test]
+    fn savitzky_golay_keeps_a_parabola() {
+        let points: Vec<_> = (0..5).map(|x| (x as f64, (x * x) as f64)).collect();
+        for x in 0..5 {
+            let fitted = local_fit(&points, x as f64, 2);
+            assert!((fitted - (x * x) as f64).abs() < 1e-9);
+        }
+        // Two points take a line, one a constant
+        assert!((local_fit(&points[1..3], 1.5, 2) - 2.5).abs() < 1e-9);
+        assert_eq!(local_fit(&points[2..3], 2.0, 2), 4.0);
+        assert!(local_fit(&[], 0.0, 2).is_nan());
+    }
+
+    #[test]
+    fn smoothing_option_parses() {
+        assert!(matches!(
+            Smoothing::parse("savgol=5"),
+            Ok(Smoothing::SavitzkyGolay(5))
+        ));
+        assert!(matches!(
+            Smoothing::parse("window = 3"),
+            Ok(Smoothing::MovingAverage(3))
+        ));
+        assert!(Smoothing::parse("window=4").is_err());
+        assert!(Smoothing::parse("median=3").is_err());
+        assert!(Smoothing::parse("savgol").is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use anyhow::Result;
+use rusqlite::types::ToSqlOutput;
+use rusqlite::{params_from_iter, Connection, ToSql};
+
+use super::{Record, ResultWriter, Value};
+
+const TABLE: &str = "results";
+
+/// Rows in the `results` table of an SQLite database, keyed by input file,
+/// dataset, frame and cluster (0 for the whole frame, or a frame without
+/// clusters). Existing databases are added to; a frame that is
+/// analysed again replaces its earlier row, and result columns that the
+/// table lacks are added to it.
+pub struct SqliteWriter {
+    db: Connection,
+    has_table: bool,
+    in_transaction: bool,
+}
+
+impl SqliteWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            db: Connection::open(path)?,
+            has_table: false,
+            in_transaction: false,
+        })
+    }
+
+    /// Creates the table, or adds the columns it is missing.
+    fn prepare_table(&mut self, columns: &[(String, Value)]) -> Result<()> {
+        self.db.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {TABLE} (
+                Input TEXT NOT NULL,
+                Dataset TEXT NOT NULL DEFAULT '',
+                FrameNumber INTEGER NOT NULL,
+                Cluster INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (Input, Dataset, FrameNumber, Cluster)
+            )"
+        ))?;
+        let existing = self
+            .db
+            .prepare(&format!("SELECT name FROM pragma_table_info('{TABLE}')"))?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (name, value) in columns {
+            if existing.iter().any(|c| c == name) {
+                continue;
+            }
+            let ty = match value {
+                Value::Text(_) => "TEXT",
+                Value::Int(_) => "INTEGER",
+                Value::Float(_) => "REAL",
+                Value::Bool(_) => "BOOLEAN",
+                Value::Null => continue,
+            };
+            self.db
+                .execute_batch(&format!("ALTER TABLE {TABLE} ADD COLUMN \"{name}\" {ty}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        match self {
+            Value::Text(s) => s.to_sql(),
+            Value::Int(i) => i.to_sql(),
+            Value::Float(f) => f.to_sql(),
+            Value::Bool(b) => b.to_sql(),
+            Value::Null => Ok(ToSqlOutput::from(rusqlite::types::Null)),
+        }
+    }
+}
+
+impl ResultWriter for SqliteWriter {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        let mut columns = vec![("Input".to_string(), Value::Text(record.input.clone()))];
+        columns.extend(
+            record
+                .columns()
+                .into_iter()
+                // Cells without a value keep the column default
+                .filter(|(name, value)| {
+                    !matches!(name.as_str(), "Run" | "Input") && *value != Value::Null
+                }),
+        );
+        if !self.has_table {
+            self.prepare_table(&columns)?;
+            self.has_table = true;
+        }
+        if !self.in_transaction {
+            self.db.execute_batch("BEGIN")?;
+            self.in_transaction = true;
+        }
+
+        // Names such as D-2 or D0.5 need quoting
+        let names: Vec<_> = columns
+            .iter()
+            .map(|(name, _)| format!("\"{name}\""))
+            .collect();
+        let placeholders = vec!["?"; names.len()].join(", ");
+        let sql = format!(
+            "INSERT OR REPLACE INTO {TABLE} ({}) VALUES ({placeholders})",
+            names.join(", ")
+        );
+        self.db
+            .prepare_cached(&sql)?
+            .execute(params_from_iter(columns.iter().map(|(_, value)| value)))?;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.in_transaction {
+            self.db.execute_batch("COMMIT")?;
+            self.in_transaction = false;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{temp_path, write_records};
+    use super::*;
+
+    #[test]
+    fn tables_read_back() {
+        let path = temp_path("read.sqlite");
+        write_records(Box::new(SqliteWriter::create(&path).unwrap()));
+        let db = Connection::open(&path).unwrap();
+        let rows: Vec<(String, i64, i64, f64, bool)> = db
+            .prepare(&format!(
+                "SELECT Input, FrameNumber, Cluster, FractalDimension, PercolatesX FROM {TABLE}"
+            ))
+            .unwrap()
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        drop(db);
+        std::fs::remove_file(path).unwrap();
+
+        // A frame without clusters keeps the default cluster of 0
+        assert_eq!(
+            rows,
+            [
+                ("a.npz".to_string(), 0, 0, 1.5, true),
+                ("a.npz".to_string(), 1, 1, 2.25, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn frames_analysed_again_replace_their_rows() {
+        let path = temp_path("replace.sqlite");
+        write_records(Box::new(SqliteWriter::create(&path).unwrap()));
+        write_records(Box::new(SqliteWriter::create(&path).unwrap()));
+        let db = Connection::open(&path).unwrap();
+        let count: i64 = db
+            .query_row(&format!("SELECT COUNT(*) FROM {TABLE}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        drop(db);
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(count, 2);
+    }
+}
@@ -0,0 +1,193 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+use super::series::fit_line;
+use super::{Record, RunInfo};
+
+#[derive(Clone, Copy)]
+enum SummaryFormat {
+    Yaml,
+    Toml,
+}
+
+/// A small YAML or TOML report of the run for workflow engines: the final
+/// dimension of every input and dataset and its mean over the last frames,
+/// with the runtime and the parameters of the run.
+pub struct Summary {
+    path: PathBuf,
+    format: SummaryFormat,
+    /// Number of trailing frames the mean dimension is taken over
+    last_frames: usize,
+    /// First and last frame the growth exponent is fitted over, else all
+    growth_frames: Option<[usize; 2]>,
+    /// Every input and dataset, in order of appearance
+    series: Vec<Series>,
+}
+
+/// The frames of one input and dataset seen so far
+struct Series {
+    input: String,
+    dataset: Option<String>,
+    dimensions: Vec<f64>,
+    /// First frame that percolates along some axis, once percolation is
+    /// checked
+    first_percolation_frame: Option<Option<usize>>,
+    /// Logarithms of the time and the mass of the frames the growth exponent
+    /// is fitted on
+    growth: Vec<(f64, f64)>,
+}
+
+#[derive(Serialize)]
+struct SeriesSummary {
+    input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dataset: Option<String>,
+    frames: usize,
+    final_dimension: f64,
+    mean_dimension: f64,
+    /// Null if the aggregate never percolates; left out if it was not checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_percolation_frame: Option<Option<usize>>,
+    /// Exponent beta of the mass growth M ~ t^beta, and its standard error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    growth_exponent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    growth_exponent_stderr: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    tool: &'static str,
+    version: &'static str,
+    runtime_seconds: f64,
+    /// Number of trailing frames `mean_dimension` is taken over
+    mean_over_last_frames: usize,
+    inputs: Vec<String>,
+    series: Vec<SeriesSummary>,
+    parameters: serde_json::Value,
+}
+
+impl Summary {
+    /// Checks that `path` names a YAML or TOML file.
+    pub fn new(path: &Path, last_frames: usize, growth_frames: Option<[usize; 2]>) -> Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let format = match ext.as_deref() {
+            Some("yaml" | "yml") => SummaryFormat::Yaml,
+            Some("toml") => SummaryFormat::Toml,
+            _ => {
+                return Err(anyhow!(
+                    "cannot write a summary to {}; use a .yaml or .toml file",
+                    path.display()
+                ))
+            }
+        };
+        if last_frames == 0 {
+            return Err(anyhow!("the summary needs at least one frame to average"));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            last_frames,
+            growth_frames,
+            series: Vec::new(),
+        })
+    }
+
+    /// Adds the result of a frame with `mass` occupied voxels.
+    pub fn add(&mut self, record: &Record, mass: usize) {
+        let position = self
+            .series
+            .iter()
+            .position(|s| s.input == record.input && s.dataset == record.dataset);
+        let series = match position {
+            Some(i) => &mut self.series[i],
+            None => {
+                self.series.push(Series {
+                    input: record.input.clone(),
+                    dataset: record.dataset.clone(),
+                    dimensions: Vec::new(),
+                    first_percolation_frame: None,
+                    growth: Vec::new(),
+                });
+                self.series.last_mut().unwrap()
+            }
+        };
+        series.dimensions.push(record.fractal_dimension);
+        // Against the time where known, else the frame number; frames at time
+        // 0 or without mass have no logarithm
+        let t = record.time_or_frame();
+        let in_range = self
+            .growth_frames
+            .is_none_or(|[first, last]| (first..=last).contains(&record.frame_number));
+        if in_range && t > 0.0 && mass > 0 {
+            series.growth.push((t.ln(), (mass as f64).ln()));
+        }
+        if let Some(percolates) = record.percolates {
+            let first = series.first_percolation_frame.get_or_insert(None);
+            if first.is_none() && percolates.contains(&true) {
+                *first = Some(record.frame_number);
+            }
+        }
+    }
+
+    pub fn save(self, info: RunInfo, runtime: Duration) -> Result<()> {
+        let series = self
+            .series
+            .into_iter()
+            .map(|series| {
+                let dimensions = &series.dimensions;
+                let growth = (series.growth.len() >= 2).then(|| fit_line(&series.growth));
+                let last = &dimensions[dimensions.len().saturating_sub(self.last_frames)..];
+                SeriesSummary {
+                    frames: dimensions.len(),
+                    final_dimension: *dimensions.last().unwrap(),
+                    mean_dimension: last.iter().sum::<f64>() / last.len() as f64,
+                    input: series.input,
+                    dataset: series.dataset,
+                    first_percolation_frame: series.first_percolation_frame,
+                    growth_exponent: growth.map(|g| g.0),
+                    growth_exponent_stderr: growth.map(|g| g.1),
+                }
+            })
+            .collect();
+        let report = Report {
+            tool: info.tool,
+            version: info.version,
+            runtime_seconds: runtime.as_secs_f64(),
+            mean_over_last_frames: self.last_frames,
+            inputs: info.inputs,
+            series,
+            parameters: info.parameters,
+        };
+
+        let text = match self.format {
+            SummaryFormat::Yaml => serde_yaml::to_string(&report)?,
+            SummaryFormat::Toml => {
+                // TOML has no null, so options that were not given are left out
+                let mut report = serde_json::to_value(&report)?;
+                drop_nulls(&mut report);
+                toml::to_string(&report)?
+            }
+        };
+
+        Ok(std::fs::write(&self.path, text)?)
+    }
+}
+
+fn drop_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(drop_nulls);
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(drop_nulls),
+        _ => {}
+    }
+}
@@ -0,0 +1,156 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use rust_xlsxwriter::{Format, Workbook, Worksheet};
+
+use super::{Record, ResultWriter, RunInfo, Value};
+
+/// An Excel workbook with a `Results` sheet holding the per-frame table and
+/// a `Parameters` sheet describing the run. The workbook is written when the
+/// run finishes.
+pub struct XlsxWriter {
+    path: PathBuf,
+    info: RunInfo,
+    records: Vec<Record>,
+}
+
+impl XlsxWriter {
+    pub fn create(path: &Path, info: RunInfo) -> Result<Self> {
+        // Fail early rather than after the whole run
+        File::create(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            info,
+            records: Vec::new(),
+        })
+    }
+}
+
+/// Writes a bold header row and keeps it in view when scrolling.
+fn write_header(sheet: &mut Worksheet, names: &[&str]) -> Result<()> {
+    let bold = Format::new().set_bold();
+    for (col, name) in names.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *name, &bold)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+
+    Ok(())
+}
+
+impl ResultWriter for XlsxWriter {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let mut workbook = Workbook::new();
+
+        let results = workbook.add_worksheet().set_name("Results")?;
+        for (row, record) in self.records.iter().enumerate() {
+            let columns = record.columns();
+            if row == 0 {
+                let names: Vec<_> = columns.iter().map(|(name, _)| name.as_str()).collect();
+                write_header(results, &names)?;
+            }
+            let row = row as u32 + 1;
+            for (col, (_, value)) in columns.into_iter().enumerate() {
+                let col = col as u16;
+                match value {
+                    Value::Text(s) => results.write_string(row, col, s)?,
+                    Value::Int(i) => results.write_number(row, col, i as f64)?,
+                    Value::Float(f) => results.write_number(row, col, f)?,
+                    Value::Bool(b) => results.write_boolean(row, col, b)?,
+                    Value::Null => continue,
+                };
+            }
+        }
+        results.autofit();
+
+        let parameters = workbook.add_worksheet().set_name("Parameters")?;
+        write_header(parameters, &["Name", "Value"])?;
+        let mut entries = vec![
+            ("tool".to_string(), self.info.tool.to_string()),
+            ("version".to_string(), self.info.version.to_string()),
+            ("inputs".to_string(), self.info.inputs.join("\n")),
+        ];
+        if let serde_json::Value::Object(options) = &self.info.parameters {
+            entries.extend(options.iter().map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::Null => String::new(),
+                    serde_json::Value::String(s) => s.clone(),
+                    value => value.to_string(),
+                };
+                (name.clone(), value)
+            }));
+        }
+        for (row, (name, value)) in entries.into_iter().enumerate() {
+            let row = row as u32 + 1;
+            parameters.write_string(row, 0, name)?;
+            parameters.write_string(row, 1, value)?;
+        }
+        parameters.autofit();
+
+        workbook.save(&self.path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Seek, SeekFrom};
+
+    use flate2::read::DeflateDecoder;
+
+    use super::super::tests::{info, temp_path, write_records};
+    use super::*;
+
+    /// The text of the deflated member `name` of the workbook.
+    fn member(path: &Path, name: &str) -> String {
+        let mut zip = zip::ZipArchive::new(File::open(path).unwrap()).unwrap();
+        let member = zip.by_name(name).unwrap();
+        let (start, size) = (member.data_start(), member.compressed_size());
+        drop(member);
+        let mut file = File::open(path).unwrap();
+        file.seek(SeekFrom::Start(start)).unwrap();
+        let mut text = String::new();
+        DeflateDecoder::new(file.take(size))
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[test]
+    fn workbooks_read_back() {
+        let path = temp_path("read.xlsx");
+        write_records(Box::new(XlsxWriter::create(&path, info()).unwrap()));
+        let results = member(&path, "xl/worksheets/sheet1.xml");
+        let strings = member(&path, "xl/sharedStrings.xml");
+        let workbook = member(&path, "xl/workbook.xml");
+        std::fs::remove_file(path).unwrap();
+
+        assert!(workbook.contains("name=\"Results\"") && workbook.contains("name=\"Parameters\""));
+        for name in [
+            "Run",
+            "FrameNumber",
+            "Cluster",
+            "FractalDimension",
+            "a.npz",
+            "zbox",
+        ] {
+            assert!(strings.contains(&format!(">{name}<")), "{name}");
+        }
+        // The frame without a cluster leaves its cell out
+        assert!(!results.contains("r=\"C2\""));
+        assert!(results.contains("<c r=\"C3\"><v>1</v></c>"));
+        assert!(results.contains("<v>2.25</v>"));
+        assert!(results.contains("<c r=\"G2\" t=\"b\"><v>1</v></c>"));
+    }
+}